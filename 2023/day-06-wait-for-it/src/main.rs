@@ -1,97 +1,163 @@
-use std::fs;
+use num_bigint::BigUint;
+use std::{fs, ops::RangeInclusive};
 
 #[derive(Debug, PartialEq, Eq)]
 struct Race {
-    time: u64,
-    distance: u64,
+    time: BigUint,
+    distance: BigUint,
 }
 
 impl Race {
-    fn winning_ways(&self) -> u64 {
-        let time = self.time as i128;
-        let distance = self.distance as i128;
-        let determinant: i128 = time * time - 4 * distance;
-
-        //because the equation is
-        // x^2 - time * x + distance <= 0
-        // if the determinant is less than 0,
-        // it means that the quadratic function
-        // doesn't intersect the x-axis
-        // meaning that the function value
-        // is always greater than 0
-        if determinant < 0 {
-            return 0;
+    fn new(time: impl Into<BigUint>, distance: impl Into<BigUint>) -> Self {
+        Race {
+            time: time.into(),
+            distance: distance.into(),
         }
+    }
+
+    fn beats_record(&self, hold: &BigUint) -> bool {
+        hold <= &self.time && hold * (&self.time - hold) > self.distance
+    }
 
-        let lower_bound = (((self.time as f64) - (determinant as f64).sqrt()) / 2.0).ceil() as u64;
-        let upper_bound = (((self.time as f64) + (determinant as f64).sqrt()) / 2.0).floor() as u64;
+    /// The hold times that beat the record.
+    ///
+    /// Holding for `x` goes `x * (time - x)`, so the winners are the integers
+    /// strictly between the roots of `x^2 - time * x + distance`. Rounding the
+    /// square root of the discriminant down puts `(time - root) / 2` within
+    /// one of the first winner, and the last winner mirrors it around
+    /// `time / 2`.
+    fn winning_interval(&self) -> Option<RangeInclusive<BigUint>> {
+        let square = &self.time * &self.time;
+        let four_distance = &self.distance * 4u32;
+
+        // Even holding for half the time doesn't go further than the record
+        if square <= four_distance {
+            return None;
+        }
+
+        let root = (square - four_distance).sqrt();
+        let mut first = (&self.time - root) / 2u32;
+        while !self.beats_record(&first) {
+            first += 1u32;
+            if &first * 2u32 > self.time {
+                return None;
+            }
+        }
+        while first > BigUint::ZERO && self.beats_record(&(&first - 1u32)) {
+            first -= 1u32;
+        }
 
-        return upper_bound - lower_bound + 1;
+        let last = &self.time - &first;
+        Some(first..=last)
     }
+
+    fn winning_ways(&self) -> BigUint {
+        match self.winning_interval() {
+            Some(interval) => interval.end() - interval.start() + 1u32,
+            None => BigUint::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ParseRaceError {
+    MissingLine(&'static str),
+    InvalidNumber(String),
+    /// The lines hold different numbers of races.
+    RaceCount {
+        times: usize,
+        distances: usize,
+    },
+    /// A time and its record don't end in the same column, so the sheet
+    /// can't be read as columns.
+    Misaligned {
+        race: usize,
+        time_end: usize,
+        distance_end: usize,
+    },
 }
 
-#[derive(Debug)]
-struct ParseRaceError;
+/// The numbers after `prefix`, each with the column just past its last digit.
+fn numbers<'a>(
+    line: Option<&'a str>,
+    prefix: &'static str,
+) -> Result<Vec<(usize, &'a str)>, ParseRaceError> {
+    let rest = line
+        .and_then(|line| line.strip_prefix(prefix))
+        .ok_or(ParseRaceError::MissingLine(prefix))?;
 
-fn parse_races(s: &str) -> Result<Vec<Race>, ParseRaceError> {
+    let mut numbers = Vec::new();
+    let mut start = None;
+    for (i, ch) in rest.char_indices().chain([(rest.len(), ' ')]) {
+        match (ch.is_ascii_whitespace(), start) {
+            (true, Some(begin)) => {
+                numbers.push((prefix.len() + i, &rest[begin..i]));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+
+    Ok(numbers)
+}
+
+/// The times and records column by column, checked to be right-aligned in
+/// pairs like on the puzzle's sheet.
+fn columns(s: &str) -> Result<Vec<(&str, &str)>, ParseRaceError> {
     let mut lines = s.lines();
-    let times = lines
-        .next()
-        .ok_or(ParseRaceError)?
-        .strip_prefix("Time:")
-        .ok_or(ParseRaceError)?
-        .trim_start()
-        .split_ascii_whitespace()
-        .map(|time| time.parse::<u64>().map_err(|_| ParseRaceError));
-
-    let distances = lines
-        .next()
-        .ok_or(ParseRaceError)?
-        .strip_prefix("Distance:")
-        .ok_or(ParseRaceError)?
-        .trim_start()
-        .split_ascii_whitespace()
-        .map(|time| time.parse::<u64>().map_err(|_| ParseRaceError));
-
-    return times
-        .zip(distances)
-        .map(|(time, distance)| {
-            if time.is_err() || distance.is_err() {
-                return Err(ParseRaceError);
+    let times = numbers(lines.next(), "Time:")?;
+    let distances = numbers(lines.next(), "Distance:")?;
+
+    if times.len() != distances.len() {
+        return Err(ParseRaceError::RaceCount {
+            times: times.len(),
+            distances: distances.len(),
+        });
+    }
+
+    times
+        .iter()
+        .zip(&distances)
+        .enumerate()
+        .map(|(race, ((time_end, time), (distance_end, distance)))| {
+            if time_end != distance_end {
+                return Err(ParseRaceError::Misaligned {
+                    race,
+                    time_end: *time_end,
+                    distance_end: *distance_end,
+                });
             }
 
-            return Ok(Race {
-                time: time.unwrap(),
-                distance: distance.unwrap(),
-            });
+            Ok((*time, *distance))
         })
-        .collect();
+        .collect()
 }
 
+fn parse_number(s: &str) -> Result<BigUint, ParseRaceError> {
+    if !s.chars().all(|ch| ch.is_ascii_digit()) {
+        return Err(ParseRaceError::InvalidNumber(s.to_string()));
+    }
+
+    s.parse()
+        .map_err(|_| ParseRaceError::InvalidNumber(s.to_string()))
+}
+
+fn parse_races(s: &str) -> Result<Vec<Race>, ParseRaceError> {
+    columns(s)?
+        .iter()
+        .map(|(time, distance)| Ok(Race::new(parse_number(time)?, parse_number(distance)?)))
+        .collect()
+}
+
+/// Reads the sheet with the bad kerning fixed: all the digits on a line make
+/// up one number, however long.
 fn parse_race(s: &str) -> Result<Race, ParseRaceError> {
-    let mut lines = s.lines();
-    let times = lines
-        .next()
-        .ok_or(ParseRaceError)?
-        .strip_prefix("Time:")
-        .ok_or(ParseRaceError)?
-        .trim_start()
-        .split_ascii_whitespace()
-        .fold(String::from(""), |fstr, time| fstr + time);
-
-    let distances = lines
-        .next()
-        .ok_or(ParseRaceError)?
-        .strip_prefix("Distance:")
-        .ok_or(ParseRaceError)?
-        .trim_start()
-        .split_ascii_whitespace()
-        .fold(String::from(""), |fstr, distance| fstr + distance);
-
-    let time = times.parse::<u64>().map_err(|_| ParseRaceError)?;
-    let distance = distances.parse::<u64>().map_err(|_| ParseRaceError)?;
-
-    return Ok(Race { time, distance });
+    let columns = columns(s)?;
+    let time: String = columns.iter().map(|(time, _)| *time).collect();
+    let distance: String = columns.iter().map(|(_, distance)| *distance).collect();
+
+    Ok(Race::new(parse_number(&time)?, parse_number(&distance)?))
 }
 
 fn main() {
@@ -100,16 +166,30 @@ fn main() {
 
     println!(
         "{}",
-        races.iter().map(|race| race.winning_ways()).sum::<u64>()
+        races
+            .iter()
+            .map(|race| race.winning_ways())
+            .product::<BigUint>()
     );
 
     let race = parse_race(&input).unwrap();
-    println!("{}", race.winning_ways());
+    match race.winning_interval() {
+        Some(interval) => println!(
+            "{} (holding {} to {})",
+            race.winning_ways(),
+            interval.start(),
+            interval.end()
+        ),
+        None => println!("0"),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{parse_race, parse_races, Race};
+    use num_bigint::BigUint;
+    use proptest::prelude::*;
+
+    use crate::{parse_race, parse_races, ParseRaceError, Race};
 
     const RACES: &str = "Time:      7  15   30
 Distance:  9  40  200";
@@ -124,49 +204,60 @@ Distance:  9  40  200";
         assert_eq!(
             races,
             vec![
-                Race {
-                    time: 7,
-                    distance: 9
-                },
-                Race {
-                    time: 15,
-                    distance: 40
-                },
-                Race {
-                    time: 30,
-                    distance: 200
-                }
+                Race::new(7u32, 9u32),
+                Race::new(15u32, 40u32),
+                Race::new(30u32, 200u32)
             ]
         );
     }
 
     #[test]
-    fn test_winning_ways() {
+    fn test_parse_errors() {
         assert_eq!(
-            Race {
-                time: 7,
-                distance: 9
-            }
-            .winning_ways(),
-            4
+            parse_races("Time:      7  15   30\nDistance:  9  40 200"),
+            Err(ParseRaceError::Misaligned {
+                race: 2,
+                time_end: 21,
+                distance_end: 20
+            })
         );
-
         assert_eq!(
-            Race {
-                time: 6,
-                distance: 9
-            }
-            .winning_ways(),
-            1
+            parse_races("Time:      7  15\nDistance:  9  40  200"),
+            Err(ParseRaceError::RaceCount {
+                times: 2,
+                distances: 3
+            })
+        );
+        assert_eq!(
+            parse_races("Time:      7  1x\nDistance:  9  40"),
+            Err(ParseRaceError::InvalidNumber("1x".to_string()))
         );
+        assert_eq!(
+            parse_race("Time:      7  15   30"),
+            Err(ParseRaceError::MissingLine("Distance:"))
+        );
+    }
+
+    #[test]
+    fn test_winning_ways() {
+        assert_eq!(Race::new(7u32, 9u32).winning_ways(), BigUint::from(4u32));
+        assert_eq!(Race::new(6u32, 9u32).winning_ways(), BigUint::from(0u32));
+        assert_eq!(Race::new(6u32, 8u32).winning_ways(), BigUint::from(1u32));
+        assert_eq!(Race::new(6u32, 10u32).winning_ways(), BigUint::from(0u32));
+        assert_eq!(Race::new(30u32, 200u32).winning_ways(), BigUint::from(9u32));
+    }
 
+    #[test]
+    fn test_winning_interval() {
+        let interval = Race::new(30u32, 200u32).winning_interval().unwrap();
+
+        assert_eq!(*interval.start(), BigUint::from(11u32));
+        assert_eq!(*interval.end(), BigUint::from(19u32));
+        assert_eq!(Race::new(6u32, 9u32).winning_interval(), None);
         assert_eq!(
-            Race {
-                time: 6,
-                distance: 10
-            }
-            .winning_ways(),
-            0
+            Race::new(0u32, 0u32).winning_interval(),
+            None,
+            "A race without time can't be won"
         );
     }
 
@@ -178,12 +269,47 @@ Distance:  9  40  200";
 
         let race = race.unwrap();
 
-        assert_eq!(
-            race,
-            Race {
-                time: 71530,
-                distance: 940200
-            }
-        );
+        assert_eq!(race, Race::new(71530u32, 940200u32));
+        assert_eq!(race.winning_ways(), BigUint::from(71503u32));
+    }
+
+    #[test]
+    fn test_huge_race() {
+        // Beating a record of a * b in a + b milliseconds means holding for
+        // more than a and less than b.
+        let a: BigUint = "123456789012345678901234567890".parse().unwrap();
+        let b: BigUint = "987654321098765432109876543210".parse().unwrap();
+        let race = Race::new(&a + &b, &a * &b);
+        let interval = race.winning_interval().unwrap();
+
+        assert_eq!(*interval.start(), &a + 1u32);
+        assert_eq!(*interval.end(), &b - 1u32);
+    }
+
+    proptest! {
+        #[test]
+        fn matches_brute_force(time in 0u64..300, distance in 0u64..25000) {
+            let winners: Vec<u64> = (0..=time)
+                .filter(|hold| hold * (time - hold) > distance)
+                .collect();
+            let race = Race::new(time, distance);
+
+            prop_assert_eq!(race.winning_ways(), BigUint::from(winners.len()));
+            prop_assert_eq!(
+                race.winning_interval(),
+                winners
+                    .first()
+                    .map(|first| BigUint::from(*first)..=BigUint::from(*winners.last().unwrap()))
+            );
+        }
+
+        #[test]
+        fn finds_the_roots(a in any::<u128>(), gap in 0u128..1000) {
+            let b = BigUint::from(a) + BigUint::from(gap);
+            let a = BigUint::from(a);
+            let race = Race::new(&a + &b, &a * &b);
+
+            prop_assert_eq!(race.winning_ways(), BigUint::from(gap.saturating_sub(1)));
+        }
     }
 }
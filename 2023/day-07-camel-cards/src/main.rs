@@ -1,189 +1,118 @@
 use std::{
     cmp::Ordering,
-    collections::HashMap,
     fs::File,
     io::{BufRead, BufReader},
-    str::FromStr,
 };
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-enum Strength {
-    HighCard,
-    OnePair,
-    TwoPair,
-    ThreeKind,
-    FullHouse,
-    FourKind,
-    FiveKind,
+/// A hand category and the groups of equal cards it needs, largest first.
+/// Cards outside those groups don't matter, so `[3]` is any three of a kind.
+#[derive(Debug, Clone)]
+struct Category {
+    name: &'static str,
+    groups: Vec<usize>,
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-enum Card {
-    Jocker,
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    Queen,
-    King,
-    Ace,
+impl Category {
+    fn new(name: &'static str, groups: &[usize]) -> Self {
+        Self {
+            name,
+            groups: groups.to_vec(),
+        }
+    }
 }
 
-#[derive(Debug)]
-struct CardParsingError;
-
-impl FromStr for Card {
-    type Err = CardParsingError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        return match s {
-            "A" => Ok(Card::Ace),
-            "K" => Ok(Card::King),
-            "Q" => Ok(Card::Queen),
-            "J" => Ok(Card::Jocker),
-            "T" => Ok(Card::Ten),
-            "9" => Ok(Card::Nine),
-            "8" => Ok(Card::Eight),
-            "7" => Ok(Card::Seven),
-            "6" => Ok(Card::Six),
-            "5" => Ok(Card::Five),
-            "4" => Ok(Card::Four),
-            "3" => Ok(Card::Three),
-            "2" => Ok(Card::Two),
-            _ => Err(CardParsingError),
-        };
-    }
+/// Everything that changes between variants of the game.
+#[derive(Debug, Clone)]
+struct Rules {
+    /// Card labels from weakest to strongest.
+    order: Vec<char>,
+    /// Labels that count as whatever card makes the hand strongest.
+    wildcards: Vec<char>,
+    /// Categories from weakest to strongest.
+    categories: Vec<Category>,
+    hand_size: usize,
 }
 
 #[derive(Debug, PartialEq, Eq)]
-struct Hand {
-    cards: [Card; 5],
-    bid: u64,
-    strength: Strength,
-}
+struct HandParsingError;
 
-impl Hand {
-    fn get_strength(cards: &[Card; 5]) -> Strength {
-        let counts = cards.iter().fold(HashMap::new(), |mut map, card| {
-            *map.entry(card).or_insert(0) += 1;
-            return map;
-        });
-
-        return match counts.len() {
-            1 => Strength::FiveKind,
-            2 => {
-                if counts.contains_key(&Card::Jocker) {
-                    return Strength::FiveKind;
-                }
-
-                let max_counts = *counts.iter().max_by(|a, b| a.1.cmp(b.1)).unwrap().1;
-                if max_counts == 4 {
-                    return Strength::FourKind;
-                }
-
-                return Strength::FullHouse;
-            },
-            3 => {
-                let max_counts = *counts.iter().max_by(|a, b| a.1.cmp(b.1)).unwrap().1;
-                match  *counts.get(&Card::Jocker).unwrap_or(&0) {
-                    0 => {
-                        if max_counts == 3 {
-                            return Strength::ThreeKind;
-                        }
-        
-                        return Strength::TwoPair;
-                    },
-                    1 => {
-                        if max_counts == 3 {
-                            return Strength::FourKind;
-                        }
-        
-                        return Strength::FullHouse;
-                    },
-                    2| 3 => return Strength::FourKind,
-                    _ => panic!("In a hand of 5 cards, where there are three different kinds of cards, it is not possible to have more than 3 jockers")
-                }
-
-                
-            },
-            4 => {
-                match  *counts.get(&Card::Jocker).unwrap_or(&0) {
-                    0 => return Strength::OnePair,
-                    1 | 2 => return Strength::ThreeKind,
-                    _ => panic!("In a hand of 5 cards, where there are four different kinds of cards, it is not possible to have more than 2 jockers")
-                }
-            },
-            5 => {
-                if counts.contains_key(&Card::Jocker) {
-                    return Strength::OnePair;
-                }
-
-                return Strength::HighCard
-            },
-            _ => panic!("An array of 5 elements converted to a map must have 1 to 5 keys!"),
-        };
+impl Rules {
+    fn standard() -> Self {
+        Self {
+            order: "23456789TJQKA".chars().collect(),
+            wildcards: Vec::new(),
+            categories: vec![
+                Category::new("high card", &[]),
+                Category::new("one pair", &[2]),
+                Category::new("two pair", &[2, 2]),
+                Category::new("three of a kind", &[3]),
+                Category::new("full house", &[3, 2]),
+                Category::new("four of a kind", &[4]),
+                Category::new("five of a kind", &[5]),
+            ],
+            hand_size: 5,
+        }
     }
 
-    fn new(cards: [Card; 5], bid: u64) -> Hand {
-        let strength = Hand::get_strength(&cards);
-
-        return Hand {
-            cards,
-            bid,
-            strength,
-        };
+    /// Part two: `J` is the weakest card but stands in for any other.
+    fn jokers() -> Self {
+        Self {
+            order: "J23456789TQKA".chars().collect(),
+            wildcards: vec!['J'],
+            ..Self::standard()
+        }
     }
-}
 
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        return match self.strength.cmp(&other.strength) {
-            Ordering::Less => Some(Ordering::Less),
-            Ordering::Equal => {
-                for (card_self, card_other) in self.cards.iter().zip(&other.cards) {
-                    match card_self.cmp(card_other) {
-                        Ordering::Less => return Some(Ordering::Less),
-                        Ordering::Equal => (),
-                        Ordering::Greater => return Some(Ordering::Greater),
-                    }
-                }
-                Some(Ordering::Equal)
+    /// The index of the strongest category the cards make. Wildcards fill
+    /// the groups of a category largest to largest, which never needs more of
+    /// them than any other way of matching groups up.
+    fn strength(&self, cards: &[char]) -> usize {
+        let mut counts: Vec<usize> = Vec::new();
+        let mut labels: Vec<char> = Vec::new();
+        let mut wildcards = 0;
+        for card in cards {
+            if self.wildcards.contains(card) {
+                wildcards += 1;
+            } else if let Some(i) = labels.iter().position(|label| label == card) {
+                counts[i] += 1;
+            } else {
+                labels.push(*card);
+                counts.push(1);
             }
-            Ordering::Greater => Some(Ordering::Greater),
-        };
-    }
-}
+        }
+        counts.sort_by(|a, b| b.cmp(a));
 
-impl Ord for Hand {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        return self.partial_cmp(other).unwrap();
+        self.categories
+            .iter()
+            .rposition(|category| {
+                let missing: usize = category
+                    .groups
+                    .iter()
+                    .enumerate()
+                    .map(|(i, group)| group.saturating_sub(counts.get(i).copied().unwrap_or(0)))
+                    .sum();
+                missing <= wildcards
+            })
+            .expect("Some category has to match every hand")
     }
-}
 
-#[derive(Debug)]
-struct HandParsingError;
-
-impl FromStr for Hand {
-    type Err = HandParsingError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn parse_hand(&self, s: &str) -> Result<Hand, HandParsingError> {
         let mut split = s.split(" ");
 
-        let cards: Vec<Card> = split
-            .next()
-            .ok_or(HandParsingError)?
-            .chars()
+        let cards: Vec<char> = split.next().ok_or(HandParsingError)?.chars().collect();
+        if cards.len() != self.hand_size {
+            return Err(HandParsingError);
+        }
+
+        let ranks = cards
+            .iter()
             .map(|card| {
-                card.to_string()
-                    .parse::<Card>()
-                    .map_err(|_| HandParsingError)
+                self.order
+                    .iter()
+                    .position(|label| label == card)
+                    .ok_or(HandParsingError)
             })
-            .collect::<Result<Vec<Card>, HandParsingError>>()?;
+            .collect::<Result<Vec<usize>, HandParsingError>>()?;
 
         let bid: u64 = split
             .next()
@@ -191,299 +120,234 @@ impl FromStr for Hand {
             .parse()
             .map_err(|_| HandParsingError)?;
 
-        let cards = cards.try_into().map_err(|_| HandParsingError)?;
+        Ok(Hand {
+            strength: self.strength(&cards),
+            ranks,
+            bid,
+        })
+    }
+}
+
+/// A hand as seen by one set of rules. Hands compare by category first and
+/// then card by card; the bid plays no part.
+#[derive(Debug)]
+struct Hand {
+    strength: usize,
+    ranks: Vec<usize>,
+    bid: u64,
+}
+
+impl Hand {
+    fn key(&self) -> (usize, &[usize]) {
+        (self.strength, &self.ranks)
+    }
+}
+
+impl PartialEq for Hand {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
 
-        return Ok(Hand::new(cards, bid));
+impl Eq for Hand {}
+
+impl PartialOrd for Hand {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
+impl Ord for Hand {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+fn winnings(lines: &[String], rules: &Rules) -> Result<u64, HandParsingError> {
+    let mut hands = lines
+        .iter()
+        .map(|line| rules.parse_hand(line))
+        .collect::<Result<Vec<Hand>, HandParsingError>>()?;
+
+    hands.sort();
+
+    Ok(hands
+        .iter()
+        .enumerate()
+        .map(|(i, hand)| (i as u64 + 1) * hand.bid)
+        .sum())
+}
+
 fn main() {
     let file = File::open("input").unwrap();
-
-    let mut hands: Vec<Hand> = BufReader::new(file)
+    let lines: Vec<String> = BufReader::new(file)
         .lines()
-        .map(|line| line.unwrap().parse::<Hand>().unwrap())
+        .map(|line| line.unwrap())
         .collect();
 
-    hands.sort();
+    for rules in [Rules::standard(), Rules::jokers()] {
+        println!("{}", winnings(&lines, &rules).unwrap());
 
-    println!(
-        "{}",
-        hands
-            .iter()
-            .enumerate()
-            .map(|(i, hand)| (i as u64 + 1) * hand.bid)
-            .sum::<u64>()
-    );
+        let mut counts = vec![0; rules.categories.len()];
+        for line in &lines {
+            counts[rules.parse_hand(line).unwrap().strength] += 1;
+        }
+        for (category, count) in rules.categories.iter().zip(counts).rev() {
+            println!("  {}: {}", category.name, count);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::cmp::Ordering;
+    use std::fs;
 
-    use crate::{Card, Hand, Strength};
+    use crate::{winnings, Category, HandParsingError, Rules};
+
+    fn category(rules: &Rules, cards: &str) -> &'static str {
+        let hand = rules.parse_hand(&format!("{} 0", cards)).unwrap();
+        rules.categories[hand.strength].name
+    }
+
+    /// Compares the cards with different bids, which must not matter.
+    fn compare(rules: &Rules, a: &str, b: &str) -> Ordering {
+        let a = rules.parse_hand(&format!("{} 100", a)).unwrap();
+        let b = rules.parse_hand(&format!("{} 150", b)).unwrap();
+        a.cmp(&b)
+    }
 
     #[test]
     fn test_get_strength() {
-        assert_eq!(
-            Hand::get_strength(&[Card::Ace, Card::Ace, Card::Ace, Card::Ace, Card::Ace]),
-            Strength::FiveKind
-        );
-        assert_eq!(
-            Hand::get_strength(&[Card::Nine, Card::Nine, Card::Eight, Card::Nine, Card::Nine]),
-            Strength::FourKind
-        );
-        assert_eq!(
-            Hand::get_strength(&[Card::Two, Card::Three, Card::Three, Card::Two, Card::Three]),
-            Strength::FullHouse
-        );
-        assert_eq!(
-            Hand::get_strength(&[Card::Ten, Card::Nine, Card::Ten, Card::Eight, Card::Ten]),
-            Strength::ThreeKind
-        );
-        assert_eq!(
-            Hand::get_strength(&[Card::Two, Card::Three, Card::Jocker, Card::Three, Card::Two]),
-            Strength::TwoPair
-        );
-        assert_eq!(
-            Hand::get_strength(&[Card::Queen, Card::Two, Card::Three, Card::Queen, Card::King]),
-            Strength::OnePair
-        );
-        assert_eq!(
-            Hand::get_strength(&[Card::Six, Card::Three, Card::Four, Card::Five, Card::King]),
-            Strength::HighCard
-        );
+        let rules = Rules::standard();
+        assert_eq!(category(&rules, "AAAAA"), "five of a kind");
+        assert_eq!(category(&rules, "99899"), "four of a kind");
+        assert_eq!(category(&rules, "23323"), "full house");
+        assert_eq!(category(&rules, "T9T8T"), "three of a kind");
+        assert_eq!(category(&rules, "23J32"), "two pair");
+        assert_eq!(category(&rules, "Q23QK"), "one pair");
+        assert_eq!(category(&rules, "6345K"), "high card");
     }
 
     #[test]
     fn test_get_strength_jocker() {
+        let rules = Rules::jokers();
+
         //1 card type
-        assert_eq!(
-            Hand::get_strength(&[Card::Jocker, Card::Jocker, Card::Jocker, Card::Jocker, Card::Jocker]),
-            Strength::FiveKind
-        );
+        assert_eq!(category(&rules, "JJJJJ"), "five of a kind");
 
         //2 card types
-        assert_eq!(
-            Hand::get_strength(&[Card::Jocker, Card::Jocker, Card::Nine, Card::Jocker, Card::Jocker]),
-            Strength::FiveKind
-        );
-        assert_eq!(
-            Hand::get_strength(&[Card::Nine, Card::Nine, Card::Jocker, Card::Nine, Card::Nine]),
-            Strength::FiveKind
-        );
+        assert_eq!(category(&rules, "JJ9JJ"), "five of a kind");
+        assert_eq!(category(&rules, "99J99"), "five of a kind");
 
         //3 card types
-        assert_eq!(
-            Hand::get_strength(&[Card::Jocker, Card::Jocker, Card::Jocker, Card::Two, Card::Three]),
-            Strength::FourKind
-        );
-        assert_eq!(
-            Hand::get_strength(&[Card::Two, Card::Jocker, Card::Jocker, Card::Two, Card::Three]),
-            Strength::FourKind
-        );
-        assert_eq!(
-            Hand::get_strength(&[Card::Two, Card::Three, Card::Jocker, Card::Three, Card::Three]),
-            Strength::FourKind
-        );
-        assert_eq!(
-            Hand::get_strength(&[Card::Two, Card::Three, Card::Jocker, Card::Two, Card::Three]),
-            Strength::FullHouse
-        );
+        assert_eq!(category(&rules, "JJJ23"), "four of a kind");
+        assert_eq!(category(&rules, "2JJ23"), "four of a kind");
+        assert_eq!(category(&rules, "23J33"), "four of a kind");
+        assert_eq!(category(&rules, "23J23"), "full house");
 
         //4 card types
-        assert_eq!(
-            Hand::get_strength(&[Card::Ten, Card::Nine, Card::Jocker, Card::Eight, Card::Ten]),
-            Strength::ThreeKind
-        );
-        assert_eq!(
-            Hand::get_strength(&[Card::Two, Card::Three, Card::Jocker, Card::Jocker, Card::Four]),
-            Strength::ThreeKind
-        );
+        assert_eq!(category(&rules, "T9J8T"), "three of a kind");
+        assert_eq!(category(&rules, "23JJ4"), "three of a kind");
 
         //5 card types
-        assert_eq!(
-            Hand::get_strength(&[Card::Queen, Card::Two, Card::Three, Card::Jocker, Card::King]),
-            Strength::OnePair
-        );
+        assert_eq!(category(&rules, "Q23JK"), "one pair");
     }
 
     #[test]
     fn test_hand_order() {
+        let rules = Rules::jokers();
+
         // A five kind of the same card is equal
-        assert_eq!(
-            Hand::new([Card::Ace, Card::Ace, Card::Ace, Card::Ace, Card::Ace], 100).cmp(
-                &Hand::new([Card::Ace, Card::Ace, Card::Ace, Card::Ace, Card::Ace], 150)
-            ),
-            Ordering::Equal
-        );
+        assert_eq!(compare(&rules, "AAAAA", "AAAAA"), Ordering::Equal);
 
         // A hand with a five kind of a greater first card beats a hand of a five kind
         // of smaller first card
-        assert_eq!(
-            Hand::new([Card::Ace, Card::Ace, Card::Ace, Card::Ace, Card::Ace], 100).cmp(
-                &Hand::new(
-                    [Card::Nine, Card::Nine, Card::Nine, Card::Nine, Card::Nine],
-                    150
-                )
-            ),
-            Ordering::Greater
-        );
+        assert_eq!(compare(&rules, "AAAAA", "99999"), Ordering::Greater);
 
         //A five kind beats a four kind
-        assert_eq!(
-            Hand::new(
-                [Card::Nine, Card::Nine, Card::Nine, Card::Nine, Card::Nine],
-                100
-            )
-            .cmp(&Hand::new(
-                [Card::Nine, Card::Nine, Card::Eight, Card::Nine, Card::Nine],
-                150
-            )),
-            Ordering::Greater
-        );
+        assert_eq!(compare(&rules, "99999", "99899"), Ordering::Greater);
 
         // A full house loses to a four kind
-        assert_eq!(
-            Hand::new(
-                [Card::Nine, Card::Nine, Card::Eight, Card::Eight, Card::Nine],
-                100
-            )
-            .cmp(&Hand::new(
-                [Card::Nine, Card::Nine, Card::Eight, Card::Nine, Card::Nine],
-                150
-            )),
-            Ordering::Less
-        );
+        assert_eq!(compare(&rules, "99889", "99899"), Ordering::Less);
 
         // A three kind loses to a full house
-        assert_eq!(
-            Hand::new(
-                [Card::Nine, Card::Nine, Card::Eight, Card::Seven, Card::Jocker],
-                100
-            )
-            .cmp(&Hand::new(
-                [Card::Nine, Card::Nine, Card::Eight, Card::Eight, Card::Jocker],
-                150
-            )),
-            Ordering::Less
-        );
+        assert_eq!(compare(&rules, "9987J", "9988J"), Ordering::Less);
 
         // A three kind beats a two pair
-        assert_eq!(
-            Hand::new(
-                [Card::Nine, Card::Nine, Card::Eight, Card::Seven, Card::Nine],
-                100
-            )
-            .cmp(&Hand::new(
-                [Card::Ace, Card::Two, Card::Eight, Card::Eight, Card::Ace],
-                150
-            )),
-            Ordering::Greater
-        );
+        assert_eq!(compare(&rules, "99879", "A288A"), Ordering::Greater);
 
         // A one pair loses to a two pair
-        assert_eq!(
-            Hand::new(
-                [Card::Nine, Card::Four, Card::Eight, Card::Ten, Card::Jocker],
-                100
-            )
-            .cmp(&Hand::new(
-                [Card::Ace, Card::Two, Card::Eight, Card::Eight, Card::Ace],
-                150
-            )),
-            Ordering::Less
-        );
+        assert_eq!(compare(&rules, "948TJ", "A288A"), Ordering::Less);
 
         // A one pair beats a high card
-        assert_eq!(
-            Hand::new(
-                [Card::Nine, Card::Seven, Card::Eight, Card::Ten, Card::Jocker],
-                100
-            )
-            .cmp(&Hand::new(
-                [Card::Ace, Card::Two, Card::Eight, Card::Three, Card::King],
-                150
-            )),
-            Ordering::Greater
-        );
+        assert_eq!(compare(&rules, "978TJ", "A283K"), Ordering::Greater);
 
         // A one pair with a higher 5th card beats a high card with a lower 5th card
-        assert_eq!(
-            Hand::new(
-                [Card::Ace, Card::King, Card::Queen, Card::Jocker, Card::Ten],
-                100
-            )
-            .cmp(&Hand::new(
-                [Card::Ace, Card::King, Card::Queen, Card::Jocker, Card::Nine],
-                150
-            )),
-            Ordering::Greater
-        );
+        assert_eq!(compare(&rules, "AKQJT", "AKQJ9"), Ordering::Greater);
 
         //a one pair wins if the fifth car is greater
-        assert_eq!(
-            Hand::new(
-                [Card::Five, Card::Four, Card::Three, Card::Two, Card::Two],
-                100
-            )
-            .cmp(&Hand::new(
-                [Card::Five, Card::Four, Card::Three, Card::Two, Card::Jocker],
-                150
-            )),
-            Ordering::Greater
-        );
+        assert_eq!(compare(&rules, "54322", "5432J"), Ordering::Greater);
 
         // Two hands are equal if they have equal cards in equal orders
-        assert_eq!(
-            Hand::new(
-                [Card::Ace, Card::King, Card::Queen, Card::Jocker, Card::Ten],
-                100
-            )
-            .cmp(&Hand::new(
-                [Card::Ace, Card::King, Card::Queen, Card::Jocker, Card::Ten],
-                150
-            )),
-            Ordering::Equal
-        );
+        assert_eq!(compare(&rules, "AKQJT", "AKQJT"), Ordering::Equal);
     }
 
     #[test]
     fn test_hand_parsing() {
-        let hand = "32T4K 765".parse::<Hand>();
-        assert!(hand.is_ok());
-        assert_eq!(
-            hand.unwrap(),
-            Hand::new(
-                [Card::Three, Card::Two, Card::Ten, Card::Four, Card::King],
-                765
-            )
-        );
-
-        let hand = "75896 134".parse::<Hand>();
-        assert!(hand.is_ok());
-        assert_eq!(
-            hand.unwrap(),
-            Hand::new(
-                [Card::Seven, Card::Five, Card::Eight, Card::Nine, Card::Six],
-                134
-            )
-        );
-
-        let hand = "JAQJA 666".parse::<Hand>();
-        assert!(hand.is_ok());
-        assert_eq!(
-            hand.unwrap(),
-            Hand::new(
-                [
-                    Card::Jocker,
-                    Card::Ace,
-                    Card::Queen,
-                    Card::Jocker,
-                    Card::Ace
-                ],
-                666
-            )
-        );
+        let rules = Rules::standard();
+        let hand = rules.parse_hand("32T4K 765").unwrap();
+        assert_eq!(hand.ranks, vec![1, 0, 8, 2, 11]);
+        assert_eq!(hand.bid, 765);
+
+        let hand = Rules::jokers().parse_hand("JAQJA 666").unwrap();
+        assert_eq!(hand.ranks, vec![0, 12, 10, 0, 12]);
+        assert_eq!(hand.bid, 666);
+
+        assert_eq!(rules.parse_hand("32T4 765"), Err(HandParsingError));
+        assert_eq!(rules.parse_hand("32X4K 765"), Err(HandParsingError));
+        assert_eq!(rules.parse_hand("32T4K"), Err(HandParsingError));
+    }
+
+    #[test]
+    fn test_winnings() {
+        let lines: Vec<String> = fs::read_to_string("test")
+            .unwrap()
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+
+        assert_eq!(winnings(&lines, &Rules::standard()), Ok(6440));
+        assert_eq!(winnings(&lines, &Rules::jokers()), Ok(5905));
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        // Both jokers and stars are wild
+        let rules = Rules {
+            wildcards: vec!['J', '*'],
+            order: "*J23456789TQKA".chars().collect(),
+            ..Rules::standard()
+        };
+        assert_eq!(category(&rules, "*J*J2"), "five of a kind");
+        assert_eq!(category(&rules, "*3J45"), "three of a kind");
+        assert_eq!(compare(&rules, "*2222", "J2222"), Ordering::Less);
+
+        // Six card hands with two more categories on top
+        let mut rules = Rules {
+            hand_size: 6,
+            ..Rules::jokers()
+        };
+        rules
+            .categories
+            .insert(5, Category::new("two triples", &[3, 3]));
+        rules.categories.push(Category::new("six of a kind", &[6]));
+        assert_eq!(category(&rules, "222333"), "two triples");
+        // The joker is worth more on the triple
+        assert_eq!(category(&rules, "22J333"), "four of a kind");
+        assert_eq!(category(&rules, "2JJJJ2"), "six of a kind");
+        assert_eq!(category(&rules, "223344"), "two pair");
+        assert_eq!(category(&rules, "222233"), "four of a kind");
+        assert!(rules.parse_hand("22233 1").is_err());
     }
 }
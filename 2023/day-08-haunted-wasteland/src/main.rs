@@ -1,3 +1,4 @@
+use common::cycle::{find_cycle, Cycle};
 use std::{collections::HashMap, fs, str::FromStr};
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -14,10 +15,10 @@ struct Instructions {
 
 impl Instructions {
     fn new(instructions: Vec<Instruction>) -> Instructions {
-        return Instructions {
+        Instructions {
             list: instructions,
             current: 0,
-        };
+        }
     }
 }
 
@@ -32,7 +33,7 @@ impl Iterator for Instructions {
         let instruction = self.list[self.current];
         self.current += 1;
 
-        return Some(instruction);
+        Some(instruction)
     }
 }
 
@@ -47,14 +48,14 @@ impl FromStr for Instructions {
             .chars()
             .map(|ch| {
                 if ch == 'R' {
-                    return Instruction::Right;
+                    Instruction::Right
+                } else {
+                    Instruction::Left
                 }
-
-                return Instruction::Left;
             })
             .collect();
 
-        return Ok(Instructions::new(instructions));
+        Ok(Instructions::new(instructions))
     }
 }
 
@@ -83,10 +84,10 @@ impl FromStr for Destination {
             .strip_suffix(")")
             .ok_or(ParseDestinationError)?;
 
-        return Ok(Destination {
+        Ok(Destination {
             left: left.to_string(),
             right: right.to_string(),
-        });
+        })
     }
 }
 
@@ -109,47 +110,167 @@ impl Map {
                 break;
             }
 
-            match instruction {
-                Instruction::Left => current = &self.map[current].left,
-                Instruction::Right => current = &self.map[current].right,
-            }
-
+            current = self.next(current, instruction);
             count += 1;
         }
 
-        return count;
+        count
+    }
+
+    fn next(&self, node: &str, instruction: Instruction) -> &str {
+        match instruction {
+            Instruction::Left => &self.map[node].left,
+            Instruction::Right => &self.map[node].right,
+        }
+    }
+
+    /// Follows one ghost from `start`. Its state is the node together with
+    /// the position in the instructions, so it has to come back to an earlier
+    /// state after at most `nodes * instructions` steps.
+    fn ghost<'a>(
+        &'a self,
+        instructions: &[Instruction],
+        start: &'a str,
+        is_end: fn(&str) -> bool,
+    ) -> Ghost {
+        let step = |state: &mut State<'a>| {
+            state.0 = self.next(state.0, instructions[state.1]);
+            state.1 = (state.1 + 1) % instructions.len();
+        };
+
+        let cycle = find_cycle(&(start, 0), step);
+        let mut state = (start, 0);
+        let mut hits = Vec::new();
+        for i in 0..cycle.start + cycle.length {
+            if is_end(state.0) {
+                hits.push(i);
+            }
+            step(&mut state);
+        }
+
+        Ghost { cycle, hits }
     }
 
-    #[allow(dead_code)]
-    fn traverse_parallel(&self, instructions: Instructions) -> usize {
-        let mut current_nodes = self
+    fn starts(&self) -> Vec<&str> {
+        let mut starts: Vec<&str> = self
             .map
             .keys()
-            .filter(|key| key.ends_with("A"))
-            .collect::<Vec<&String>>();
-        let mut count = 0;
+            .filter(|key| key.ends_with('A'))
+            .map(|key| key.as_str())
+            .collect();
+        starts.sort();
 
-        for instruction in instructions {
-            if current_nodes.iter().all(|node| node.ends_with("Z")) {
-                break;
-            }
+        starts
+    }
+}
 
-            current_nodes = match instruction {
-                Instruction::Left => current_nodes
-                    .iter()
-                    .map(|node| &self.map[*node].left)
-                    .collect(),
-                Instruction::Right => current_nodes
-                    .iter()
-                    .map(|node| &self.map[*node].right)
-                    .collect(),
-            };
+type State<'a> = (&'a str, usize);
 
-            count += 1;
+/// The walk of one ghost: `cycle` over its `(node, instruction index)` states
+/// and every step up to the end of the first lap at which it stands on an end
+/// node. Hits before `cycle.start` happen once, the rest come back every lap.
+#[derive(Debug, PartialEq, Eq)]
+struct Ghost {
+    cycle: Cycle,
+    hits: Vec<u64>,
+}
+
+impl Ghost {
+    fn is_at_end(&self, step: u64) -> bool {
+        self.hits.contains(&self.cycle.equivalent_step(step))
+    }
+}
+
+/// The shortcut that would have found the same answer.
+#[derive(Debug, PartialEq, Eq)]
+enum Assumption {
+    /// Every ghost is at an end exactly at the multiples of its lap length, so
+    /// the answer is their LCM.
+    Lcm,
+    /// Every ghost hits one end once per lap and never on the way in, so
+    /// a single congruence per ghost is enough.
+    SingleHit,
+    /// Some ghost hits ends on the way in or several times per lap.
+    General,
+}
+
+impl Assumption {
+    fn of(ghosts: &[Ghost]) -> Self {
+        if ghosts.iter().all(|ghost| {
+            ghost.hits == [ghost.cycle.length] && ghost.cycle.start <= ghost.cycle.length
+        }) {
+            Assumption::Lcm
+        } else if ghosts
+            .iter()
+            .all(|ghost| ghost.hits.len() == 1 && ghost.hits[0] >= ghost.cycle.start)
+        {
+            Assumption::SingleHit
+        } else {
+            Assumption::General
         }
+    }
+}
 
-        return count;
+/// `(g, x, y)` with `a * x + b * y = g = gcd(a, b)`.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - a / b * y)
+    }
+}
+
+/// Merges `x = a1 (mod m1)` and `x = a2 (mod m2)` into a single congruence
+/// modulo their LCM. The moduli don't have to be coprime, but then the
+/// remainders have to agree modulo their GCD.
+fn crt((a1, m1): (i128, i128), (a2, m2): (i128, i128)) -> Option<(i128, i128)> {
+    let (g, p, _) = extended_gcd(m1, m2);
+    if (a2 - a1) % g != 0 {
+        return None;
+    }
+
+    let lcm = m1 / g * m2;
+    let k = ((a2 - a1) / g * p).rem_euclid(m2 / g);
+
+    Some(((a1 + m1 * k).rem_euclid(lcm), lcm))
+}
+
+/// The first step at which every ghost stands on an end node at once, and the
+/// shortcut that would have found it. Before every ghost is in its loop the
+/// steps are simply tried one by one; after that each ghost allows a set of
+/// remainders modulo its lap length and every combination goes through CRT.
+fn ghost_walk(ghosts: &[Ghost]) -> Option<(u64, Assumption)> {
+    let tail = ghosts.iter().map(|ghost| ghost.cycle.start).max()?;
+    let assumption = Assumption::of(ghosts);
+
+    if let Some(step) = (0..tail).find(|step| ghosts.iter().all(|ghost| ghost.is_at_end(*step))) {
+        return Some((step, assumption));
+    }
+
+    let mut congruences = vec![(0, 1)];
+    for ghost in ghosts {
+        let length = ghost.cycle.length as i128;
+        let mut merged: Vec<(i128, i128)> = congruences
+            .iter()
+            .flat_map(|congruence| {
+                ghost
+                    .hits
+                    .iter()
+                    .filter(|hit| **hit >= ghost.cycle.start)
+                    .filter_map(move |hit| crt(*congruence, (*hit as i128 % length, length)))
+            })
+            .collect();
+        merged.sort();
+        merged.dedup();
+        congruences = merged;
     }
+
+    congruences
+        .iter()
+        .map(|(remainder, modulus)| tail as i128 + (remainder - tail as i128).rem_euclid(*modulus))
+        .min()
+        .map(|step| (step as u64, assumption))
 }
 
 #[derive(Debug)]
@@ -171,12 +292,12 @@ impl FromStr for Map {
                 .next()
                 .ok_or(ParseMapError::MapError)?
                 .parse::<Destination>()
-                .map_err(|e| ParseMapError::DestinationError(e))?;
+                .map_err(ParseMapError::DestinationError)?;
 
             map.insert(from.to_string(), to);
         }
 
-        return Ok(Map { map });
+        Ok(Map { map })
     }
 }
 
@@ -188,27 +309,36 @@ fn main() {
     let instructions = split.next().unwrap().parse::<Instructions>().unwrap();
     let map = split.next().unwrap().parse::<Map>().unwrap();
 
-    // println!("{}", map.traverse_parallel(instructions.clone()));
-
-    let current_nodes = map
-        .map
-        .keys()
-        .filter(|key| key.ends_with("A"))
-        .collect::<Vec<&String>>();
-
-    println!("{:?}", current_nodes);
+    if map.map.contains_key("AAA") {
+        println!(
+            "{}",
+            map.traverse(instructions.clone(), "AAA", |node| node == "ZZZ")
+        );
+    }
 
-    let counts = current_nodes
+    let ghosts: Vec<Ghost> = map
+        .starts()
         .iter()
-        .map(|start| map.traverse(instructions.clone(), &start, |node| node.ends_with("Z")))
-        .collect::<Vec<usize>>();
+        .map(|start| map.ghost(&instructions.list, start, |node| node.ends_with('Z')))
+        .collect();
+    for (start, ghost) in map.starts().iter().zip(&ghosts) {
+        println!(
+            "{}: tail {}, loop {}, ends at {:?}",
+            start, ghost.cycle.start, ghost.cycle.length, ghost.hits
+        );
+    }
 
-    println!("{:?}", counts);
+    match ghost_walk(&ghosts) {
+        Some((steps, assumption)) => println!("{} ({:?})", steps, assumption),
+        None => println!("The ghosts never all stand on an end"),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Instruction, Instructions, Map};
+    use common::cycle::Cycle;
+
+    use crate::{crt, ghost_walk, Assumption, Ghost, Instruction, Instructions, Map};
 
     #[test]
     fn test_parsing_instructions() {
@@ -229,7 +359,6 @@ mod tests {
             Instruction::Left,
             Instruction::Right,
         ])
-        .into_iter()
         .zip(vec![0, 1, 2, 3, 4, 5, 6])
         .collect::<Vec<(Instruction, usize)>>();
 
@@ -282,7 +411,7 @@ ZZZ = (ZZZ, ZZZ)";
 
         assert_eq!(
             map.unwrap()
-                .traverse(instructions.unwrap(), &"AAA", |node| node == "ZZZ"),
+                .traverse(instructions.unwrap(), "AAA", |node| node == "ZZZ"),
             2
         );
     }
@@ -296,13 +425,134 @@ ZZZ = (ZZZ, ZZZ)";
 22Z = (22B, 22B)
 XXX = (XXX, XXX)";
     #[test]
-    fn test_map_traverse_parallel() {
-        let map: Result<Map, crate::ParseMapError> = MAP_PARALLEL.parse::<Map>();
-        assert!(map.is_ok());
+    fn test_ghost() {
+        let map = MAP_PARALLEL.parse::<Map>().unwrap();
+        let instructions = "LR".parse::<Instructions>().unwrap();
 
-        let instructions = "LR".parse::<Instructions>();
-        assert!(instructions.is_ok());
+        // 22A 22B 22C 22Z 22B 22C 22Z, back at 22B on the same instruction
+        assert_eq!(
+            map.ghost(&instructions.list, "22A", |node| node.ends_with('Z')),
+            Ghost {
+                cycle: Cycle {
+                    start: 1,
+                    length: 6
+                },
+                hits: vec![3, 6]
+            }
+        );
+        assert_eq!(map.starts(), vec!["11A", "22A"]);
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt((2, 3), (3, 5)), Some((8, 15)));
+        assert_eq!(crt((2, 4), (0, 6)), Some((6, 12)));
+        assert_eq!(crt((1, 4), (0, 6)), None);
+        assert_eq!(crt((0, 1), (3, 7)), Some((3, 7)));
+    }
+
+    fn walk(map: &str, instructions: &str) -> (Vec<Ghost>, Option<(u64, Assumption)>) {
+        let map = map.parse::<Map>().unwrap();
+        let instructions = instructions.parse::<Instructions>().unwrap();
+        let ghosts: Vec<Ghost> = map
+            .starts()
+            .iter()
+            .map(|start| map.ghost(&instructions.list, start, |node| node.ends_with('Z')))
+            .collect();
+        let result = ghost_walk(&ghosts);
 
-        assert_eq!(map.unwrap().traverse_parallel(instructions.unwrap()), 6);
+        (ghosts, result)
+    }
+
+    /// Moves every ghost one step at a time until they all stand on an end.
+    fn brute_force(ghosts: &[Ghost], limit: u64) -> Option<u64> {
+        (0..limit).find(|step| ghosts.iter().all(|ghost| ghost.is_at_end(*step)))
+    }
+
+    #[test]
+    fn test_ghost_walk() {
+        let (_, result) = walk(MAP_PARALLEL, "LR");
+        assert_eq!(result, Some((6, Assumption::General)));
+
+        let (ghosts, result) = walk(
+            "11A = (11B, 11B)
+11B = (11Z, 11Z)
+11Z = (11B, 11B)
+22A = (22B, 22B)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)",
+            "L",
+        );
+        assert_eq!(result, Some((6, Assumption::Lcm)));
+        assert_eq!(brute_force(&ghosts, 100), Some(6));
+    }
+
+    #[test]
+    fn test_ghost_walk_without_lcm() {
+        // Ends at 2, 6, 10, ... and at 1, 4, 7, ...
+        let (ghosts, result) = walk(
+            "11A = (11B, 11B)
+11B = (11Z, 11Z)
+11Z = (11C, 11C)
+11C = (11D, 11D)
+11D = (11B, 11B)
+22A = (22Z, 22Z)
+22Z = (22B, 22B)
+22B = (22C, 22C)
+22C = (22Z, 22Z)",
+            "L",
+        );
+        assert_eq!(result, Some((10, Assumption::SingleHit)));
+        assert_eq!(brute_force(&ghosts, 100), Some(10));
+
+        // The second ghost only passes an end on its way into the loop
+        let (_, result) = walk(
+            "11A = (11Z, 11Z)
+11Z = (11B, 11B)
+11B = (11B, 11B)
+22A = (22Z, 22Z)
+22Z = (22Z, 22Z)",
+            "L",
+        );
+        assert_eq!(result, Some((1, Assumption::General)));
+
+        // Ends at odd steps and at even steps only
+        let (_, result) = walk(
+            "11A = (11Z, 11Z)
+11Z = (11B, 11B)
+11B = (11Z, 11Z)
+22A = (22B, 22B)
+22B = (22Z, 22Z)
+22Z = (22B, 22B)",
+            "L",
+        );
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_ghost_walk_brute_force() {
+        // Every combination of three-way branching over six nodes
+        let names = ["11A", "11B", "11Z", "22A", "22B", "22Z"];
+        for seed in 0..729u32 {
+            let mut map = String::new();
+            for (i, name) in names.iter().enumerate() {
+                let left = names[(i / 3) * 3 + (seed / 3u32.pow(i as u32) % 3) as usize];
+                let right =
+                    names[(i / 3) * 3 + ((seed / 3u32.pow(i as u32) + i as u32) % 3) as usize];
+                map.push_str(&format!("{} = ({}, {})\n", name, left, right));
+            }
+
+            for instructions in ["L", "R", "LR", "LLR", "RRLR"] {
+                let (ghosts, result) = walk(map.trim_end(), instructions);
+                assert_eq!(
+                    result.map(|(steps, _)| steps),
+                    brute_force(&ghosts, 1000),
+                    "{}\n{}",
+                    instructions,
+                    map
+                );
+            }
+        }
     }
 }
@@ -1,30 +1,27 @@
+use common::polynomial::Polynomial;
 use std::{
     fs::File,
     io::{BufRead, BufReader},
 };
 
-fn predict_next(sequence: Vec<i64>) -> (i64, i64) {
-    if sequence.iter().all(|el| *el == 0) {
-        return (0, 0);
-    }
+/// The values before the first reading and after the last one, from the
+/// polynomial the readings follow.
+fn predict_next(sequence: &[i64]) -> (i64, i64) {
+    let polynomial = fit(sequence);
 
-    let mut diffs = Vec::new();
-    for i in 1..sequence.len() {
-        diffs.push(sequence[i] - sequence[i - 1]);
-    }
+    (polynomial.backward(1) as i64, polynomial.forward(1) as i64)
+}
 
-    let next_diff = predict_next(diffs);
+fn fit(sequence: &[i64]) -> Polynomial {
+    let samples: Vec<i128> = sequence.iter().map(|value| *value as i128).collect();
 
-    return (
-        sequence.first().unwrap() - next_diff.0,
-        sequence.last().unwrap() + next_diff.1,
-    );
+    Polynomial::fit(&samples)
 }
 
 fn main() {
     let file = File::open("input").unwrap();
 
-    let sum: (i64, i64) = BufReader::new(file)
+    let sequences: Vec<Vec<i64>> = BufReader::new(file)
         .lines()
         .map(|line| {
             line.unwrap()
@@ -32,22 +29,49 @@ fn main() {
                 .map(|num| num.parse::<i64>().unwrap())
                 .collect::<Vec<i64>>()
         })
+        .collect();
+
+    let sum = sequences
+        .iter()
         .map(|sequence| predict_next(sequence))
         .fold((0, 0), |sums, predicted| {
             (sums.0 + predicted.0, sums.1 + predicted.1)
         });
 
     println!("Previous sum: {}, Next sum: {}", sum.0, sum.1);
+
+    let unconfirmed = sequences
+        .iter()
+        .filter(|sequence| fit(sequence).confirmations() == 0)
+        .count();
+    let max_degree = sequences
+        .iter()
+        .map(|sequence| fit(sequence).degree())
+        .max()
+        .unwrap_or(0);
+    println!(
+        "Highest degree: {}, not polynomial within their length: {}",
+        max_degree, unconfirmed
+    );
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::predict_next;
+    use crate::{fit, predict_next};
 
     #[test]
     fn test_predict_next() {
-        assert_eq!(predict_next(vec![0, 3, 6, 9, 12, 15]), (-3, 18));
-        assert_eq!(predict_next(vec![1, 3, 6, 10, 15, 21]), (0, 28));
-        assert_eq!(predict_next(vec![10, 13, 16, 21, 30, 45]), (5, 68));
+        assert_eq!(predict_next(&[0, 3, 6, 9, 12, 15]), (-3, 18));
+        assert_eq!(predict_next(&[1, 3, 6, 10, 15, 21]), (0, 28));
+        assert_eq!(predict_next(&[10, 13, 16, 21, 30, 45]), (5, 68));
+    }
+
+    #[test]
+    fn test_degree() {
+        assert_eq!(fit(&[0, 3, 6, 9, 12, 15]).degree(), 1);
+        assert_eq!(fit(&[1, 3, 6, 10, 15, 21]).degree(), 2);
+        assert_eq!(fit(&[10, 13, 16, 21, 30, 45]).degree(), 3);
+        assert_eq!(fit(&[10, 13, 16, 21, 30, 45]).forward(3), 146);
+        assert_eq!(fit(&[10, 13, 16, 21, 30, 45]).backward(2), -4);
     }
 }
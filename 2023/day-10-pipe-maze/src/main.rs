@@ -1,12 +1,13 @@
-use std::{collections::VecDeque, fs};
+use common::polygon::{Direction, Point, Polygon};
+use std::{collections::HashSet, env, fs};
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 struct Cell {
     i: usize,
     j: usize,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Pipe {
     Vertical,
     Horizontal,
@@ -17,22 +18,44 @@ enum Pipe {
     Ground,
 }
 
+const PIPES: [Pipe; 6] = [
+    Pipe::Vertical,
+    Pipe::Horizontal,
+    Pipe::NorthEast,
+    Pipe::NorthWest,
+    Pipe::SouthWest,
+    Pipe::SouthEast,
+];
+
 impl Pipe {
-    fn get_next(&self, i: usize, j: usize) -> (Cell, Cell) {
-        return match self {
-            Pipe::Vertical => (Cell { i: i + 1, j }, Cell { i: i - 1, j }),
-            Pipe::Horizontal => (Cell { i, j: j + 1 }, Cell { i, j: j - 1 }),
-            Pipe::NorthEast => (Cell { i: i - 1, j }, Cell { i, j: j + 1 }),
-            Pipe::NorthWest => (Cell { i: i - 1, j }, Cell { i, j: j - 1 }),
-            Pipe::SouthWest => (Cell { i: i + 1, j }, Cell { i, j: j - 1 }),
-            Pipe::SouthEast => (Cell { i: i + 1, j }, Cell { i, j: j + 1 }),
-            Pipe::Ground => (Cell { i, j }, Cell { i, j }),
-        };
+    /// The two sides the pipe connects, or none for ground.
+    fn ends(&self) -> Vec<Direction> {
+        match self {
+            Pipe::Vertical => vec![Direction::Up, Direction::Down],
+            Pipe::Horizontal => vec![Direction::Left, Direction::Right],
+            Pipe::NorthEast => vec![Direction::Up, Direction::Right],
+            Pipe::NorthWest => vec![Direction::Up, Direction::Left],
+            Pipe::SouthWest => vec![Direction::Down, Direction::Left],
+            Pipe::SouthEast => vec![Direction::Down, Direction::Right],
+            Pipe::Ground => vec![],
+        }
+    }
+
+    fn box_drawing(&self) -> char {
+        match self {
+            Pipe::Vertical => '│',
+            Pipe::Horizontal => '─',
+            Pipe::NorthEast => '└',
+            Pipe::NorthWest => '┘',
+            Pipe::SouthWest => '┐',
+            Pipe::SouthEast => '┌',
+            Pipe::Ground => '·',
+        }
     }
 }
 
 fn parse_pipe(ch: char) -> Pipe {
-    return match ch {
+    match ch {
         '|' => Pipe::Vertical,
         '-' => Pipe::Horizontal,
         'L' => Pipe::NorthEast,
@@ -40,179 +63,280 @@ fn parse_pipe(ch: char) -> Pipe {
         '7' => Pipe::SouthWest,
         'F' => Pipe::SouthEast,
         _ => Pipe::Ground,
-    };
+    }
 }
 
-fn get_next_from_start(pipes: &Vec<Vec<Pipe>>, start: &Cell) -> VecDeque<Cell> {
-    let mut q = VecDeque::new();
-    let diffs: [(i32, i32); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
-    for diff in diffs {
-        let i = start.i as i32 + diff.0;
-        let j = start.j as i32 + diff.1;
-        if i >= 0 && i < pipes.len() as i32 && j >= 0 && j < pipes[0].len() as i32 {
-            let i = i as usize;
-            let j = j as usize;
-            let next = pipes[i][j].get_next(i, j);
-            if (next.0.i == start.i && next.0.j == start.j)
-                || (next.1.i == start.i && next.1.j == start.j)
-            {
-                q.push_back(Cell { i, j });
-            }
+struct Maze {
+    pipes: Vec<Vec<Pipe>>,
+    start: Cell,
+}
+
+impl Maze {
+    fn parse(s: &str) -> Self {
+        let mut pipes = Vec::new();
+        let mut start = Cell { i: 0, j: 0 };
+        for (i, line) in s.lines().enumerate() {
+            pipes.push(
+                line.chars()
+                    .enumerate()
+                    .map(|(j, ch)| {
+                        if ch == 'S' {
+                            start.i = i;
+                            start.j = j;
+                        }
+                        parse_pipe(ch)
+                    })
+                    .collect::<Vec<Pipe>>(),
+            );
         }
+
+        Maze { pipes, start }
     }
 
-    return q;
-}
+    fn neighbour(&self, cell: Cell, direction: Direction) -> Option<Cell> {
+        let (i, j) = match direction {
+            Direction::Up => (cell.i.checked_sub(1)?, cell.j),
+            Direction::Right => (cell.i, cell.j + 1),
+            Direction::Down => (cell.i + 1, cell.j),
+            Direction::Left => (cell.i, cell.j.checked_sub(1)?),
+        };
 
-fn determine_start(start: &Cell, next: &VecDeque<Cell>) -> Pipe {
-    if next[0].i.abs_diff(next[1].i) == 2 {
-        return Pipe::Vertical;
+        (i < self.pipes.len() && j < self.pipes[i].len()).then_some(Cell { i, j })
     }
 
-    if next[0].j.abs_diff(next[1].j) == 2 {
-        return Pipe::Horizontal;
-    }
+    /// Follows the pipes out of `start` going `direction` for as long as each
+    /// pipe connects back to the one before it. Returns the cells in walking
+    /// order if that leads back into `start` from its other end.
+    fn walk(&self, start: Cell, start_pipe: Pipe, direction: Direction) -> Option<Vec<Cell>> {
+        let mut cells = vec![start];
+        let mut current = start;
+        let mut direction = direction;
 
-    //this means that the pipe must go from north
-    if start.i as i32 - next[0].i as i32 == 1 {
-        //this means that the pipe must go to west
-        if start.j as i32 - next[1].j as i32 == 1 {
-            return Pipe::NorthWest;
-        } else {
-            //because we already tested north->south
-            // (vertical pipe) and north->west
-            // this is the only one left;
-            return Pipe::NorthEast;
+        loop {
+            let next = self.neighbour(current, direction)?;
+            let coming_from = direction.opposite();
+            if next == start {
+                return start_pipe.ends().contains(&coming_from).then_some(cells);
+            }
+
+            let ends = self.pipes[next.i][next.j].ends();
+            if !ends.contains(&coming_from) {
+                return None;
+            }
+
+            direction = *ends.iter().find(|end| **end != coming_from).unwrap();
+            current = next;
+            cells.push(current);
         }
     }
 
-    //otherwise, the pipe must go to south
-    //this means the pipe must go to west
-    if start.j as i32 - next[1].j as i32 == 1 {
-        return Pipe::SouthWest;
-    }
+    /// The loop through the start as an ordered cycle starting at it. The
+    /// start could be any pipe, so every shape is tried and the longest loop
+    /// wins; pipes around the start that lead nowhere or into another loop
+    /// are skipped. The start tile is replaced by the shape that worked.
+    fn find_loop(&mut self) -> Option<Vec<Cell>> {
+        let (pipe, cells) = PIPES
+            .iter()
+            .filter_map(|pipe| Some((*pipe, self.walk(self.start, *pipe, pipe.ends()[0])?)))
+            .max_by_key(|(_, cells)| cells.len())?;
 
-    return Pipe::SouthEast;
-}
+        self.pipes[self.start.i][self.start.j] = pipe;
 
-fn bfs(pipes: &mut Vec<Vec<Pipe>>, start: &Cell) -> (usize, Vec<Vec<bool>>) {
-    let mut level = 1;
-    let mut q: VecDeque<Cell> = get_next_from_start(&pipes, start);
-    let start_pipe = determine_start(start, &q);
-    pipes[start.i][start.j] = start_pipe;
-    println!("{:?}", pipes[start.i][start.j]);
+        Some(cells)
+    }
 
-    let mut visited = pipes
-        .iter()
-        .map(|pipeline| pipeline.iter().map(|_| false).collect())
-        .collect::<Vec<Vec<bool>>>();
-    visited[start.i][start.j] = true;
-
-    while !q.is_empty() {
-        let mut next_level = VecDeque::new();
-        while !q.is_empty() {
-            let current = q.pop_front().unwrap();
-            visited[current.i][current.j] = true;
-            let next = pipes[current.i][current.j].get_next(current.i, current.j);
-            if !visited[next.0.i][next.0.j] {
-                next_level.push_back(next.0);
-            }
-            if !visited[next.1.i][next.1.j] {
-                next_level.push_back(next.1);
+    /// The tiles enclosed by the loop. Going along a row, a tile is inside
+    /// once an odd number of loop pipes reaching up have been crossed.
+    fn enclosed(&self, cells: &[Cell]) -> HashSet<Cell> {
+        let on_loop: HashSet<Cell> = cells.iter().copied().collect();
+        let mut enclosed = HashSet::new();
+
+        for (i, row) in self.pipes.iter().enumerate() {
+            let mut inside = false;
+            for (j, pipe) in row.iter().enumerate() {
+                let cell = Cell { i, j };
+                if on_loop.contains(&cell) {
+                    if pipe.ends().contains(&Direction::Up) {
+                        inside = !inside;
+                    }
+                } else if inside {
+                    enclosed.insert(cell);
+                }
             }
         }
-        q = next_level;
-        level += 1;
-    }
 
-    return (level, visited);
-}
+        enclosed
+    }
 
-fn get_inside_surfice(pipes: &Vec<Vec<Pipe>>, is_loop: &Vec<Vec<bool>>) -> u32 {
-    let mut inside_count = 0;
-    let mut is_inside = false;
+    /// Draws the loop with box-drawing characters and every other tile as
+    /// `I` or `O` depending on whether it is enclosed. With `colours` the
+    /// other tiles keep their own pipe instead, on a green background when
+    /// inside, and the loop is drawn in bold yellow.
+    fn render(&self, cells: &[Cell], enclosed: &HashSet<Cell>, colours: bool) -> String {
+        let on_loop: HashSet<Cell> = cells.iter().copied().collect();
+        let mut result = String::new();
 
-    for i in 0..pipes.len() {
-        for j in 0..pipes[0].len() {
-            if is_loop[i][j] {
-                match pipes[i][j] {
-                    Pipe::Vertical | Pipe::SouthWest | Pipe::SouthEast => is_inside = !is_inside,
-                    _ => {}
+        for (i, row) in self.pipes.iter().enumerate() {
+            for (j, pipe) in row.iter().enumerate() {
+                let cell = Cell { i, j };
+                let tile = match (on_loop.contains(&cell), enclosed.contains(&cell), colours) {
+                    (true, _, false) => pipe.box_drawing().to_string(),
+                    (true, _, true) => format!("\x1b[1;33m{}\x1b[0m", pipe.box_drawing()),
+                    (false, true, false) => "I".to_string(),
+                    (false, true, true) => format!("\x1b[42m{}\x1b[0m", pipe.box_drawing()),
+                    (false, false, false) => "O".to_string(),
+                    (false, false, true) => format!("\x1b[2m{}\x1b[0m", pipe.box_drawing()),
                 };
-            } else if is_inside {
-                inside_count += 1;
+                result.push_str(&tile);
             }
+            result.push('\n');
         }
-    }
 
-    return inside_count;
+        result
+    }
 }
 
-fn parse_pipes(s: &str) -> (Vec<Vec<Pipe>>, Cell) {
-    let mut result = Vec::new();
-    let mut start = Cell { i: 0, j: 0 };
-    for (i, line) in s.lines().enumerate() {
-        result.push(
-            line.chars()
-                .enumerate()
-                .map(|(j, ch)| {
-                    if ch == 'S' {
-                        start.i = i;
-                        start.j = j;
-                    }
-                    return parse_pipe(ch);
-                })
-                .collect::<Vec<Pipe>>(),
-        );
-    }
+/// The loop as a polygon through the centres of its tiles. By Pick's theorem
+/// its interior lattice points are exactly the enclosed tiles.
+fn loop_polygon(cells: &[Cell]) -> Polygon {
+    let vertices = cells
+        .iter()
+        .map(|cell| Point::new(cell.j as i64, cell.i as i64))
+        .collect();
 
-    return (result, start);
+    Polygon::from_vertices(vertices).expect("The loop is a closed rectilinear polygon")
 }
 
 fn main() {
     let input = fs::read_to_string("input").unwrap();
-    let (mut pipes, start) = parse_pipes(&input);
+    let mut maze = Maze::parse(&input);
+
+    let cells = maze.find_loop().expect("The start is not on a loop");
+    println!("{}", cells.len() / 2);
 
-    let (levels, is_loop) = bfs(&mut pipes, &start);
-    println!("{}", levels - 1);
+    println!("{}", loop_polygon(&cells).interior_points());
 
-    println!("{}", get_inside_surfice(&pipes, &is_loop));
+    if env::args().any(|arg| arg == "--show") {
+        print!("{}", maze.render(&cells, &maze.enclosed(&cells), true));
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Cell, Pipe};
+    use std::fs;
+
+    use common::polygon::Direction;
+
+    use crate::{loop_polygon, Cell, Maze, Pipe};
+
+    fn maze(path: &str) -> (Maze, Vec<Cell>) {
+        let mut maze = Maze::parse(&fs::read_to_string(path).unwrap());
+        let cells = maze.find_loop().unwrap();
+
+        (maze, cells)
+    }
+
+    fn inside_count(path: &str) -> i64 {
+        let (_, cells) = maze(path);
+
+        loop_polygon(&cells).interior_points()
+    }
+
+    #[test]
+    fn test_inside_count() {
+        assert_eq!(inside_count("test"), 1);
+        assert_eq!(inside_count("test3"), 4);
+        assert_eq!(inside_count("test4"), 8);
+        assert_eq!(inside_count("test5"), 10);
+    }
+
+    #[test]
+    fn test_enclosed() {
+        for (path, count) in [("test", 1), ("test3", 4), ("test4", 8), ("test5", 10)] {
+            let (maze, cells) = maze(path);
+            assert_eq!(maze.enclosed(&cells).len(), count, "{}", path);
+        }
+
+        let (maze, cells) = maze("test3");
+        let enclosed = maze.enclosed(&cells);
+        assert!(enclosed.contains(&Cell { i: 6, j: 2 }));
+        assert!(!enclosed.contains(&Cell { i: 3, j: 3 }));
+    }
+
+    #[test]
+    fn test_enclosed_matches_polygon() {
+        // The parity scan and Pick's theorem on the loop have to agree
+        for path in ["test", "test2", "test3", "test4", "test5"] {
+            let (maze, cells) = maze(path);
+            assert_eq!(
+                maze.enclosed(&cells).len() as i64,
+                loop_polygon(&cells).interior_points(),
+                "{}",
+                path
+            );
+        }
+    }
 
     #[test]
-    fn test_get_next() {
-        let pipe = Pipe::Vertical.get_next(2, 3);
-        let pipe = [pipe.0, pipe.1];
-        assert!(pipe.contains(&Cell { i: 3, j: 3 }));
-        assert!(pipe.contains(&Cell { i: 1, j: 3 }));
-
-        let pipe = Pipe::Horizontal.get_next(2, 3);
-        let pipe = [pipe.0, pipe.1];
-        assert!(pipe.contains(&Cell { i: 2, j: 2 }));
-        assert!(pipe.contains(&Cell { i: 2, j: 4 }));
-
-        let pipe = Pipe::NorthEast.get_next(2, 3);
-        let pipe = [pipe.0, pipe.1];
-        assert!(pipe.contains(&Cell { i: 1, j: 3 }));
-        assert!(pipe.contains(&Cell { i: 2, j: 4 }));
-
-        let pipe = Pipe::NorthWest.get_next(2, 3);
-        let pipe = [pipe.0, pipe.1];
-        assert!(pipe.contains(&Cell { i: 1, j: 3 }));
-        assert!(pipe.contains(&Cell { i: 2, j: 2 }));
-
-        let pipe = Pipe::SouthWest.get_next(2, 3);
-        let pipe = [pipe.0, pipe.1];
-        assert!(pipe.contains(&Cell { i: 3, j: 3 }));
-        assert!(pipe.contains(&Cell { i: 2, j: 2 }));
-
-        let pipe = Pipe::SouthEast.get_next(2, 3);
-        let pipe = [pipe.0, pipe.1];
-        assert!(pipe.contains(&Cell { i: 3, j: 3 }));
-        assert!(pipe.contains(&Cell { i: 2, j: 4 }));
+    fn test_walk_loop() {
+        let (maze, cells) = maze("test2");
+
+        assert_eq!(cells.len(), 16);
+        assert_eq!(cells[0], maze.start);
+        assert_eq!(maze.pipes[maze.start.i][maze.start.j], Pipe::SouthEast);
+        for (i, cell) in cells.iter().enumerate() {
+            let next = cells[(i + 1) % cells.len()];
+            assert_eq!(cell.i.abs_diff(next.i) + cell.j.abs_diff(next.j), 1);
+        }
+    }
+
+    #[test]
+    fn test_junk_around_start() {
+        // Three neighbours of the start point at it, but the pipe above runs
+        // off the map, and the loop on the left never reaches the start.
+        let mut maze = Maze::parse(
+            "..|....
+F7|....
+||S-7..
+LJ|.|..
+..L-J..",
+        );
+        let cells = maze.find_loop().unwrap();
+
+        assert_eq!(cells.len(), 8);
+        assert_eq!(maze.pipes[2][2], Pipe::SouthEast);
+        assert_eq!(maze.enclosed(&cells).len(), 1);
+
+        assert!(Maze::parse("S-7\n|..").find_loop().is_none());
+    }
+
+    #[test]
+    fn test_render() {
+        let (maze, cells) = maze("test");
+        let enclosed = maze.enclosed(&cells);
+
+        assert_eq!(
+            maze.render(&cells, &enclosed, false),
+            "OOOOO\nO┌─┐O\nO│I│O\nO└─┘O\nOOOOO\n"
+        );
+    }
+
+    #[test]
+    fn test_ends() {
+        let cases = [
+            (Pipe::Vertical, [Direction::Up, Direction::Down]),
+            (Pipe::Horizontal, [Direction::Left, Direction::Right]),
+            (Pipe::NorthEast, [Direction::Up, Direction::Right]),
+            (Pipe::NorthWest, [Direction::Up, Direction::Left]),
+            (Pipe::SouthWest, [Direction::Down, Direction::Left]),
+            (Pipe::SouthEast, [Direction::Down, Direction::Right]),
+        ];
+
+        for (pipe, ends) in cases {
+            let actual = pipe.ends();
+            assert_eq!(actual.len(), 2);
+            assert!(ends.iter().all(|end| actual.contains(end)), "{:?}", pipe);
+        }
+        assert!(Pipe::Ground.ends().is_empty());
     }
 }
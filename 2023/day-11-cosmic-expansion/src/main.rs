@@ -1,8 +1,8 @@
 use std::{
-    cmp::{max, min},
-    collections::HashSet,
+    env,
+    fmt::{self, Display},
     fs,
-    io::Empty,
+    str::FromStr,
 };
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -11,148 +11,129 @@ enum Tile {
     Empty,
 }
 
-fn expand_rows(space: &Vec<Vec<Tile>>) -> Vec<Vec<Tile>> {
-    let mut expanded = Vec::new();
+fn find_galaxies(space: &[Vec<Tile>]) -> Vec<(usize, usize)> {
+    let mut galaxies = Vec::new();
 
-    for row in space {
-        expanded.push(row.clone());
-        if row.iter().all(|tile| *tile == Tile::Empty) {
-            expanded.push(row.clone());
+    for (i, row) in space.iter().enumerate() {
+        for (j, tile) in row.iter().enumerate() {
+            if *tile == Tile::Galaxy {
+                galaxies.push((i, j));
+            }
         }
     }
 
-    return expanded;
+    galaxies
 }
 
-fn expand_columns(space: &Vec<Vec<Tile>>) -> Vec<Vec<Tile>> {
-    let mut expanded = vec![Vec::<Tile>::new(); space.len()];
-
-    for j in 0..space[0].len() {
-        let mut to_expand = true;
-        for i in 0..space.len() {
-            if space[i][j] == Tile::Galaxy {
-                to_expand = false;
-                break;
-            }
-        }
+fn rows_to_expand(space: &[Vec<Tile>]) -> Vec<usize> {
+    (0..space.len())
+        .filter(|i| space[*i].iter().all(|tile| *tile == Tile::Empty))
+        .collect()
+}
 
-        for i in 0..space.len() {
-            expanded[i].push(space[i][j]);
-            if to_expand {
-                expanded[i].push(space[i][j]);
-            }
-        }
-    }
+fn columns_to_expand(space: &[Vec<Tile>]) -> Vec<usize> {
+    (0..space[0].len())
+        .filter(|j| space.iter().all(|row| row[*j] == Tile::Empty))
+        .collect()
+}
 
-    return expanded;
+/// The coordinates of every galaxy once each empty row and column has been
+/// replaced by `factor` of them, in the order `find_galaxies` returns them.
+struct Expanded {
+    rows: Vec<u128>,
+    columns: Vec<u128>,
 }
 
-fn find_galaxies(space: &Vec<Vec<Tile>>) -> Vec<(usize, usize)> {
-    let mut galaxies = Vec::new();
+#[derive(Debug, PartialEq, Eq)]
+enum ExpansionError {
+    /// Every empty line has to stay at least one line wide.
+    ZeroFactor,
+    /// Galaxies are numbered from 1 to `count`.
+    NoSuchGalaxy { galaxy: usize, count: usize },
+}
 
-    for i in 0..space.len() {
-        for j in 0..space[i].len() {
-            if space[i][j] == Tile::Galaxy {
-                galaxies.push((i, j));
+impl Display for ExpansionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpansionError::ZeroFactor => write!(f, "The expansion factor must be at least 1"),
+            ExpansionError::NoSuchGalaxy { galaxy, count } => {
+                write!(f, "No galaxy {}, they are numbered 1 to {}", galaxy, count)
             }
         }
     }
-
-    return galaxies;
 }
 
-fn find_distances_sum(galaxies: &Vec<(usize, usize)>) -> Vec<Vec<usize>> {
-    let mut result: Vec<Vec<usize>> = Vec::new();
-
-    for i in 0..galaxies.len() - 1 {
-        result.push(Vec::new());
-        for j in i + 1..galaxies.len() {
-            let distance =
-                galaxies[i].0.abs_diff(galaxies[j].0) + galaxies[i].1.abs_diff(galaxies[j].1);
-            result[i].push(distance);
-        }
+fn expand(space: &[Vec<Tile>], factor: u128) -> Result<Expanded, ExpansionError> {
+    if factor == 0 {
+        return Err(ExpansionError::ZeroFactor);
     }
 
-    return result;
-}
+    let galaxies = find_galaxies(space);
+    let empty_rows = rows_to_expand(space);
+    let empty_columns = columns_to_expand(space);
 
-fn expand(space: &Vec<Vec<Tile>>) -> Vec<Vec<Tile>> {
-    let expanded_columns = expand_columns(&space);
+    // Both lists are sorted, so the empty lines before a coordinate are
+    // exactly the ones a binary search puts in front of it.
+    let shift = |coordinate: usize, empty: &[usize]| {
+        coordinate as u128 + (factor - 1) * empty.partition_point(|line| *line < coordinate) as u128
+    };
 
-    return expand_rows(&expanded_columns);
+    Ok(Expanded {
+        rows: galaxies
+            .iter()
+            .map(|(i, _)| shift(*i, &empty_rows))
+            .collect(),
+        columns: galaxies
+            .iter()
+            .map(|(_, j)| shift(*j, &empty_columns))
+            .collect(),
+    })
 }
 
-fn rows_to_expand(space: &Vec<Vec<Tile>>) -> HashSet<usize> {
-    let mut expanded = HashSet::new();
-
-    for (i, row) in space.iter().enumerate() {
-        if row.iter().all(|tile| *tile == Tile::Empty) {
-            expanded.insert(i);
-        }
+impl Expanded {
+    fn distance(&self, a: usize, b: usize) -> u128 {
+        self.rows[a].abs_diff(self.rows[b]) + self.columns[a].abs_diff(self.columns[b])
     }
 
-    return expanded;
-}
-
-fn columns_to_expand(space: &Vec<Vec<Tile>>) -> HashSet<usize> {
-    let mut expanded = HashSet::new();
-
-    for j in 0..space[0].len() {
-        let mut to_expand = true;
-        for i in 0..space.len() {
-            if space[i][j] == Tile::Galaxy {
-                to_expand = false;
-                break;
+    /// The distance between galaxies `a` and `b`, numbered from 1 the way
+    /// the puzzle numbers them.
+    fn galaxy_distance(&self, a: usize, b: usize) -> Result<u128, ExpansionError> {
+        let count = self.rows.len();
+        for galaxy in [a, b] {
+            if galaxy == 0 || galaxy > count {
+                return Err(ExpansionError::NoSuchGalaxy { galaxy, count });
             }
         }
 
-        if to_expand {
-            expanded.insert(j);
-        }
+        Ok(self.distance(a - 1, b - 1))
     }
 
-    return expanded;
+    /// The distance between every pair of galaxies, summed. Manhattan
+    /// distances split into rows and columns, which add up separately.
+    fn distances_sum(&self) -> u128 {
+        pairwise_sum(&self.rows) + pairwise_sum(&self.columns)
+    }
 }
 
-fn find_distances_sum_sets(
-    galaxies: &Vec<(usize, usize)>,
-    expanded_rows: &HashSet<usize>,
-    expanded_columns: &HashSet<usize>,
-    expand_factor: usize,
-) -> Vec<Vec<usize>> {
-    let mut result: Vec<Vec<usize>> = Vec::new();
-
-    for i in 0..galaxies.len() - 1 {
-        result.push(Vec::new());
-        for j in i + 1..galaxies.len() {
-            let mut expanded_rows_count = 0;
-            for k in min(galaxies[i].0, galaxies[j].0)..max(galaxies[i].0, galaxies[j].0) {
-                if expanded_rows.contains(&k) {
-                    expanded_rows_count += 1;
-                }
-            }
-
-            let mut expanded_columns_count = 0;
-            for k in min(galaxies[i].1, galaxies[j].1)..max(galaxies[i].1, galaxies[j].1) {
-                if expanded_columns.contains(&k) {
-                    expanded_columns_count += 1;
-                }
-            }
-
-            let distance = galaxies[i].0.abs_diff(galaxies[j].0)
-                + galaxies[i].1.abs_diff(galaxies[j].1)
-                + (expand_factor - 1) * (expanded_columns_count + expanded_rows_count);
-
-            result[i].push(distance);
-        }
+/// The sum of `|a - b|` over every pair of values. Once sorted, each value is
+/// at least as large as all the ones before it, so it adds itself once per
+/// earlier value minus their running total.
+fn pairwise_sum(values: &[u128]) -> u128 {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+
+    let mut before = 0;
+    let mut sum = 0;
+    for (k, value) in sorted.iter().enumerate() {
+        sum += value * k as u128 - before;
+        before += value;
     }
 
-    return result;
+    sum
 }
 
 fn parse_space(s: &str) -> Vec<Vec<Tile>> {
-    return s
-        .lines()
+    s.lines()
         .map(|line| {
             line.chars()
                 .map(|tile| match tile {
@@ -161,37 +142,64 @@ fn parse_space(s: &str) -> Vec<Vec<Tile>> {
                 })
                 .collect()
         })
-        .collect();
+        .collect()
 }
 
-fn get_sum(distances: &Vec<Vec<usize>>) -> usize {
-    return distances
-        .iter()
-        .map(|dist_from_galaxy| dist_from_galaxy.iter().sum::<usize>())
-        .sum();
+/// The `count` numbers following `flag`, `Ok(None)` without the flag and
+/// `Err` with a usage line if they are missing or not numbers.
+fn flag_numbers<T: FromStr>(
+    args: &[String],
+    flag: &str,
+    usage: &str,
+    count: usize,
+) -> Result<Option<Vec<T>>, String> {
+    let Some(position) = args.iter().position(|arg| arg == flag) else {
+        return Ok(None);
+    };
+
+    (1..=count)
+        .map(|k| args.get(position + k).and_then(|arg| arg.parse().ok()))
+        .collect::<Option<Vec<T>>>()
+        .map(Some)
+        .ok_or_else(|| format!("Usage: {} {}", flag, usage))
 }
 
 fn main() {
     let input = fs::read_to_string("input").unwrap();
     let space = parse_space(&input);
-    let expanded_space = expand(&space);
-    let galaxies = find_galaxies(&expanded_space);
-
-    println!("{}", get_sum(&find_distances_sum(&galaxies)));
-
-    let expanded_rows = rows_to_expand(&space);
-    let expanded_columns = columns_to_expand(&space);
-    let galaxies_non_expanded = find_galaxies(&space);
-
-    println!(
-        "{}",
-        get_sum(&find_distances_sum_sets(
-            &galaxies_non_expanded,
-            &expanded_rows,
-            &expanded_columns,
-            1000000
-        ))
-    );
+
+    let args: Vec<String> = env::args().collect();
+    let (factor, galaxies) = match (
+        flag_numbers::<u128>(&args, "--factor", "<factor>", 1),
+        flag_numbers::<usize>(&args, "--distance", "<galaxy> <galaxy>", 2),
+    ) {
+        (Ok(factor), Ok(galaxies)) => (factor.map(|factor| factor[0]), galaxies),
+        (Err(usage), _) | (_, Err(usage)) => {
+            println!("{}", usage);
+            return;
+        }
+    };
+
+    for factor in factor.map_or(vec![2, 1000000], |factor| vec![factor]) {
+        let expanded = match expand(&space, factor) {
+            Ok(expanded) => expanded,
+            Err(error) => {
+                println!("{}", error);
+                return;
+            }
+        };
+        println!("{}", expanded.distances_sum());
+
+        if let Some(&[a, b]) = galaxies.as_deref() {
+            match expanded.galaxy_distance(a, b) {
+                Ok(distance) => println!("  {} to {}: {}", a, b, distance),
+                Err(error) => {
+                    println!("{}", error);
+                    return;
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -199,15 +207,15 @@ mod tests {
     use std::{collections::HashSet, fs};
 
     use crate::{
-        columns_to_expand, expand, find_distances_sum, find_distances_sum_sets, find_galaxies,
-        get_sum, parse_space, rows_to_expand, Tile,
+        columns_to_expand, expand, find_galaxies, pairwise_sum, parse_space, rows_to_expand,
+        ExpansionError, Tile,
     };
 
     #[test]
     fn test_parse() {
         let input = fs::read_to_string("test").unwrap();
         let space = parse_space(&input);
-        let galaxySet = HashSet::from([
+        let galaxy_set = HashSet::from([
             (0, 3),
             (1, 7),
             (2, 0),
@@ -219,12 +227,12 @@ mod tests {
             (9, 4),
         ]);
 
-        for i in 0..space.len() {
-            for j in 0..space[i].len() {
-                if galaxySet.contains(&(i, j)) {
-                    assert_eq!(space[i][j], Tile::Galaxy);
+        for (i, row) in space.iter().enumerate() {
+            for (j, tile) in row.iter().enumerate() {
+                if galaxy_set.contains(&(i, j)) {
+                    assert_eq!(*tile, Tile::Galaxy);
                 } else {
-                    assert_eq!(space[i][j], Tile::Empty);
+                    assert_eq!(*tile, Tile::Empty);
                 }
             }
         }
@@ -234,12 +242,23 @@ mod tests {
     fn test_expand() {
         let input = fs::read_to_string("test").unwrap();
         let space = parse_space(&input);
-        let expanded_space = expand(&space);
+        let expanded = expand(&space, 2).unwrap();
 
         let expanded_input = fs::read_to_string("test_expanded").unwrap();
-        let expected_expanded = parse_space(&expanded_input);
-
-        assert_eq!(expanded_space, expected_expanded);
+        let expected: Vec<(u128, u128)> = find_galaxies(&parse_space(&expanded_input))
+            .iter()
+            .map(|(i, j)| (*i as u128, *j as u128))
+            .collect();
+
+        assert_eq!(
+            expanded
+                .rows
+                .iter()
+                .copied()
+                .zip(expanded.columns.iter().copied())
+                .collect::<Vec<_>>(),
+            expected
+        );
     }
 
     #[test]
@@ -265,10 +284,38 @@ mod tests {
     fn test_find_distances() {
         let input = fs::read_to_string("test").unwrap();
         let space = parse_space(&input);
-        let expanded_space = expand(&space);
-        let galaxies = find_galaxies(&expanded_space);
+        let expanded = expand(&space, 2).unwrap();
+
+        assert_eq!(expanded.distances_sum(), 374);
+        assert_eq!(expanded.distance(4, 8), 9);
+        assert_eq!(expanded.distance(0, 6), 15);
+        assert_eq!(expanded.distance(2, 5), 17);
+        assert_eq!(expanded.distance(7, 8), 5);
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        let input = fs::read_to_string("test").unwrap();
+        let space = parse_space(&input);
 
-        assert_eq!(get_sum(&find_distances_sum(&galaxies)), 374);
+        assert_eq!(expand(&space, 0).err(), Some(ExpansionError::ZeroFactor));
+
+        let expanded = expand(&space, 2).unwrap();
+        assert_eq!(expanded.galaxy_distance(5, 9), Ok(9));
+        assert_eq!(
+            expanded.galaxy_distance(0, 9),
+            Err(ExpansionError::NoSuchGalaxy {
+                galaxy: 0,
+                count: 9
+            })
+        );
+        assert_eq!(
+            expanded.galaxy_distance(1, 10),
+            Err(ExpansionError::NoSuchGalaxy {
+                galaxy: 10,
+                count: 9
+            })
+        );
     }
 
     #[test]
@@ -277,7 +324,7 @@ mod tests {
         let space = parse_space(&input);
         let expanded_rows = rows_to_expand(&space);
 
-        assert_eq!(expanded_rows, HashSet::from([3, 7]));
+        assert_eq!(expanded_rows, vec![3, 7]);
     }
 
     #[test]
@@ -286,18 +333,34 @@ mod tests {
         let space = parse_space(&input);
         let expanded_rows = columns_to_expand(&space);
 
-        assert_eq!(expanded_rows, HashSet::from([2, 5, 8]));
+        assert_eq!(expanded_rows, vec![2, 5, 8]);
     }
 
     #[test]
-    fn test_find_distances_sum_set() {
+    fn test_expansion_factors() {
         let input = fs::read_to_string("test").unwrap();
         let space = parse_space(&input);
-        let galaxies = find_galaxies(&space);
-        let expanded_rows = rows_to_expand(&space);
-        let expanded_columns = columns_to_expand(&space);
 
-        let sum_sets = find_distances_sum_sets(&galaxies, &expanded_rows, &expanded_columns, 2);
-        assert_eq!(get_sum(&sum_sets), 374);
+        assert_eq!(expand(&space, 10).unwrap().distances_sum(), 1030);
+        assert_eq!(expand(&space, 100).unwrap().distances_sum(), 8410);
+        assert_eq!(expand(&space, 1).unwrap().distances_sum(), 292);
+        assert_eq!(
+            expand(&space, 10u128.pow(30)).unwrap().distances_sum(),
+            82 * (10u128.pow(30) - 1) + 292
+        );
+    }
+
+    #[test]
+    fn test_pairwise_sum() {
+        let values: [u128; 7] = [5, 1, 9, 3, 3, 12, 0];
+        let mut expected = 0;
+        for (k, a) in values.iter().enumerate() {
+            for b in &values[k + 1..] {
+                expected += a.abs_diff(*b);
+            }
+        }
+
+        assert_eq!(pairwise_sum(&values), expected);
+        assert_eq!(pairwise_sum(&[]), 0);
     }
 }
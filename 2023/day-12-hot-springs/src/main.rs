@@ -1,129 +1,166 @@
-use std::{collections::HashMap, fs, time::Instant};
+use rand::Rng;
+use std::{env, fs};
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Condition {
     Operational,
     Damaged,
     Unknown,
 }
 
-fn count_arrangements_line(
-    line: &mut Vec<Condition>,
-    groups: &Vec<u32>,
-    spring: usize,
-    mut current_group: usize,
-    mut current_group_size: u32,
-    cache: &mut HashMap<(usize, usize, u32), u64>,
-) -> u64 {
-    if spring == line.len() {
-        if current_group == groups.len() - 1 && current_group_size != groups[current_group] {
-            return 0;
+/// Arrangement table for a single row.
+///
+/// `ways[i][g]` is the number of ways to fill `line[i..]` with `groups[g..]`,
+/// given that the spring before `i` (if any) is operational. A damaged group is
+/// placed as a whole run, so the run length never has to be tracked and the
+/// table is filled in O(n·g). Because every entry counts completions, the table
+/// can also be walked forward to enumerate, rank or sample arrangements.
+struct Arrangements<'a> {
+    line: &'a [Condition],
+    groups: &'a [usize],
+    ways: Vec<Vec<u128>>,
+}
+
+impl<'a> Arrangements<'a> {
+    fn new(line: &'a [Condition], groups: &'a [usize]) -> Self {
+        let n = line.len();
+        // operational_before[i] = number of operational springs in line[..i]
+        let mut operational_before = vec![0; n + 1];
+        for (i, condition) in line.iter().enumerate() {
+            operational_before[i + 1] =
+                operational_before[i] + usize::from(*condition == Condition::Operational);
         }
 
-        if current_group < groups.len() - 1 {
-            return 0;
+        let mut ways = vec![vec![0u128; groups.len() + 1]; n + 2];
+        ways[n][groups.len()] = 1;
+        ways[n + 1][groups.len()] = 1;
+
+        for i in (0..n).rev() {
+            for g in (0..=groups.len()).rev() {
+                let mut count = 0;
+                if line[i] != Condition::Damaged {
+                    count += ways[i + 1][g];
+                }
+
+                if line[i] != Condition::Operational && g < groups.len() {
+                    let end = i + groups[g];
+                    if end <= n
+                        && operational_before[end] == operational_before[i]
+                        && (end == n || line[end] != Condition::Damaged)
+                    {
+                        count += ways[end + 1][g + 1];
+                    }
+                }
+
+                ways[i][g] = count;
+            }
         }
 
-        return 1;
+        Arrangements { line, groups, ways }
     }
 
-    let key = (spring, current_group, current_group_size);
-    if let Some(count) = cache.get(&key) {
-        return *count;
+    fn count(&self) -> u128 {
+        self.ways[0][0]
     }
 
-    let count = match line[spring] {
-        Condition::Operational => {
-            if spring > 0 && line[spring - 1] == Condition::Damaged {
-                if groups[current_group] != current_group_size {
-                    cache.insert(key, 0);
-                    return 0;
-                }
+    /// Returns the `k`-th arrangement (0-based) in the order that places an
+    /// operational spring before a damaged one at each unknown position.
+    fn nth(&self, mut k: u128) -> Option<Vec<Condition>> {
+        if k >= self.count() {
+            return None;
+        }
 
-                current_group += 1;
-                current_group_size = 0;
+        let mut arrangement = Vec::with_capacity(self.line.len());
+        let (mut i, mut g) = (0, 0);
+        while i < self.line.len() {
+            let operational = if self.line[i] != Condition::Damaged {
+                self.ways[i + 1][g]
+            } else {
+                0
+            };
+
+            if k < operational {
+                arrangement.push(Condition::Operational);
+                i += 1;
+                continue;
             }
 
-            count_arrangements_line(
-                line,
-                groups,
-                spring + 1,
-                current_group,
-                current_group_size,
-                cache,
-            )
-        }
-        Condition::Damaged => {
-            if current_group >= groups.len() {
-                cache.insert(key, 0);
-                return 0;
+            k -= operational;
+            let end = i + self.groups[g];
+            arrangement.extend((i..end).map(|_| Condition::Damaged));
+            if end < self.line.len() {
+                arrangement.push(Condition::Operational);
             }
+            i = end + 1;
+            g += 1;
+        }
+
+        Some(arrangement)
+    }
+
+    fn all(&self) -> Vec<Vec<Condition>> {
+        let mut result = Vec::new();
+        let mut current = Vec::with_capacity(self.line.len());
+        self.collect(0, 0, &mut current, &mut result);
+
+        result
+    }
 
-            current_group_size += 1;
-            if current_group_size > groups[current_group] {
-                cache.insert(key, 0);
-                return 0;
+    fn collect(
+        &self,
+        i: usize,
+        g: usize,
+        current: &mut Vec<Condition>,
+        result: &mut Vec<Vec<Condition>>,
+    ) {
+        if i >= self.line.len() {
+            if g == self.groups.len() {
+                result.push(current.clone());
             }
+            return;
+        }
 
-            count_arrangements_line(
-                line,
-                groups,
-                spring + 1,
-                current_group,
-                current_group_size,
-                cache,
-            )
+        if self.line[i] != Condition::Damaged && self.ways[i + 1][g] > 0 {
+            current.push(Condition::Operational);
+            self.collect(i + 1, g, current, result);
+            current.pop();
         }
-        Condition::Unknown => {
-            line[spring] = Condition::Damaged;
-            let mut arrangements = 0;
-            if current_group < groups.len() && current_group_size + 1 <= groups[current_group] {
-                arrangements += count_arrangements_line(
-                    line,
-                    groups,
-                    spring + 1,
-                    current_group,
-                    current_group_size + 1,
-                    cache,
-                );
-            }
-            line[spring] = Condition::Operational;
-            if spring > 0 && line[spring - 1] == Condition::Damaged {
-                if groups[current_group] != current_group_size {
-                    arrangements += 0;
-                } else {
-                    arrangements += count_arrangements_line(
-                        line,
-                        groups,
-                        spring + 1,
-                        current_group + 1,
-                        0,
-                        cache,
-                    );
+
+        if self.line[i] != Condition::Operational && g < self.groups.len() {
+            let end = i + self.groups[g];
+            if end <= self.line.len()
+                && self.line[i..end]
+                    .iter()
+                    .all(|c| *c != Condition::Operational)
+                && (end == self.line.len() || self.line[end] != Condition::Damaged)
+                && self.ways[end + 1][g + 1] > 0
+            {
+                let length = current.len();
+                current.extend((i..end).map(|_| Condition::Damaged));
+                if end < self.line.len() {
+                    current.push(Condition::Operational);
                 }
-            } else {
-                arrangements += count_arrangements_line(
-                    line,
-                    groups,
-                    spring + 1,
-                    current_group,
-                    current_group_size,
-                    cache,
-                );
+                self.collect(end + 1, g + 1, current, result);
+                current.truncate(length);
             }
-            line[spring] = Condition::Unknown;
+        }
+    }
 
-            arrangements
+    fn sample<R: Rng>(&self, rng: &mut R) -> Option<Vec<Condition>> {
+        if self.count() == 0 {
+            return None;
         }
-    };
 
-    cache.insert(key, count);
-    return count;
+        self.nth(rng.random_range(0..self.count()))
+    }
+}
+
+fn count_arrangements(line: &[Condition], groups: &[usize]) -> u128 {
+    Arrangements::new(line, groups).count()
 }
 
 fn parse_line(s: &str) -> Vec<Condition> {
-    return s
-        .chars()
+    s.chars()
         .map(|ch| match ch {
             '#' => Condition::Damaged,
             '.' => Condition::Operational,
@@ -132,78 +169,143 @@ fn parse_line(s: &str) -> Vec<Condition> {
                 panic!("Wrong input.")
             }
         })
-        .collect();
+        .collect()
 }
 
-fn multiply_line(line: Vec<Condition>) -> Vec<Condition> {
-    let mut result = Vec::new();
-    for _ in 0..5 {
-        result.append(&mut line.clone());
-        result.push(Condition::Unknown);
-    }
+fn line_to_string(line: &[Condition]) -> String {
+    line.iter()
+        .map(|condition| match condition {
+            Condition::Damaged => '#',
+            Condition::Operational => '.',
+            Condition::Unknown => '?',
+        })
+        .collect()
+}
+
+fn parse_groups(s: &str) -> Vec<usize> {
+    s.split(",").map(|num| num.parse().unwrap()).collect()
+}
 
-    result.pop();
+fn parse_row(s: &str) -> (Vec<Condition>, Vec<usize>) {
+    let (line, groups) = s.split_once(" ").unwrap();
 
-    return result;
+    (parse_line(line), parse_groups(groups))
 }
 
-fn multiply_group(group: Vec<u32>) -> Vec<u32> {
+fn unfold_line(line: &[Condition], factor: usize) -> Vec<Condition> {
     let mut result = Vec::new();
-    for _ in 0..5 {
-        result.append(&mut group.clone());
+    for i in 0..factor {
+        if i > 0 {
+            result.push(Condition::Unknown);
+        }
+        result.extend_from_slice(line);
     }
 
-    return result;
+    result
 }
 
-fn line_to_string(line: &Vec<Condition>) -> String {
-    return line
-        .iter()
-        .map(|condition| match condition {
-            Condition::Operational => '.',
-            Condition::Damaged => '#',
-            Condition::Unknown => '?',
+fn unfold_groups(groups: &[usize], factor: usize) -> Vec<usize> {
+    groups.repeat(factor)
+}
+
+fn sum_arrangements(input: &str, factor: usize) -> u128 {
+    input
+        .lines()
+        .map(|row| {
+            let (line, groups) = parse_row(row);
+            count_arrangements(&unfold_line(&line, factor), &unfold_groups(&groups, factor))
         })
-        .collect();
+        .sum()
 }
 
 fn main() {
     let input = fs::read_to_string("input").unwrap();
 
-    let sum: u64 = input
-        .lines()
-        .enumerate()
-        .map(|(i, line)| {
-            let mut split = line.split(" ");
-
-            let mut line = parse_line(split.next().unwrap());
-            line = multiply_line(line);
-
-            let mut groups: Vec<u32> = split
-                .next()
-                .unwrap()
-                .split(",")
-                .map(|num| num.parse::<u32>().unwrap())
-                .collect();
-            groups = multiply_group(groups);
-            let now = Instant::now();
-            let count = count_arrangements_line(&mut line, &groups, 0, 0, 0, &mut HashMap::new());
-            println!("{i}: {:.2}", now.elapsed().as_secs_f32());
-            return count;
-        })
-        .sum();
-    println!("{}", sum);
+    println!("{}", sum_arrangements(&input, 1));
+    println!("{}", sum_arrangements(&input, 5));
+
+    // `--show <row>` lists the arrangements of one row, numbered from 1. Rows
+    // with too many to list show the first, the last and a few at random.
+    let args: Vec<String> = env::args().collect();
+    if let Some(position) = args.iter().position(|arg| arg == "--show") {
+        let Some(number) = args
+            .get(position + 1)
+            .and_then(|arg| arg.parse::<usize>().ok())
+        else {
+            println!("Usage: --show <row>");
+            return;
+        };
+        let Some(row) = number.checked_sub(1).and_then(|k| input.lines().nth(k)) else {
+            println!("No row {}", number);
+            return;
+        };
+
+        let (line, groups) = parse_row(row);
+        let arrangements = Arrangements::new(&line, &groups);
+        let count = arrangements.count();
+        println!("{}: {} arrangements", row, count);
+        if count <= 10 {
+            for arrangement in arrangements.all() {
+                println!("  {}", line_to_string(&arrangement));
+            }
+        } else {
+            let mut rng = rand::rng();
+            println!("  first  {}", line_to_string(&arrangements.nth(0).unwrap()));
+            println!(
+                "  last   {}",
+                line_to_string(&arrangements.nth(count - 1).unwrap())
+            );
+            for _ in 0..3 {
+                let arrangement = arrangements.sample(&mut rng).unwrap();
+                println!("  random {}", line_to_string(&arrangement));
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
 
     use crate::{
-        count_arrangements_line, line_to_string, multiply_group, multiply_line, parse_line,
+        count_arrangements, parse_line, sum_arrangements, unfold_groups, unfold_line, Arrangements,
         Condition,
     };
 
+    fn matches_groups(line: &[Condition], groups: &[usize]) -> bool {
+        let runs: Vec<usize> = line
+            .split(|c| *c != Condition::Damaged)
+            .map(|run| run.len())
+            .filter(|len| *len > 0)
+            .collect();
+
+        runs == groups
+    }
+
+    fn brute_force(line: &[Condition], groups: &[usize]) -> Vec<Vec<Condition>> {
+        let unknown: Vec<usize> = (0..line.len())
+            .filter(|i| line[*i] == Condition::Unknown)
+            .collect();
+
+        let mut result = Vec::new();
+        for mask in 0..1u32 << unknown.len() {
+            let mut candidate = line.to_vec();
+            for (bit, i) in unknown.iter().enumerate() {
+                candidate[*i] = if mask & (1 << bit) != 0 {
+                    Condition::Damaged
+                } else {
+                    Condition::Operational
+                };
+            }
+
+            if matches_groups(&candidate, groups) {
+                result.push(candidate);
+            }
+        }
+
+        result
+    }
+
     #[test]
     fn test_parse_line() {
         let line = parse_line("#.#.??#");
@@ -223,64 +325,103 @@ mod tests {
     }
 
     #[test]
-    fn test_count_arrangements_line() {
-        let mut line = parse_line("#.#.###");
-
-        assert_eq!(
-            count_arrangements_line(&mut line, &vec![1, 1, 3], 0, 0, 0, &mut HashMap::new()),
-            1
-        );
-
-        line = parse_line("???.###");
+    fn test_count_arrangements() {
+        assert_eq!(count_arrangements(&parse_line("#.#.###"), &[1, 1, 3]), 1);
+        assert_eq!(count_arrangements(&parse_line("???.###"), &[1, 1, 3]), 1);
         assert_eq!(
-            count_arrangements_line(&mut line, &vec![1, 1, 3], 0, 0, 0, &mut HashMap::new()),
-            1
-        );
-
-        line = parse_line(".??..#....###.");
-        assert_eq!(
-            count_arrangements_line(&mut line, &vec![1, 1, 3], 0, 0, 0, &mut HashMap::new()),
+            count_arrangements(&parse_line(".??..#....###."), &[1, 1, 3]),
             2
         );
-
-        line = parse_line(".??..??...?##.");
         assert_eq!(
-            count_arrangements_line(&mut line, &vec![1, 1, 3], 0, 0, 0, &mut HashMap::new()),
+            count_arrangements(&parse_line(".??..??...?##."), &[1, 1, 3]),
             4
         );
-
-        line = parse_line("?#?#?#?#?#?#?#?");
         assert_eq!(
-            count_arrangements_line(&mut line, &vec![1, 3, 1, 6], 0, 0, 0, &mut HashMap::new()),
+            count_arrangements(&parse_line("?#?#?#?#?#?#?#?"), &[1, 3, 1, 6]),
             1
         );
-
-        line = parse_line("?###????????");
         assert_eq!(
-            count_arrangements_line(&mut line, &vec![3, 2, 1], 0, 0, 0, &mut HashMap::new()),
+            count_arrangements(&parse_line("?###????????"), &[3, 2, 1]),
             10
         );
-
-        line = parse_line(".##.?#??.#.?#");
         assert_eq!(
-            count_arrangements_line(&mut line, &vec![2, 1, 1, 1], 0, 0, 0, &mut HashMap::new()),
+            count_arrangements(&parse_line(".##.?#??.#.?#"), &[2, 1, 1, 1]),
             1
         );
+        assert_eq!(count_arrangements(&parse_line("...."), &[]), 1);
+        assert_eq!(count_arrangements(&parse_line(".#.."), &[]), 0);
+    }
+
+    #[test]
+    fn test_sum_arrangements() {
+        let input = "???.### 1,1,3
+.??..??...?##. 1,1,3
+?#?#?#?#?#?#?#? 1,3,1,6
+????.#...#... 4,1,1
+????.######..#####. 1,6,5
+?###???????? 3,2,1";
+
+        assert_eq!(sum_arrangements(input, 1), 21);
+        assert_eq!(sum_arrangements(input, 5), 525152);
     }
 
     #[test]
-    fn test_multiply_group() {
-        let group = multiply_group(vec![1, 2]);
+    fn test_unfold_groups() {
+        let group = unfold_groups(&[1, 2], 5);
         assert_eq!(group, [1, 2, 1, 2, 1, 2, 1, 2, 1, 2]);
     }
 
     #[test]
-    fn test_multiply_line() {
-        let line = multiply_line(parse_line("???.###"));
+    fn test_unfold_line() {
+        let line = unfold_line(&parse_line("???.###"), 5);
 
-        assert_eq!(
-            line_to_string(&line),
-            "???.###????.###????.###????.###????.###"
-        );
+        assert_eq!(line, parse_line("???.###????.###????.###????.###????.###"));
+        assert_eq!(unfold_line(&parse_line("#?"), 1), parse_line("#?"));
+    }
+
+    #[test]
+    fn test_arrangements_all_and_nth() {
+        let line = parse_line("?###????????");
+        let groups = [3, 2, 1];
+        let arrangements = Arrangements::new(&line, &groups);
+
+        let all = arrangements.all();
+        assert_eq!(all.len(), 10);
+        for (k, arrangement) in all.iter().enumerate() {
+            assert_eq!(arrangements.nth(k as u128).as_ref(), Some(arrangement));
+        }
+        assert_eq!(arrangements.nth(10), None);
+        assert_eq!(arrangements.nth(0).unwrap(), parse_line(".###....##.#"));
+    }
+
+    #[test]
+    fn test_arrangements_match_brute_force() {
+        let mut rng = StdRng::seed_from_u64(12);
+        for _ in 0..500 {
+            let length = rng.random_range(0..=12);
+            let line: Vec<Condition> = (0..length)
+                .map(|_| match rng.random_range(0..3) {
+                    0 => Condition::Operational,
+                    1 => Condition::Damaged,
+                    _ => Condition::Unknown,
+                })
+                .collect();
+            let groups: Vec<usize> = (0..rng.random_range(0..=4))
+                .map(|_| rng.random_range(1..=4))
+                .collect();
+
+            let mut expected = brute_force(&line, &groups);
+            let arrangements = Arrangements::new(&line, &groups);
+            let mut actual = arrangements.all();
+
+            assert_eq!(arrangements.count(), expected.len() as u128);
+            expected.sort_by_key(|a| format!("{a:?}"));
+            actual.sort_by_key(|a| format!("{a:?}"));
+            assert_eq!(actual, expected);
+
+            if let Some(sample) = arrangements.sample(&mut rng) {
+                assert!(matches_groups(&sample, &groups));
+            }
+        }
     }
 }
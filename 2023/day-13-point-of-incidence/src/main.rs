@@ -1,180 +1,325 @@
-use std::fs;
-
-#[derive(Debug, PartialEq, Eq, Clone)]
-enum Element {
-    Ash,
-    Rock,
-}
+use std::{env, fs};
 
 #[derive(Debug)]
 struct ParseSubPatternError;
 
-fn parse_line(s: &str) -> Result<Vec<Element>, ParseSubPatternError> {
-    return s
-        .chars()
-        .map(|ch| match ch {
-            '.' => Ok(Element::Ash),
-            '#' => Ok(Element::Rock),
-            _ => Err(ParseSubPatternError),
-        })
-        .collect::<Result<Vec<Element>, ParseSubPatternError>>();
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Orientation {
+    /// Between two rows.
+    Horizontal,
+    /// Between two columns.
+    Vertical,
 }
 
-fn parse_pattern(input: &str) -> Result<Vec<Vec<Element>>, ParseSubPatternError> {
-    return input
-        .lines()
-        .map(|line| parse_line(line))
-        .collect::<Result<Vec<Vec<Element>>, ParseSubPatternError>>();
+/// A candidate mirror line, `position` rows or columns from the top or left,
+/// with how many cells don't match their reflection.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Reflection {
+    orientation: Orientation,
+    position: usize,
+    mismatches: u32,
 }
 
-fn transpose(pattern: &Vec<Vec<Element>>) -> Vec<Vec<Element>> {
-    let mut transposed = vec![vec![Element::Ash; pattern.len()]; pattern[0].len()];
-
-    for i in 0..pattern.len() {
-        for j in 0..pattern[i].len() {
-            transposed[j][i] = pattern[i][j].clone();
+impl Reflection {
+    fn score(&self) -> usize {
+        match self.orientation {
+            Orientation::Horizontal => 100 * self.position,
+            Orientation::Vertical => self.position,
         }
     }
+}
 
-    return transposed;
+/// A pattern with rock as set bits, once by row and once by column, so two
+/// rows or columns compare with a single xor.
+#[derive(Debug, PartialEq, Eq)]
+struct Pattern {
+    rows: Vec<u64>,
+    columns: Vec<u64>,
 }
 
-fn check_expand(
-    pattern: &Vec<Vec<Element>>,
-    left: i64,
-    right: usize,
-    error_threshold: usize,
-) -> usize {
-    let mut left = left;
-    let mut right = right;
-
-    let mut errors = 0;
-    while left >= 0 && right < pattern.len() {
-        for j in 0..pattern[0].len() {
-            if pattern[left as usize][j] != pattern[right][j] {
-                errors += 1;
-            }
+impl Pattern {
+    fn parse(input: &str) -> Result<Pattern, ParseSubPatternError> {
+        let lines: Vec<&str> = input.lines().collect();
+        let width = lines.first().ok_or(ParseSubPatternError)?.len();
+        if width > 64 || lines.len() > 64 || lines.iter().any(|line| line.len() != width) {
+            return Err(ParseSubPatternError);
         }
 
-        if errors > error_threshold {
-            break;
+        let mut rows = vec![0; lines.len()];
+        let mut columns = vec![0; width];
+        for (i, line) in lines.iter().enumerate() {
+            for (j, ch) in line.chars().enumerate() {
+                match ch {
+                    '.' => {}
+                    '#' => {
+                        rows[i] |= 1 << j;
+                        columns[j] |= 1 << i;
+                    }
+                    _ => return Err(ParseSubPatternError),
+                }
+            }
         }
 
-        left -= 1;
-        right += 1;
+        Ok(Pattern { rows, columns })
     }
 
-    return errors;
-}
+    fn lines(&self, orientation: Orientation) -> &[u64] {
+        match orientation {
+            Orientation::Horizontal => &self.rows,
+            Orientation::Vertical => &self.columns,
+        }
+    }
 
-fn get_indexes_from_middle(length: usize) -> Vec<usize> {
-    let i = length / 2;
-    let mut rows_to_check: Vec<usize> = Vec::new();
-    rows_to_check.push(i);
-    let mut step = 1;
+    /// The rows or columns that face each other across a mirror line,
+    /// innermost first.
+    fn facing(
+        &self,
+        orientation: Orientation,
+        position: usize,
+    ) -> impl Iterator<Item = (usize, usize)> {
+        let length = self.lines(orientation).len();
+        (0..position.min(length - position)).map(move |k| (position - 1 - k, position + k))
+    }
 
-    while rows_to_check.len() != length {
-        if (i as i64 - step as i64) >= 0 {
-            rows_to_check.push(i - step);
+    /// Every possible mirror line with its mismatch count, rows first.
+    fn reflections(&self) -> Vec<Reflection> {
+        let mut reflections = Vec::new();
+        for orientation in [Orientation::Horizontal, Orientation::Vertical] {
+            let lines = self.lines(orientation);
+            for position in 1..lines.len() {
+                let mismatches = self
+                    .facing(orientation, position)
+                    .map(|(a, b)| (lines[a] ^ lines[b]).count_ones())
+                    .sum();
+                reflections.push(Reflection {
+                    orientation,
+                    position,
+                    mismatches,
+                });
+            }
         }
 
-        if i + step < length {
-            rows_to_check.push(i + step);
+        reflections
+    }
+
+    /// The line with exactly `mismatches` smudges, if there is one.
+    fn mirror(&self, mismatches: u32) -> Option<Reflection> {
+        self.reflections()
+            .into_iter()
+            .find(|reflection| reflection.mismatches == mismatches)
+    }
+
+    /// The `(row, column)` of the cells that have to flip for `reflection`
+    /// to be perfect. Each mismatch can be fixed on either side of the line;
+    /// this picks the cell above or left of it.
+    fn smudges(&self, reflection: &Reflection) -> Vec<(usize, usize)> {
+        let lines = self.lines(reflection.orientation);
+        let mut smudges = Vec::new();
+        for (a, b) in self.facing(reflection.orientation, reflection.position) {
+            let mut different = lines[a] ^ lines[b];
+            while different != 0 {
+                let k = different.trailing_zeros() as usize;
+                smudges.push(match reflection.orientation {
+                    Orientation::Horizontal => (a, k),
+                    Orientation::Vertical => (k, a),
+                });
+                different &= different - 1;
+            }
         }
+        smudges.sort();
 
-        step += 1;
+        smudges
     }
 
-    return rows_to_check;
-}
+    fn is_rock(&self, i: usize, j: usize) -> bool {
+        self.rows[i] >> j & 1 == 1
+    }
 
-fn get_mirror(pattern: &Vec<Vec<Element>>, error_threshold: usize) -> usize {
-    for i in get_indexes_from_middle(pattern.len()) {
-        if i == pattern.len() - 1 {
-            continue;
-        }
+    /// Draws the pattern with the mirror line between the rows or columns it
+    /// splits and each smudge as `*`.
+    fn render(&self, reflection: &Reflection) -> String {
+        let smudges = self.smudges(reflection);
+        let width = self.columns.len();
+        let mut result = String::new();
+
+        for i in 0..self.rows.len() {
+            if reflection.orientation == Orientation::Horizontal && i == reflection.position {
+                result.push_str(&"─".repeat(width));
+                result.push('\n');
+            }
 
-        let errors = check_expand(pattern, i as i64, i + 1, error_threshold);
-        if errors == error_threshold {
-            return i + 1;
+            for j in 0..width {
+                if reflection.orientation == Orientation::Vertical && j == reflection.position {
+                    result.push('│');
+                }
+
+                result.push(if smudges.contains(&(i, j)) {
+                    '*'
+                } else if self.is_rock(i, j) {
+                    '#'
+                } else {
+                    '.'
+                });
+            }
+            result.push('\n');
         }
-    }
 
-    return 0;
+        result
+    }
 }
 
 fn main() {
     let input = fs::read_to_string("input.txt").unwrap();
-
-    let mut sum = 0;
-    for pattern_str in input.split("\n\n") {
-        let pattern = parse_pattern(pattern_str).unwrap();
-
-        let rows = get_mirror(&pattern, 1);
-
-        if rows == 0 {
-            let columns = get_mirror(&transpose(&pattern), 1);
-            sum += columns;
-        } else {
-            sum += rows * 100;
+    let patterns = input
+        .split("\n\n")
+        .map(Pattern::parse)
+        .collect::<Result<Vec<Pattern>, ParseSubPatternError>>()
+        .unwrap();
+    let show = env::args().any(|arg| arg == "--show");
+
+    for mismatches in [0, 1] {
+        let mut sum = 0;
+        for pattern in &patterns {
+            let reflection = pattern.mirror(mismatches).expect("No mirror line");
+            sum += reflection.score();
+            if show {
+                println!("{}", pattern.render(&reflection));
+            }
         }
-    }
 
-    println!("{}", sum);
+        println!("{}", sum);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::fs;
 
-    use crate::{get_indexes_from_middle, get_mirror, parse_pattern, transpose, Element};
+    use crate::{Orientation, Pattern, Reflection};
 
-    fn get_pattern(file: &str) -> Vec<Vec<Element>> {
+    fn get_pattern(file: &str) -> Pattern {
         let input = fs::read_to_string(file).unwrap();
-        let pattern = parse_pattern(&input);
+        let pattern = Pattern::parse(&input);
 
         assert!(pattern.is_ok());
 
-        return pattern.unwrap();
+        pattern.unwrap()
+    }
+
+    fn perfect(pattern: &Pattern, orientation: Orientation) -> Option<usize> {
+        pattern
+            .reflections()
+            .iter()
+            .find(|reflection| reflection.orientation == orientation && reflection.mismatches == 0)
+            .map(|reflection| reflection.position)
     }
 
     #[test]
-    fn test_get_indexes_from_middle() {
-        assert_eq!(get_indexes_from_middle(5), vec![2, 1, 3, 0, 4]);
-        assert_eq!(get_indexes_from_middle(6), vec![3, 2, 4, 1, 5, 0]);
+    fn test_parse() {
+        let pattern = Pattern::parse("..#\n#.#").unwrap();
+
+        assert_eq!(pattern.rows, vec![0b100, 0b101]);
+        assert_eq!(pattern.columns, vec![0b10, 0b00, 0b11]);
+        assert!(Pattern::parse("..#\n#.").is_err());
+        assert!(Pattern::parse("..x").is_err());
     }
 
     #[test]
-    fn test_transpose() {
-        let pattern = vec![
-            vec![Element::Ash, Element::Rock],
-            vec![Element::Ash, Element::Rock],
-            vec![Element::Rock, Element::Ash],
-        ];
+    fn test_get_mirror() {
+        use Orientation::{Horizontal, Vertical};
 
-        let expected_transposed = vec![
-            vec![Element::Ash, Element::Ash, Element::Rock],
-            vec![Element::Rock, Element::Rock, Element::Ash],
-        ];
+        assert_eq!(perfect(&get_pattern("test_row_1.txt"), Horizontal), Some(4));
+        assert_eq!(
+            perfect(&get_pattern("test_row_2.txt"), Horizontal),
+            Some(14)
+        );
+        assert_eq!(perfect(&get_pattern("test_column_1.txt"), Horizontal), None);
+        assert_eq!(
+            perfect(&Pattern::parse("#.\n#.\n.#").unwrap(), Horizontal),
+            Some(1)
+        );
 
-        assert_eq!(transpose(&pattern), expected_transposed);
+        assert_eq!(
+            perfect(&get_pattern("test_column_1.txt"), Vertical),
+            Some(5)
+        );
+        assert_eq!(
+            perfect(&get_pattern("test_column_2.txt"), Vertical),
+            Some(10)
+        );
+        assert_eq!(perfect(&get_pattern("test_row_1.txt"), Vertical), None);
     }
 
     #[test]
-    fn test_get_mirror() {
-        assert_eq!(get_mirror(&get_pattern("test_row_1.txt"), 0), 4);
-        assert_eq!(get_mirror(&get_pattern("test_row_2.txt"), 0), 14);
-        assert_eq!(get_mirror(&get_pattern("test_column_1.txt"), 0), 0);
-        assert_eq!(get_mirror(&get_pattern("buggy.txt"), 0), 1);
+    fn test_reflections() {
+        let pattern = get_pattern("test_column_1.txt");
+        let reflections = pattern.reflections();
+
+        assert_eq!(reflections.len(), 6 + 8);
+        assert!(reflections.contains(&Reflection {
+            orientation: Orientation::Horizontal,
+            position: 3,
+            mismatches: 1
+        }));
+        assert!(reflections.contains(&Reflection {
+            orientation: Orientation::Vertical,
+            position: 5,
+            mismatches: 0
+        }));
+    }
+
+    #[test]
+    fn test_smudges() {
+        let pattern = get_pattern("test_column_1.txt");
+        let reflection = pattern.mirror(1).unwrap();
+
+        assert_eq!(reflection.score(), 300);
+        assert_eq!(pattern.smudges(&reflection), vec![(0, 0)]);
+
+        let pattern = get_pattern("test_row_1.txt");
+        let reflection = pattern.mirror(1).unwrap();
+
+        assert_eq!(reflection.score(), 100);
+        assert_eq!(pattern.smudges(&reflection), vec![(0, 4)]);
+    }
+
+    #[test]
+    fn test_render() {
+        let pattern = get_pattern("test_column_1.txt");
 
         assert_eq!(
-            get_mirror(&transpose(&get_pattern("test_column_1.txt")), 0),
-            5
+            pattern.render(&pattern.mirror(1).unwrap()),
+            "*.##..##.
+..#.##.#.
+##......#
+─────────
+##......#
+..#.##.#.
+..##..##.
+#.#.##.#.
+"
         );
         assert_eq!(
-            get_mirror(&transpose(&get_pattern("test_column_2.txt")), 0),
-            10
+            pattern.render(&pattern.mirror(0).unwrap()).lines().next(),
+            Some("#.##.│.##.")
         );
-        assert_eq!(get_mirror(&transpose(&get_pattern("test_row_1.txt")), 0), 0);
+    }
+
+    #[test]
+    fn test_example() {
+        let input = fs::read_to_string("test.txt").unwrap();
+        let patterns: Vec<Pattern> = input
+            .split("\n\n")
+            .map(|pattern| Pattern::parse(pattern).unwrap())
+            .collect();
+
+        let sum = |mismatches| -> usize {
+            patterns
+                .iter()
+                .map(|pattern| pattern.mirror(mismatches).unwrap().score())
+                .sum()
+        };
+        assert_eq!(sum(0), 405);
+        assert_eq!(sum(1), 400);
     }
 }
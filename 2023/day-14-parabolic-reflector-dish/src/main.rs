@@ -1,31 +1,8 @@
-use std::{collections::HashMap, fs};
+use common::cycle;
+use common::visualize::{Colour, Frame, Output, Recorder};
+use std::{env, fs, time::Duration};
 
-fn calculate_load_1(platform: &str) -> usize {
-    let mut load = 0;
-    let rows = platform.lines().collect::<Vec<_>>();
-    let mut previous_tilt = vec![-1; rows[0].len()];
-
-    for (i, row) in rows.iter().enumerate() {
-        for (j, tile) in row.chars().enumerate() {
-            match tile {
-                'O' => {
-                    previous_tilt[j] += 1;
-                    load += rows.len() - previous_tilt[j] as usize;
-                }
-                '#' => {
-                    previous_tilt[j] = i as i32;
-                }
-                _ => (),
-            };
-        }
-
-        // println!("{:?}", previous_tilt);
-    }
-
-    return load;
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Tile {
     Round,
     Square,
@@ -40,118 +17,89 @@ enum Direction {
     East,
 }
 
-fn transpose(platform: Vec<Vec<Tile>>) -> Vec<Vec<Tile>> {
-    let m = platform[0].len();
+/// Rolls every round rock as far as it goes towards `direction`, lane by lane.
+/// `cell(lane, k)` is the `k`-th cell of a lane counted from the edge the rocks
+/// roll to, so all four directions share the same loop.
+fn tilt_platform(platform: &mut [Vec<Tile>], direction: &Direction) {
     let n = platform.len();
-    let mut transposed = vec![vec![Tile::Empty; n]; m];
-
-    for i in 0..n {
-        for j in 0..m {
-            transposed[j][i] = platform[i][j];
-        }
-    }
-
-    return transposed;
-}
-
-fn mirror(platform: Vec<Vec<Tile>>) -> Vec<Vec<Tile>> {
     let m = platform[0].len();
-    let n = platform.len();
-    let mut mirrored = vec![vec![Tile::Empty; m]; n];
-
-    for i in 0..n {
-        for j in 0..m {
-            mirrored[n - 1 - i][m - 1 - j] = platform[i][j];
-        }
-    }
-
-    return mirrored;
-}
-
-fn transform_to_north(platform: Vec<Vec<Tile>>, direction: &Direction) -> Vec<Vec<Tile>> {
-    return match direction {
-        Direction::North => platform,
-        Direction::West => transpose(platform),
-        Direction::South => mirror(platform),
-        Direction::East => transpose(mirror(platform)),
+    let (lanes, length) = match direction {
+        Direction::North | Direction::South => (m, n),
+        Direction::West | Direction::East => (n, m),
+    };
+    let cell = |lane: usize, k: usize| match direction {
+        Direction::North => (k, lane),
+        Direction::South => (n - 1 - k, lane),
+        Direction::West => (lane, k),
+        Direction::East => (lane, m - 1 - k),
     };
-}
-
-fn tilt_platform(platform: Vec<Vec<Tile>>, direction: &Direction) -> Vec<Vec<Tile>> {
-    let platform = transform_to_north(platform, direction);
-
-    let n = platform.len();
-    let m = platform[0].len();
-    let mut new_position = vec![vec![-1; m]; n];
-
-    for j in 0..m {
-        match platform[0][j] {
-            Tile::Round => new_position[0][j] = 0,
-            Tile::Square => new_position[0][j] = 0,
-            Tile::Empty => (),
-        }
-    }
-
-    for i in 1..n {
-        for j in 0..m {
-            new_position[i][j] = match platform[i][j] {
-                Tile::Round => new_position[i - 1][j] + 1,
-                Tile::Square => i as i32,
-                Tile::Empty => new_position[i - 1][j],
-            };
-        }
-    }
 
-    let mut result = vec![vec![Tile::Empty; m]; n];
-    for i in 0..n {
-        for j in 0..m {
+    for lane in 0..lanes {
+        let mut free = 0;
+        for k in 0..length {
+            let (i, j) = cell(lane, k);
             match platform[i][j] {
-                Tile::Round => result[new_position[i][j] as usize][j] = Tile::Round,
-                Tile::Square => result[new_position[i][j] as usize][j] = Tile::Square,
+                Tile::Round => {
+                    platform[i][j] = Tile::Empty;
+                    let (free_i, free_j) = cell(lane, free);
+                    platform[free_i][free_j] = Tile::Round;
+                    free += 1;
+                }
+                Tile::Square => free = k + 1,
                 Tile::Empty => (),
             }
         }
     }
-
-    return transform_to_north(result, direction);
 }
 
-fn cycle_platform(mut platform: Vec<Vec<Tile>>) -> Vec<Vec<Tile>> {
-    let directions = vec![
+fn cycle_platform(platform: &mut [Vec<Tile>]) {
+    for direction in [
         Direction::North,
         Direction::West,
         Direction::South,
         Direction::East,
-    ];
+    ] {
+        tilt_platform(platform, &direction);
+    }
+}
 
-    for direction in directions {
-        platform = tilt_platform(platform, &direction);
+fn frame(platform: &[Vec<Tile>]) -> Frame {
+    let mut frame = Frame::new(platform[0].len(), platform.len());
+    for (i, row) in platform.iter().enumerate() {
+        for (j, tile) in row.iter().enumerate() {
+            match tile {
+                Tile::Round => frame.set(j, i, 'O', Colour::ORANGE),
+                Tile::Square => frame.set(j, i, '#', Colour::GREY),
+                Tile::Empty => frame.set(j, i, '.', Colour::BLACK),
+            }
+        }
     }
 
-    return platform;
+    frame
 }
 
-fn to_string(platform: &Vec<Vec<Tile>>) -> String {
-    let mut result = String::new();
+/// Every tilt of the spin cycles up to the point where the platform has
+/// gone once around the loop it settles into.
+fn record_spins(recorder: &mut Recorder, platform: &[Vec<Tile>]) {
+    let found = cycle::find_cycle(&platform.to_vec(), |platform| cycle_platform(platform));
+    let mut platform = platform.to_vec();
+    recorder.push(frame(&platform));
 
-    for row in platform {
-        for tile in row {
-            result += match tile {
-                Tile::Round => "O",
-                Tile::Square => "#",
-                Tile::Empty => ".",
-            }
+    for _ in 0..found.start + found.length {
+        for direction in [
+            Direction::North,
+            Direction::West,
+            Direction::South,
+            Direction::East,
+        ] {
+            tilt_platform(&mut platform, &direction);
+            recorder.push(frame(&platform));
         }
-
-        result += "\n";
     }
-
-    return result;
 }
 
 fn parse(s: &str) -> Vec<Vec<Tile>> {
-    return s
-        .lines()
+    s.lines()
         .map(|line| {
             line.chars()
                 .map(|ch| match ch {
@@ -162,10 +110,10 @@ fn parse(s: &str) -> Vec<Vec<Tile>> {
                 })
                 .collect()
         })
-        .collect();
+        .collect()
 }
 
-fn calculate_load(platform: &Vec<Vec<Tile>>) -> usize {
+fn calculate_load(platform: &[Vec<Tile>]) -> usize {
     let mut load = 0;
 
     for i in 0..platform.len() {
@@ -176,75 +124,40 @@ fn calculate_load(platform: &Vec<Vec<Tile>>) -> usize {
         }
     }
 
-    return load;
+    load
 }
 
 fn main() {
     let input = fs::read_to_string("input.txt").unwrap();
 
-    let mut platform = parse(&input);
-    let tilted = tilt_platform(platform.clone(), &Direction::North);
+    let platform = parse(&input);
+    let mut tilted = platform.clone();
+    tilt_platform(&mut tilted, &Direction::North);
 
     println!("{}", calculate_load(&tilted));
 
-    let mut platforms: HashMap<String, usize> = HashMap::new();
-    platforms.insert(to_string(&platform), 0);
-
-    let mut end = platform.clone();
-    let mut period = 0;
-    let mut start = 0;
-    for i in 1..1000000001 {
-        if i % 100 == 0 {
-            println!("{i}");
-        }
-
-        let cycled = cycle_platform(platform);
-        let key = to_string(&cycled);
-        if let Some(last_i) = platforms.get(&key) {
-            period = i - last_i;
-            start = *last_i;
-            break;
-        } else {
-            platforms.insert(key, i);
-        }
-
-        platform = cycled;
+    if let Some(output) = Output::from_args(&env::args().collect::<Vec<_>>()) {
+        let mut recorder = Recorder::new()
+            .with_delay(Duration::from_millis(50))
+            .with_scale(2);
+        record_spins(&mut recorder, &platform);
+        recorder.save(&output).unwrap();
     }
 
-    let remain = (1000000000 - start) % period;
-    for i in 0..remain + start {
-        end = cycle_platform(end);
+    let end = cycle::simulate(platform, 1000000000, |platform| cycle_platform(platform));
+    if env::args().any(|arg| arg == "--show") {
+        print!("{}", frame(&end));
     }
-
-    println!("{} {} {}", period, start, calculate_load(&end));
+    println!("{}", calculate_load(&end));
 }
 
 #[cfg(test)]
 mod tests {
     use std::fs;
 
-    use crate::{cycle_platform, parse, tilt_platform, transform_to_north, Direction};
+    use common::cycle;
 
-    #[test]
-    fn test_transform_to_north() {
-        let input = fs::read_to_string("test.txt");
-
-        assert!(input.is_ok());
-
-        let platform = parse(&input.unwrap());
-        let directions = vec![
-            Direction::North,
-            Direction::West,
-            Direction::South,
-            Direction::East,
-        ];
-
-        for direction in directions {
-            let transformed = transform_to_north(platform.clone(), &direction);
-            let original = transform_to_north(transformed.clone(), &direction);
-            assert_eq!(platform, original);
-        }
-    }
+    use crate::{calculate_load, cycle_platform, parse, tilt_platform, Direction};
 
     #[test]
     fn test_tilt_platform() {
@@ -257,9 +170,11 @@ mod tests {
         let platform = parse(&input.unwrap());
         let expected_tilted = parse(&expected.unwrap());
 
-        let tilted = tilt_platform(platform.clone(), &Direction::North);
+        let mut tilted = platform.clone();
+        tilt_platform(&mut tilted, &Direction::North);
 
         assert_eq!(tilted, expected_tilted);
+        assert_eq!(calculate_load(&tilted), 136);
     }
 
     #[test]
@@ -270,24 +185,34 @@ mod tests {
         let expected = fs::read_to_string("test_cycled.txt");
         assert!(expected.is_ok());
 
-        let platform = parse(&input.unwrap());
+        let mut platform = parse(&input.unwrap());
         let expected = parse(&expected.unwrap());
 
-        let cycled = cycle_platform(platform);
-        assert_eq!(cycled, expected);
+        cycle_platform(&mut platform);
+        assert_eq!(platform, expected);
 
         let expected = fs::read_to_string("test_cycled_2.txt");
         assert!(expected.is_ok());
 
-        let cycled2 = cycle_platform(cycled);
+        cycle_platform(&mut platform);
         let expected = parse(&expected.unwrap());
-        assert_eq!(cycled2, expected);
+        assert_eq!(platform, expected);
 
         let expected = fs::read_to_string("test_cycled_3.txt");
         assert!(expected.is_ok());
 
-        let cycled3 = cycle_platform(cycled2);
+        cycle_platform(&mut platform);
         let expected = parse(&expected.unwrap());
-        assert_eq!(cycled3, expected);
+        assert_eq!(platform, expected);
+    }
+
+    #[test]
+    fn test_billion_cycles() {
+        let input = fs::read_to_string("test.txt").unwrap();
+        let platform = cycle::simulate(parse(&input), 1000000000, |platform| {
+            cycle_platform(platform)
+        });
+
+        assert_eq!(calculate_load(&platform), 64);
     }
 }
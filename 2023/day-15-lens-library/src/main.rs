@@ -1,4 +1,9 @@
-use std::{collections::VecDeque, fs};
+use std::{
+    collections::HashMap,
+    env,
+    fmt::{self, Display},
+    fs,
+};
 
 fn hash(s: &str) -> u8 {
     let mut hash = 0;
@@ -9,7 +14,7 @@ fn hash(s: &str) -> u8 {
         hash %= 256;
     }
 
-    return hash as u8;
+    hash as u8
 }
 
 struct Lens {
@@ -17,112 +22,203 @@ struct Lens {
     focal_length: u8,
 }
 
+/// The lenses of a box in the order they went in. Removing a lens only
+/// empties its slot, and `slots` maps each label to its slot, so both replacing
+/// and removing a lens take constant time. The empty slots get squeezed out
+/// once they outnumber the lenses.
 struct Box {
-    lenses: VecDeque<Lens>,
+    lenses: Vec<Option<Lens>>,
+    slots: HashMap<String, usize>,
 }
 
 impl Box {
     fn new() -> Self {
-        return Self {
-            lenses: VecDeque::new(),
-        };
+        Self {
+            lenses: Vec::new(),
+            slots: HashMap::new(),
+        }
     }
 
     fn add_lens(&mut self, lens: Lens) {
-        for l in &mut self.lenses {
-            if l.label == lens.label {
-                l.focal_length = lens.focal_length;
-                return;
+        match self.slots.get(&lens.label) {
+            Some(slot) => self.lenses[*slot] = Some(lens),
+            None => {
+                self.slots.insert(lens.label.clone(), self.lenses.len());
+                self.lenses.push(Some(lens));
             }
         }
-
-        self.lenses.push_back(lens);
     }
 
     fn remove_lens(&mut self, label: &str) {
-        for (i, l) in &mut self.lenses.iter().enumerate() {
-            if l.label == label {
-                self.lenses.remove(i);
-                return;
+        if let Some(slot) = self.slots.remove(label) {
+            self.lenses[slot] = None;
+        }
+
+        if self.lenses.len() > 2 * self.slots.len() {
+            self.lenses.retain(|lens| lens.is_some());
+            for (slot, lens) in self.lenses.iter().flatten().enumerate() {
+                self.slots.insert(lens.label.clone(), slot);
             }
         }
     }
+
+    fn lenses(&self) -> impl Iterator<Item = &Lens> {
+        self.lenses.iter().flatten()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// The box's share of the focusing power, for a box `number` from 1.
+    fn focusing_power(&self, number: usize) -> u64 {
+        self.lenses()
+            .enumerate()
+            .map(|(slot, lens)| (number * (slot + 1) * lens.focal_length as usize) as u64)
+            .sum()
+    }
+}
+
+/// Lays the lenses out the way the puzzle does: `[rn 1] [cm 2]`.
+impl Display for Box {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lenses: Vec<String> = self
+            .lenses()
+            .map(|lens| format!("[{} {}]", lens.label, lens.focal_length))
+            .collect();
+
+        write!(f, "{}", lenses.join(" "))
+    }
 }
 
-struct Boxes {
+/// A row of boxes and the function that picks a box for a label. The puzzle
+/// uses `hash` over 256 boxes, but any function into `0..boxes.len()` works.
+struct Boxes<H: Fn(&str) -> usize> {
     boxes: Vec<Box>,
+    hash: H,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+struct ParseOperationError;
+
 enum Operation {
     Put(Lens),
     Remove(String),
 }
 
 impl Operation {
-    fn new(s: &str) -> Operation {
-        let split = s.split("=").collect::<Vec<_>>();
+    fn new(s: &str) -> Result<Operation, ParseOperationError> {
+        if let Some((label, focal_length)) = s.split_once('=') {
+            return Ok(Operation::Put(Lens {
+                label: label.to_string(),
+                focal_length: focal_length.parse().map_err(|_| ParseOperationError)?,
+            }));
+        }
+
+        s.strip_suffix('-')
+            .map(|label| Operation::Remove(label.to_string()))
+            .ok_or(ParseOperationError)
+    }
 
-        if split.len() == 1 {
-            return Operation::Remove(split[0].strip_suffix("-").unwrap().to_string());
+    fn label(&self) -> &str {
+        match self {
+            Operation::Put(lens) => &lens.label,
+            Operation::Remove(label) => label,
         }
+    }
+}
 
-        return Operation::Put(Lens {
-            label: split[0].to_string(),
-            focal_length: split[1].parse().unwrap(),
-        });
+impl Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::Put(lens) => write!(f, "{}={}", lens.label, lens.focal_length),
+            Operation::Remove(label) => write!(f, "{}-", label),
+        }
     }
 }
 
-impl Boxes {
+impl Boxes<fn(&str) -> usize> {
     fn new() -> Self {
-        let mut result = Boxes { boxes: Vec::new() };
+        Boxes::with_hash(256, |label| hash(label) as usize)
+    }
+}
 
-        for _ in 0..256 {
-            result.boxes.push(Box::new());
+impl<H: Fn(&str) -> usize> Boxes<H> {
+    fn with_hash(count: usize, hash: H) -> Self {
+        Boxes {
+            boxes: (0..count).map(|_| Box::new()).collect(),
+            hash,
         }
-
-        return result;
     }
 
     fn operation(&mut self, operation: Operation) {
+        let index = (self.hash)(operation.label());
         match operation {
-            Operation::Put(lens) => {
-                let label_hash = hash(&lens.label);
-
-                self.boxes[label_hash as usize].add_lens(lens);
-            }
-            Operation::Remove(label) => {
-                let label_hash = hash(&label);
-
-                self.boxes[label_hash as usize].remove_lens(&label);
-            }
+            Operation::Put(lens) => self.boxes[index].add_lens(lens),
+            Operation::Remove(label) => self.boxes[index].remove_lens(&label),
         }
     }
 
+    /// The focusing power of every box that holds a lens, by box index.
+    fn box_powers(&self) -> Vec<(usize, u64)> {
+        self.boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, bx)| !bx.is_empty())
+            .map(|(i, bx)| (i, bx.focusing_power(i + 1)))
+            .collect()
+    }
+
     fn focusing_power(&self) -> u64 {
-        let mut power = 0;
-        for (i, bx) in self.boxes.iter().enumerate() {
-            for (j, lens) in bx.lenses.iter().enumerate() {
-                power += (i + 1) * (j + 1) * lens.focal_length as usize;
-            }
-        }
+        self.box_powers().iter().map(|(_, power)| power).sum()
+    }
+
+    /// The boxes that hold a lens, one per line like `Box 0: [rn 1] [cm 2]`,
+    /// followed by the focusing power they add.
+    fn state(&self) -> String {
+        self.box_powers()
+            .iter()
+            .map(|(i, power)| format!("Box {}: {} (power {})\n", i, self.boxes[*i], power))
+            .collect()
+    }
 
-        return power as u64;
+    /// Runs every operation and records the boxes after each one, like the
+    /// puzzle's `After "rn=1":` walkthrough.
+    fn run_logged(&mut self, operations: Vec<Operation>) -> Vec<String> {
+        operations
+            .into_iter()
+            .map(|operation| {
+                let header = format!("After \"{}\":\n", operation);
+                self.operation(operation);
+                header + &self.state()
+            })
+            .collect()
     }
 }
 
+fn parse(input: &str) -> Result<Vec<Operation>, ParseOperationError> {
+    input.trim().split(",").map(Operation::new).collect()
+}
+
 fn main() {
     let input = fs::read_to_string("input.txt").unwrap();
     println!(
         "{}",
-        input.split(",").map(|part| hash(part) as u64).sum::<u64>()
+        input
+            .trim()
+            .split(",")
+            .map(|part| hash(part) as u64)
+            .sum::<u64>()
     );
 
     let mut boxes = Boxes::new();
-
-    for op in input.split(",") {
-        let operation = Operation::new(op);
-        boxes.operation(operation);
+    let operations = parse(&input).unwrap();
+    if env::args().any(|arg| arg == "--log") {
+        println!("{}", boxes.run_logged(operations).join("\n"));
+    } else {
+        for operation in operations {
+            boxes.operation(operation);
+        }
     }
 
     println!("{}", boxes.focusing_power());
@@ -132,7 +228,7 @@ fn main() {
 mod tests {
     use std::fs;
 
-    use crate::{hash, Boxes, Operation};
+    use crate::{hash, parse, Boxes, Operation, ParseOperationError};
 
     #[test]
     fn test_hash() {
@@ -149,11 +245,65 @@ mod tests {
 
         let mut boxes = Boxes::new();
 
-        for op in input.unwrap().split(",") {
-            let operation = Operation::new(op);
+        for operation in parse(&input.unwrap()).unwrap() {
             boxes.operation(operation);
         }
 
         assert_eq!(boxes.focusing_power(), 145);
+        assert_eq!(boxes.box_powers(), vec![(0, 5), (3, 140)]);
+    }
+
+    #[test]
+    fn test_operation_parsing() {
+        assert_eq!(Operation::new("rn=1").unwrap().to_string(), "rn=1");
+        assert_eq!(Operation::new("cm-").unwrap().to_string(), "cm-");
+        assert!(Operation::new("cm").is_err());
+        assert!(Operation::new("rn=x").is_err());
+        assert!(matches!(parse("rn=1,qp"), Err(ParseOperationError)));
+    }
+
+    #[test]
+    fn test_log() {
+        let input = fs::read_to_string("test.txt").unwrap();
+        let mut boxes = Boxes::new();
+        let log = boxes.run_logged(parse(&input).unwrap());
+
+        assert_eq!(log.len(), 11);
+        assert_eq!(log[0], "After \"rn=1\":\nBox 0: [rn 1] (power 1)\n");
+        assert_eq!(
+            log[3],
+            "After \"cm=2\":\nBox 0: [rn 1] [cm 2] (power 5)\nBox 1: [qp 3] (power 6)\n"
+        );
+        assert_eq!(
+            log[10],
+            "After \"ot=7\":\nBox 0: [rn 1] [cm 2] (power 5)\nBox 3: [ot 7] [ab 5] [pc 6] (power 140)\n"
+        );
+    }
+
+    #[test]
+    fn test_custom_hash() {
+        // Everything in one box turns the boxes into a single ordered list
+        let mut boxes = Boxes::with_hash(1, |_| 0);
+        let input = fs::read_to_string("test.txt").unwrap();
+        boxes.run_logged(parse(&input).unwrap());
+
+        assert_eq!(
+            boxes.boxes[0].to_string(),
+            "[rn 1] [cm 2] [ot 7] [ab 5] [pc 6]"
+        );
+        assert_eq!(boxes.focusing_power(), 1 + 4 + 21 + 20 + 30);
+    }
+
+    #[test]
+    fn test_remove_keeps_order() {
+        let mut boxes = Boxes::with_hash(1, |_| 0);
+        for step in ["a=1", "b=2", "c=3", "a-", "b-", "d=4", "c=5", "e=6", "d-"] {
+            boxes.operation(Operation::new(step).unwrap());
+        }
+
+        assert_eq!(boxes.boxes[0].to_string(), "[c 5] [e 6]");
+        // Removing `b` squeezed out the slots `a` and `b` left behind
+        assert_eq!(boxes.boxes[0].lenses.len(), 3);
+        assert_eq!(boxes.boxes[0].slots["e"], 2);
     }
 }
@@ -1,4 +1,10 @@
-use std::{fs, vec};
+use common::visualize::{Colour, Frame, Output, Recorder};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    fmt::{self, Display},
+    fs,
+};
 
 #[derive(Debug, Clone)]
 enum TileType {
@@ -9,7 +15,7 @@ enum TileType {
     HorizontalSplitter,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 enum Direction {
     Up,
     Down,
@@ -19,16 +25,16 @@ enum Direction {
 
 impl Direction {
     fn next_coord(&self, i: i64, j: i64) -> (i64, i64) {
-        return match self {
+        match self {
             Direction::Up => (i - 1, j),
             Direction::Down => (i + 1, j),
             Direction::Left => (i, j - 1),
             Direction::Right => (i, j + 1),
-        };
+        }
     }
 
     fn next_direction(&self, tile_type: &TileType) -> Vec<Direction> {
-        return match (tile_type, self) {
+        match (tile_type, self) {
             (TileType::BackMirror, Direction::Up) => vec![Direction::Right],
             (TileType::BackMirror, Direction::Down) => vec![Direction::Left],
             (TileType::BackMirror, Direction::Right) => vec![Direction::Up],
@@ -43,38 +49,39 @@ impl Direction {
             (TileType::VerticalSplitter, Direction::Left | Direction::Right) => {
                 vec![Direction::Up, Direction::Down]
             }
-            _ => vec![self.clone()],
-        };
+            _ => vec![*self],
+        }
     }
 }
 
-impl ToString for Direction {
-    fn to_string(&self) -> String {
-        return match self {
+impl Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arrow = match self {
             Direction::Up => "^",
             Direction::Down => "v",
             Direction::Left => "<",
             Direction::Right => ">",
-        }
-        .to_string();
+        };
+
+        write!(f, "{}", arrow)
     }
 }
 
 impl From<usize> for Direction {
     fn from(value: usize) -> Self {
-        return match value {
+        match value {
             0 => Direction::Up,
             1 => Direction::Down,
             2 => Direction::Left,
             3 => Direction::Right,
             n => panic!("Unkown enum direction value: {n}"),
-        };
+        }
     }
 }
 
-impl Into<usize> for Direction {
-    fn into(self) -> usize {
-        return self as usize;
+impl From<Direction> for usize {
+    fn from(direction: Direction) -> Self {
+        direction as usize
     }
 }
 
@@ -86,15 +93,14 @@ struct Tile {
 
 impl Tile {
     fn new(tile_type: TileType) -> Tile {
-        return Tile {
+        Tile {
             tile_type,
             visited: [false; 4],
-        };
+        }
     }
 
     fn parse(s: &str) -> Vec<Vec<Tile>> {
-        return s
-            .lines()
+        s.lines()
             .map(|line| {
                 line.chars()
                     .map(|ch| match ch {
@@ -107,120 +113,514 @@ impl Tile {
                     })
                     .collect()
             })
-            .collect();
+            .collect()
     }
 
-    fn tiles_to_string(tiles: &Vec<Vec<Tile>>) -> String {
-        return tiles
+    fn tiles_to_string(tiles: &[Vec<Tile>]) -> String {
+        tiles
             .iter()
             .map(|row| {
                 row.iter()
                     .fold(String::new(), |res, tile| res + &tile.to_string())
             })
-            .fold(String::new(), |res, row| res + &row + "\n");
+            .fold(String::new(), |res, row| res + &row + "\n")
+    }
+
+    /// Whether a beam going `direction` comes out of this tile as two beams.
+    fn splits(&self, direction: Direction) -> bool {
+        direction.next_direction(&self.tile_type).len() == 2
     }
+}
+
+/// Draws the tile the way the puzzle does: mirrors and splitters as they
+/// are, and empty tiles with the direction of the beam crossing them, or how
+/// many beams do if there are several.
+impl Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let directions: Vec<Direction> = self
+            .visited
+            .iter()
+            .enumerate()
+            .filter(|(_, visited)| **visited)
+            .map(|(i, _)| Direction::from(i))
+            .collect();
 
-    fn is_visited(&self) -> bool {
-        return self.visited.iter().any(|d| *d);
+        match (&self.tile_type, directions.len()) {
+            (TileType::BackMirror, _) => write!(f, "/"),
+            (TileType::ForwardMirror, _) => write!(f, "\\"),
+            (TileType::VerticalSplitter, _) => write!(f, "|"),
+            (TileType::HorizontalSplitter, _) => write!(f, "-"),
+            (TileType::Empty, 0) => write!(f, "."),
+            (TileType::Empty, 1) => write!(f, "{}", directions[0]),
+            (TileType::Empty, n) => write!(f, "{}", n),
+        }
     }
+}
 
-    fn to_string(&self) -> String {
-        let mut result = String::from(".");
+/// Moves every beam in `front` on by one tile. The tiles the beams are on
+/// are marked as visited, and a beam that has already been through its tile
+/// in the same direction, or that leaves the grid, is dropped.
+fn advance(
+    tiles: &mut [Vec<Tile>],
+    front: Vec<(usize, usize, Direction)>,
+) -> Vec<(usize, usize, Direction)> {
+    let mut next_front = Vec::new();
+    for (i, j, direction) in front {
+        let tile = &mut tiles[i][j];
+        if tile.visited[usize::from(direction)] {
+            continue;
+        }
+        tile.visited[usize::from(direction)] = true;
 
-        for (i, direction) in self.visited.iter().enumerate() {
-            if *direction && result == "." {
-                result = Direction::from(i).to_string();
-            } else if *direction && result.len() == 1 {
-                result = String::from("2");
-            } else if *direction {
-                result = result.parse::<usize>().unwrap().to_string();
+        for new_direction in direction.next_direction(&tile.tile_type) {
+            let (new_i, new_j) = new_direction.next_coord(i as i64, j as i64);
+            if new_i >= 0
+                && (new_i as usize) < tiles.len()
+                && new_j >= 0
+                && (new_j as usize) < tiles[0].len()
+            {
+                next_front.push((new_i as usize, new_j as usize, new_direction));
             }
         }
-
-        return result;
     }
+
+    next_front
 }
 
-fn beam(tiles: &mut Vec<Vec<Tile>>, i: i64, j: i64, direction: Direction) {
-    if i < 0 || i as usize >= tiles.len() || j < 0 || j as usize >= tiles[0].len() {
-        return;
+fn beam(tiles: &mut [Vec<Tile>], entry: Entry) {
+    let mut front = vec![(entry.i, entry.j, entry.direction)];
+    while !front.is_empty() {
+        front = advance(tiles, front);
     }
+}
 
-    let dir: usize = direction.clone().into();
+/// A set of cells, one bit per cell in row-major order.
+#[derive(Debug, Clone)]
+struct Cells {
+    bits: Vec<u64>,
+}
+
+impl Cells {
+    fn new(size: usize) -> Self {
+        Cells {
+            bits: vec![0; size.div_ceil(64)],
+        }
+    }
 
-    let i = i as usize;
-    let j = j as usize;
-    if tiles[i][j].visited[dir] {
-        return;
+    fn insert(&mut self, cell: usize) {
+        self.bits[cell / 64] |= 1 << (cell % 64);
     }
 
-    tiles[i][j].visited[dir] = true;
+    fn union_with(&mut self, other: &Cells) {
+        for (bits, other) in self.bits.iter_mut().zip(&other.bits) {
+            *bits |= other;
+        }
+    }
 
-    let new_directions = direction.next_direction(&tiles[i][j].tile_type);
-    for new_direction in new_directions {
-        let (new_i, new_j) = new_direction.next_coord(i as i64, j as i64);
-        beam(tiles, new_i, new_j, new_direction);
+    fn count(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|bits| bits.count_ones() as usize)
+            .sum()
     }
 }
 
-fn energized_count(tiles: &Vec<Vec<Tile>>) -> usize {
-    return tiles
-        .iter()
-        .map(|row| row.iter().filter(|tile| tile.is_visited()).count())
-        .sum();
+/// Where a beam goes until something splits it: the cells it crosses and the
+/// splitter it ends at, unless it leaves the contraption first.
+struct Segment {
+    cells: Vec<usize>,
+    splitter: Option<usize>,
 }
 
-fn main() {
-    let input = fs::read_to_string("input.txt").unwrap();
-    let tiles = Tile::parse(&input);
+/// A beam entering the contraption at a cell on its border.
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    i: usize,
+    j: usize,
+    direction: Direction,
+}
+
+/// The contraption as a graph between the splitters that actually split a
+/// beam. Everything between two splits is fixed, so each splitter lights up
+/// the same cells whichever way the beam reached it. Splitters that lead back
+/// to each other light up the same cells too, so they share one set per
+/// strongly connected component, and those sets are built sinks first by
+/// merging the sets of the components they lead to.
+struct BeamGraph {
+    width: usize,
+    height: usize,
+    splitters: HashMap<(usize, usize), usize>,
+    component: Vec<usize>,
+    energised: Vec<Cells>,
+}
+
+impl BeamGraph {
+    fn new(tiles: &[Vec<Tile>]) -> Self {
+        let height = tiles.len();
+        let width = tiles[0].len();
+        let mut positions = Vec::new();
+        let mut splitters = HashMap::new();
+        for (i, row) in tiles.iter().enumerate() {
+            for (j, tile) in row.iter().enumerate() {
+                if matches!(
+                    tile.tile_type,
+                    TileType::VerticalSplitter | TileType::HorizontalSplitter
+                ) {
+                    splitters.insert((i, j), positions.len());
+                    positions.push((i, j));
+                }
+            }
+        }
+
+        let mut graph = BeamGraph {
+            width,
+            height,
+            splitters,
+            component: Vec::new(),
+            energised: Vec::new(),
+        };
+
+        let mut own = Vec::new();
+        let mut edges = Vec::new();
+        for (i, j) in &positions {
+            let incoming = match tiles[*i][*j].tile_type {
+                TileType::VerticalSplitter => Direction::Right,
+                _ => Direction::Up,
+            };
+
+            let mut cells = Cells::new(width * height);
+            cells.insert(i * width + j);
+            let mut next = Vec::new();
+            for direction in incoming.next_direction(&tiles[*i][*j].tile_type) {
+                let (next_i, next_j) = direction.next_coord(*i as i64, *j as i64);
+                let segment = graph.trace(tiles, next_i, next_j, direction);
+                for cell in segment.cells {
+                    cells.insert(cell);
+                }
+                next.extend(segment.splitter);
+            }
+            own.push(cells);
+            edges.push(next);
+        }
+
+        let components = strongly_connected_components(&edges);
+        graph.component = vec![0; positions.len()];
+        for (index, members) in components.iter().enumerate() {
+            for member in members {
+                graph.component[*member] = index;
+            }
+        }
+
+        for (index, members) in components.iter().enumerate() {
+            let mut cells = Cells::new(width * height);
+            for member in members {
+                cells.union_with(&own[*member]);
+                for next in &edges[*member] {
+                    if graph.component[*next] != index {
+                        cells.union_with(&graph.energised[graph.component[*next]]);
+                    }
+                }
+            }
+            graph.energised.push(cells);
+        }
+
+        graph
+    }
+
+    /// Follows a beam from `(i, j)` until it leaves the contraption or
+    /// reaches a splitter that splits it. A beam that runs longer than there
+    /// are `(cell, direction)` states is going round in circles of mirrors and
+    /// has already crossed every cell it ever will.
+    fn trace(
+        &self,
+        tiles: &[Vec<Tile>],
+        mut i: i64,
+        mut j: i64,
+        mut direction: Direction,
+    ) -> Segment {
+        let mut cells = Vec::new();
+        for _ in 0..4 * self.width * self.height {
+            if i < 0 || i as usize >= self.height || j < 0 || j as usize >= self.width {
+                break;
+            }
+
+            let tile = &tiles[i as usize][j as usize];
+            let cell = i as usize * self.width + j as usize;
+            cells.push(cell);
+            if tile.splits(direction) {
+                return Segment {
+                    cells,
+                    splitter: Some(self.splitters[&(i as usize, j as usize)]),
+                };
+            }
 
-    let mut max = 0;
-    for i in 0..tiles.len() {
-        let mut left_start = tiles.clone();
-        beam(&mut left_start, i as i64, 0, Direction::Right);
-        let energized = energized_count(&left_start);
+            direction = direction.next_direction(&tile.tile_type)[0];
+            (i, j) = direction.next_coord(i, j);
+        }
+
+        Segment {
+            cells,
+            splitter: None,
+        }
+    }
 
-        if energized > max {
-            max = energized;
+    fn energised(&self, tiles: &[Vec<Tile>], entry: Entry) -> Cells {
+        let segment = self.trace(tiles, entry.i as i64, entry.j as i64, entry.direction);
+        let mut cells = match segment.splitter {
+            Some(splitter) => self.energised[self.component[splitter]].clone(),
+            None => Cells::new(self.width * self.height),
+        };
+        for cell in segment.cells {
+            cells.insert(cell);
         }
 
-        let mut right_start = tiles.clone();
-        beam(
-            &mut right_start,
-            i as i64,
-            (tiles[0].len() - 1) as i64,
-            Direction::Left,
-        );
-        let energized = energized_count(&right_start);
+        cells
+    }
 
-        if energized > max {
-            max = energized;
+    /// Every way a beam can enter from the border, pointing inwards.
+    fn entries(&self) -> Vec<Entry> {
+        let mut entries = Vec::new();
+        for i in 0..self.height {
+            entries.push(Entry {
+                i,
+                j: 0,
+                direction: Direction::Right,
+            });
+            entries.push(Entry {
+                i,
+                j: self.width - 1,
+                direction: Direction::Left,
+            });
         }
+        for j in 0..self.width {
+            entries.push(Entry {
+                i: 0,
+                j,
+                direction: Direction::Down,
+            });
+            entries.push(Entry {
+                i: self.height - 1,
+                j,
+                direction: Direction::Up,
+            });
+        }
+
+        entries
     }
 
-    for j in 0..tiles[0].len() {
-        let mut up_start = tiles.clone();
-        beam(&mut up_start, 0, j as i64, Direction::Down);
-        let energized = energized_count(&up_start);
+    fn best(&self, tiles: &[Vec<Tile>]) -> (Entry, usize) {
+        self.entries()
+            .into_iter()
+            .map(|entry| (entry, self.energised(tiles, entry).count()))
+            .max_by_key(|(_, count)| *count)
+            .unwrap()
+    }
+}
+
+/// Tarjan's algorithm. Components come out with the ones they lead to before
+/// them, so each can be finished from components that are already done.
+fn strongly_connected_components(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    struct State<'a> {
+        edges: &'a [Vec<usize>],
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    fn connect(state: &mut State, node: usize) {
+        state.index[node] = Some(state.next);
+        state.low[node] = state.next;
+        state.next += 1;
+        state.stack.push(node);
+        state.on_stack[node] = true;
+
+        for next in state.edges[node].clone() {
+            match state.index[next] {
+                None => {
+                    connect(state, next);
+                    state.low[node] = state.low[node].min(state.low[next]);
+                }
+                Some(index) if state.on_stack[next] => {
+                    state.low[node] = state.low[node].min(index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if Some(state.low[node]) == state.index[node] {
+            let mut component = Vec::new();
+            loop {
+                let member = state.stack.pop().unwrap();
+                state.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            state.components.push(component);
+        }
+    }
 
-        if energized > max {
-            max = energized;
+    let mut state = State {
+        edges,
+        index: vec![None; edges.len()],
+        low: vec![0; edges.len()],
+        on_stack: vec![false; edges.len()],
+        stack: Vec::new(),
+        next: 0,
+        components: Vec::new(),
+    };
+    for node in 0..edges.len() {
+        if state.index[node].is_none() {
+            connect(&mut state, node);
         }
+    }
+
+    state.components
+}
 
-        let mut down_start = tiles.clone();
-        beam(
-            &mut up_start,
-            (tiles.len() - 1) as i64,
-            j as i64,
-            Direction::Up,
-        );
-        let energized = energized_count(&down_start);
+/// The puzzle's picture of the beams for one entry point.
+fn render(tiles: &[Vec<Tile>], entry: Entry) -> String {
+    let mut tiles = tiles.to_vec();
+    beam(&mut tiles, entry);
 
-        if energized > max {
-            max = energized;
+    Tile::tiles_to_string(&tiles)
+}
+
+/// The tiles as `Tile` draws them, with energised tiles lit up and the
+/// front of the beams, `front`, brightest.
+fn frame(tiles: &[Vec<Tile>], front: &HashSet<(usize, usize)>) -> Frame {
+    let mut frame = Frame::new(tiles[0].len(), tiles.len());
+    for (i, row) in tiles.iter().enumerate() {
+        for (j, tile) in row.iter().enumerate() {
+            let symbol = tile.to_string().chars().next().unwrap();
+            let colour = if front.contains(&(i, j)) {
+                Colour::WHITE
+            } else if tile.visited.iter().any(|visited| *visited) {
+                Colour::ORANGE
+            } else if matches!(tile.tile_type, TileType::Empty) {
+                Colour::GREY
+            } else {
+                Colour::CYAN
+            };
+            frame.set(j, i, symbol, colour);
         }
     }
 
-    // println!("{}", Tile::tiles_to_string(&tiles));
-    println!("{}", max);
+    frame
+}
+
+/// Follows the beams from `entry` one tile at a time, so each frame shows
+/// how far they got.
+fn record_beams(recorder: &mut Recorder, tiles: &[Vec<Tile>], entry: Entry) {
+    let mut tiles = tiles.to_vec();
+    let mut front = vec![(entry.i, entry.j, entry.direction)];
+    recorder.push(frame(&tiles, &HashSet::new()));
+
+    while !front.is_empty() {
+        front = advance(&mut tiles, front);
+        let cells = front.iter().map(|(i, j, _)| (*i, *j)).collect();
+        recorder.push(frame(&tiles, &cells));
+    }
+}
+
+fn main() {
+    let input = fs::read_to_string("input.txt").unwrap();
+    let tiles = Tile::parse(&input);
+    let graph = BeamGraph::new(&tiles);
+    let show = env::args().any(|arg| arg == "--show");
+    let visualize = Output::from_args(&env::args().collect::<Vec<_>>());
+    let mut recorder = Recorder::new();
+
+    let start = Entry {
+        i: 0,
+        j: 0,
+        direction: Direction::Right,
+    };
+    println!("{}", graph.energised(&tiles, start).count());
+    if show {
+        println!("{}", render(&tiles, start));
+    }
+    if let Some(output) = &visualize {
+        record_beams(&mut recorder, &tiles, start);
+        recorder.save(&output.named("start")).unwrap();
+        recorder.clear();
+    }
+
+    let (best, count) = graph.best(&tiles);
+    println!(
+        "{} from ({}, {}) going {}",
+        count, best.i, best.j, best.direction
+    );
+    if show {
+        println!("{}", render(&tiles, best));
+    }
+    if let Some(output) = &visualize {
+        record_beams(&mut recorder, &tiles, best);
+        recorder.save(&output.named("best")).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{beam, BeamGraph, Direction, Entry, Tile};
+
+    fn example() -> Vec<Vec<Tile>> {
+        Tile::parse(&fs::read_to_string("test.txt").unwrap())
+    }
+
+    /// The energised tiles found by following every beam one tile at a time.
+    fn energised_by_beam(tiles: &[Vec<Tile>], entry: Entry) -> usize {
+        let mut tiles = tiles.to_vec();
+        beam(&mut tiles, entry);
+
+        tiles
+            .iter()
+            .flatten()
+            .filter(|tile| tile.visited.iter().any(|visited| *visited))
+            .count()
+    }
+
+    #[test]
+    fn test_energised() {
+        let tiles = example();
+        let graph = BeamGraph::new(&tiles);
+        let start = Entry {
+            i: 0,
+            j: 0,
+            direction: Direction::Right,
+        };
+
+        assert_eq!(graph.energised(&tiles, start).count(), 46);
+    }
+
+    #[test]
+    fn test_best() {
+        let tiles = example();
+        let (entry, count) = BeamGraph::new(&tiles).best(&tiles);
+
+        assert_eq!(count, 51);
+        assert_eq!((entry.i, entry.j), (0, 3));
+        assert!(matches!(entry.direction, Direction::Down));
+    }
+
+    #[test]
+    fn test_matches_beam() {
+        let tiles = example();
+        let graph = BeamGraph::new(&tiles);
+
+        for entry in graph.entries() {
+            assert_eq!(
+                graph.energised(&tiles, entry).count(),
+                energised_by_beam(&tiles, entry),
+                "{:?}",
+                entry
+            );
+        }
+    }
 }
@@ -1,9 +1,11 @@
 use std::{
     collections::{BinaryHeap, HashMap},
+    env,
+    fmt::{self, Display},
     fs,
 };
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 enum Direction {
     Up,
     Right,
@@ -31,71 +33,174 @@ impl Direction {
     }
 }
 
-fn min_heat_loss(map: &Vec<Vec<u8>>, min_step: i64, max_step: i64) -> u64 {
-    let N = map.len();
-    let M = map[0].len();
+impl Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arrow = match self {
+            Direction::Up => '^',
+            Direction::Right => '>',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+        };
 
-    // our unique "vertex" is based on the position of the cell and the direction
-    // from which we entered the cell.
-    let mut pq: BinaryHeap<(i64, (usize, usize), Direction)> = BinaryHeap::new();
-    let mut best: HashMap<(usize, usize, Direction), i64> = HashMap::new();
+        write!(f, "{}", arrow)
+    }
+}
 
-    pq.push((0, (0, 0), Direction::Right));
-    pq.push((0, (0, 0), Direction::Down));
+/// How far the crucible has to go in a straight line before it can turn or
+/// stop, and how far it can go at most.
+struct Crucible {
+    min_step: i64,
+    max_step: i64,
+}
 
-    while let Some((cost, (i, j), direction)) = pq.pop() {
-        let cost = -cost;
-        if i == N - 1 && j == M - 1 {
-            return cost as u64;
-        }
+/// The end can't be reached with the crucible's run lengths.
+#[derive(Debug, PartialEq, Eq)]
+struct Unreachable;
+
+/// The cheapest way through the map, one move per block.
+#[derive(Debug)]
+struct Route {
+    start: (usize, usize),
+    heat_loss: u64,
+    moves: Vec<Direction>,
+}
+
+type Vertex = (usize, usize, Direction);
+
+impl Crucible {
+    fn new(min_step: i64, max_step: i64) -> Self {
+        Crucible { min_step, max_step }
+    }
 
-        let key = (i, j, direction.clone());
-        if best.contains_key(&key) && best[&key] < cost {
-            continue;
+    fn route(
+        &self,
+        map: &[Vec<u8>],
+        start: (usize, usize),
+        end: (usize, usize),
+    ) -> Result<Route, Unreachable> {
+        let n = map.len();
+        let m = map[0].len();
+
+        // our unique "vertex" is based on the position of the cell and the direction
+        // from which we entered the cell.
+        let mut pq: BinaryHeap<(i64, (usize, usize), Direction)> = BinaryHeap::new();
+        let mut best: HashMap<Vertex, i64> = HashMap::new();
+        let mut previous: HashMap<Vertex, Vertex> = HashMap::new();
+
+        // Pretending to have come in going right and going down lets the
+        // first run go any way.
+        for direction in [Direction::Right, Direction::Down] {
+            pq.push((0, start, direction));
+            best.insert((start.0, start.1, direction), 0);
         }
 
-        for next_direction in vec![
-            Direction::Up,
-            Direction::Right,
-            Direction::Down,
-            Direction::Left,
-        ] {
-            //The main idea is when we change the direction to add as much steps
-            // as we can to the priority queue. To be precise we add the cells
-            // which are between min_steps and max_steps away from the current
-            // cell, but in the new direction
-            if next_direction == direction || direction.is_opposite(&next_direction) {
-                continue;
+        while let Some((cost, (i, j), direction)) = pq.pop() {
+            let cost = -cost;
+            if (i, j) == end {
+                return Ok(Route {
+                    start,
+                    heat_loss: cost as u64,
+                    moves: moves(&previous, (i, j, direction)),
+                });
             }
 
-            let mut new_cost = cost;
-            let (diff_i, diff_j) = next_direction.get_diff();
-            for distance in 1..=max_step {
-                let next_i = i as i64 + diff_i * distance;
-                let next_j = j as i64 + diff_j * distance;
+            let key = (i, j, direction);
+            if best.contains_key(&key) && best[&key] < cost {
+                continue;
+            }
 
-                if next_i < 0 || next_i >= N as i64 || next_j < 0 || next_j >= M as i64 {
-                    break;
+            for next_direction in [
+                Direction::Up,
+                Direction::Right,
+                Direction::Down,
+                Direction::Left,
+            ] {
+                //The main idea is when we change the direction to add as much steps
+                // as we can to the priority queue. To be precise we add the cells
+                // which are between min_steps and max_steps away from the current
+                // cell, but in the new direction
+                if next_direction == direction || direction.is_opposite(&next_direction) {
+                    continue;
                 }
 
-                let next_i = next_i as usize;
-                let next_j = next_j as usize;
+                let mut new_cost = cost;
+                let (diff_i, diff_j) = next_direction.get_diff();
+                for distance in 1..=self.max_step {
+                    let next_i = i as i64 + diff_i * distance;
+                    let next_j = j as i64 + diff_j * distance;
 
-                new_cost += map[next_i][next_j] as i64;
-                if distance < min_step {
-                    continue;
-                }
+                    if next_i < 0 || next_i >= n as i64 || next_j < 0 || next_j >= m as i64 {
+                        break;
+                    }
 
-                let key = (next_i, next_j, next_direction.clone());
-                if !best.contains_key(&key) || new_cost < best[&key] {
-                    best.insert(key, new_cost);
-                    pq.push((-new_cost, (next_i, next_j), next_direction.clone()));
+                    let next_i = next_i as usize;
+                    let next_j = next_j as usize;
+
+                    new_cost += map[next_i][next_j] as i64;
+                    if distance < self.min_step {
+                        continue;
+                    }
+
+                    let next_key = (next_i, next_j, next_direction);
+                    if !best.contains_key(&next_key) || new_cost < best[&next_key] {
+                        best.insert(next_key, new_cost);
+                        previous.insert(next_key, key);
+                        pq.push((-new_cost, (next_i, next_j), next_direction));
+                    }
                 }
             }
         }
+
+        Err(Unreachable)
+    }
+}
+
+/// Walks the predecessors back from `end`. Every vertex was reached by a
+/// straight run from its predecessor, as long as the distance between them.
+fn moves(previous: &HashMap<Vertex, Vertex>, end: Vertex) -> Vec<Direction> {
+    let mut moves = Vec::new();
+    let mut current = end;
+    while let Some(before) = previous.get(&current) {
+        let distance = current.0.abs_diff(before.0) + current.1.abs_diff(before.1);
+        moves.extend((0..distance).map(|_| current.2));
+        current = *before;
     }
+    moves.reverse();
 
-    return u64::MAX;
+    moves
+}
+
+impl Route {
+    /// The straight runs the route is made of, with their lengths.
+    fn runs(&self) -> Vec<(Direction, usize)> {
+        let mut runs: Vec<(Direction, usize)> = Vec::new();
+        for direction in &self.moves {
+            match runs.last_mut() {
+                Some((last, length)) if last == direction => *length += 1,
+                _ => runs.push((*direction, 1)),
+            }
+        }
+
+        runs
+    }
+
+    /// The heat map with every block the route enters replaced by the arrow
+    /// of the move into it, like in the puzzle.
+    fn render(&self, map: &[Vec<u8>]) -> String {
+        let mut cells: Vec<Vec<String>> = map
+            .iter()
+            .map(|row| row.iter().map(|heat| heat.to_string()).collect())
+            .collect();
+
+        let (mut i, mut j) = (self.start.0 as i64, self.start.1 as i64);
+        for direction in &self.moves {
+            let (diff_i, diff_j) = direction.get_diff();
+            (i, j) = (i + diff_i, j + diff_j);
+            cells[i as usize][j as usize] = direction.to_string();
+        }
+
+        cells.iter().map(|row| row.concat() + "\n").collect()
+    }
 }
 
 fn parse(s: &str) -> Vec<Vec<u8>> {
@@ -103,17 +208,145 @@ fn parse(s: &str) -> Vec<Vec<u8>> {
 
     for (i, line) in s.lines().enumerate() {
         map.push(Vec::new());
-        for (_, ch) in line.chars().enumerate() {
+        for ch in line.chars() {
             map[i].push(ch.to_digit(10).unwrap() as u8);
         }
     }
 
-    return map;
+    map
+}
+
+fn parse_cell(s: &str) -> Option<(usize, usize)> {
+    let (i, j) = s.split_once(',')?;
+    Some((i.parse().ok()?, j.parse().ok()?))
 }
 
 fn main() {
     let input = fs::read_to_string("input.txt").unwrap();
     let map = parse(&input);
-    println!("{}", min_heat_loss(&map, 1, 3));
-    println!("{}", min_heat_loss(&map, 4, 10));
+
+    let args: Vec<String> = env::args().collect();
+    let flag = |name: &str| match args.iter().position(|arg| arg == name) {
+        Some(position) => args
+            .get(position + 1)
+            .and_then(|cell| parse_cell(cell))
+            .map(Some)
+            .ok_or_else(|| format!("Usage: {} <i>,<j>", name)),
+        None => Ok(None),
+    };
+    let (start, end) = match (flag("--start"), flag("--end")) {
+        (Ok(start), Ok(end)) => (
+            start.unwrap_or((0, 0)),
+            end.unwrap_or((map.len() - 1, map[0].len() - 1)),
+        ),
+        (Err(usage), _) | (_, Err(usage)) => {
+            println!("{}", usage);
+            return;
+        }
+    };
+    let show = args.iter().any(|arg| arg == "--show");
+
+    for crucible in [Crucible::new(1, 3), Crucible::new(4, 10)] {
+        match crucible.route(&map, start, end) {
+            Ok(route) => {
+                println!("{}", route.heat_loss);
+                let runs: Vec<String> = route
+                    .runs()
+                    .iter()
+                    .map(|(direction, length)| format!("{}{}", direction, length))
+                    .collect();
+                println!("{}", runs.join(" "));
+                if show {
+                    println!("{}", route.render(&map));
+                }
+            }
+            Err(Unreachable) => println!("unreachable"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{parse, Crucible, Direction, Unreachable};
+
+    fn example() -> Vec<Vec<u8>> {
+        parse(&fs::read_to_string("test.txt").unwrap())
+    }
+
+    fn corner(map: &[Vec<u8>]) -> (usize, usize) {
+        (map.len() - 1, map[0].len() - 1)
+    }
+
+    #[test]
+    fn test_heat_loss() {
+        let map = example();
+
+        let route = Crucible::new(1, 3).route(&map, (0, 0), corner(&map));
+        assert_eq!(route.unwrap().heat_loss, 102);
+
+        let route = Crucible::new(4, 10).route(&map, (0, 0), corner(&map));
+        assert_eq!(route.unwrap().heat_loss, 94);
+    }
+
+    #[test]
+    fn test_runs() {
+        let map = example();
+        let route = Crucible::new(4, 10)
+            .route(&map, (0, 0), corner(&map))
+            .unwrap();
+
+        assert_eq!(
+            route.runs(),
+            vec![
+                (Direction::Right, 8),
+                (Direction::Down, 4),
+                (Direction::Right, 4),
+                (Direction::Down, 8)
+            ]
+        );
+
+        // Every run of the normal crucible stays within its limits, and the
+        // heat lost along the moves adds up to the total.
+        let crucible = Crucible::new(1, 3);
+        let route = crucible.route(&map, (0, 0), corner(&map)).unwrap();
+        assert!(route
+            .runs()
+            .iter()
+            .all(|(_, length)| (1..=3).contains(length)));
+
+        let (mut i, mut j) = (0i64, 0i64);
+        let mut heat_loss = 0;
+        for direction in &route.moves {
+            let (diff_i, diff_j) = direction.get_diff();
+            (i, j) = (i + diff_i, j + diff_j);
+            heat_loss += map[i as usize][j as usize] as u64;
+        }
+        assert_eq!((i as usize, j as usize), corner(&map));
+        assert_eq!(heat_loss, route.heat_loss);
+    }
+
+    #[test]
+    fn test_start_is_end() {
+        let map = example();
+        let route = Crucible::new(4, 10).route(&map, (3, 5), (3, 5)).unwrap();
+
+        assert_eq!(route.heat_loss, 0);
+        assert!(route.moves.is_empty());
+        assert!(route.runs().is_empty());
+    }
+
+    #[test]
+    fn test_unreachable() {
+        // Too small for a single run of four blocks
+        let map = parse("111\n111\n111");
+        assert_eq!(
+            Crucible::new(4, 10)
+                .route(&map, (0, 0), (2, 2))
+                .unwrap_err(),
+            Unreachable
+        );
+        assert!(Crucible::new(1, 3).route(&map, (0, 0), (2, 2)).is_ok());
+    }
 }
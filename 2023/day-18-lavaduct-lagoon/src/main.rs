@@ -1,75 +1,29 @@
-use std::{fmt::Display, fs};
-
-#[derive(Debug, PartialEq, Eq)]
-enum Direction {
-    Up,
-    Right,
-    Down,
-    Left,
-}
-
-impl Direction {
-    fn parse(s: &str) -> Direction {
-        match s {
-            "U" => Direction::Up,
-            "R" => Direction::Right,
-            "D" => Direction::Down,
-            "L" => Direction::Left,
-            _ => panic!("Unknown direction: {}", s),
-        }
-    }
-
-    fn parse_2(ch: &char) -> Direction {
-        match ch {
-            '3' => Direction::Up,
-            '0' => Direction::Right,
-            '1' => Direction::Down,
-            '2' => Direction::Left,
-            _ => panic!("Unknown direction: {}", ch),
-        }
-    }
-}
-
-impl Display for Direction {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Direction::Up => "U",
-                Direction::Right => "R",
-                Direction::Down => "D",
-                Direction::Left => "L",
-            }
-        )
+use common::{
+    polygon::{Direction, Polygon},
+    visualize::{Output, Recorder},
+};
+use std::{env, fs, time::Duration};
+
+/// The most frames `--visualize` takes to dig the trench.
+const MAX_FRAMES: usize = 200;
+
+fn parse_direction(s: &str) -> Direction {
+    match s {
+        "U" => Direction::Up,
+        "R" => Direction::Right,
+        "D" => Direction::Down,
+        "L" => Direction::Left,
+        _ => panic!("Unknown direction: {}", s),
     }
 }
 
-struct Point {
-    x: i64,
-    y: i64,
-}
-
-impl Point {
-    fn get_next(&self, direction: &Direction, length: i64) -> Point {
-        match direction {
-            Direction::Up => Point {
-                x: self.x,
-                y: self.y - length,
-            },
-            Direction::Right => Point {
-                x: self.x + length,
-                y: self.y,
-            },
-            Direction::Down => Point {
-                x: self.x,
-                y: self.y + length,
-            },
-            Direction::Left => Point {
-                x: self.x - length,
-                y: self.y,
-            },
-        }
+fn parse_hex_direction(ch: &char) -> Direction {
+    match ch {
+        '3' => Direction::Up,
+        '0' => Direction::Right,
+        '1' => Direction::Down,
+        '2' => Direction::Left,
+        _ => panic!("Unknown direction: {}", ch),
     }
 }
 
@@ -83,7 +37,7 @@ impl Step {
         let split = s.split(" ").collect::<Vec<_>>();
 
         Self {
-            direction: Direction::parse(split[0]),
+            direction: parse_direction(split[0]),
             length: split[1].parse::<i64>().unwrap(),
         }
     }
@@ -94,42 +48,69 @@ impl Step {
 
         let last_char = hex.chars().last().unwrap();
         Self {
-            direction: Direction::parse_2(&last_char),
+            direction: parse_hex_direction(&last_char),
             length: i64::from_str_radix(hex.strip_suffix(last_char).unwrap(), 16).unwrap(),
         }
     }
 }
 
-fn get_area(steps: &Vec<Step>) -> u64 {
-    let mut area = 0;
-    let mut p1 = Point { x: 0, y: 0 };
-
-    for step in steps {
-        let p2 = p1.get_next(&step.direction, step.length);
-
-        // we have to add length
-        // because the area is up to the point,
-        // so we have to include the edge as well
-        area += p1.x * p2.y - p1.y * p2.x + step.length;
-
-        p1 = p2;
-    }
-
-    //accounting for the 0,0 block
-    //that is why it's + 1
-    return 1 + (area.abs() / 2) as u64;
+fn get_polygon(steps: &[Step]) -> Polygon {
+    Polygon::from_steps(steps.iter().map(|step| (step.direction, step.length)))
+        .expect("Dig plan doesn't return to the start")
 }
 
 fn parse_steps(s: &str, parse_step: fn(&str) -> Step) -> Vec<Step> {
-    return s.lines().map(|line| parse_step(line)).collect();
+    s.lines().map(parse_step).collect()
 }
 
 fn main() {
     let input = fs::read_to_string("input.txt").unwrap();
     let steps = parse_steps(&input, Step::parse);
 
-    println!("{}", get_area(&steps));
+    let polygon = get_polygon(&steps);
+    if env::args().any(|arg| arg == "--show") {
+        print!("{}", polygon);
+    }
+    println!("{}", polygon.lattice_points());
+
+    if let Some(output) = Output::from_args(&env::args().collect::<Vec<_>>()) {
+        let mut recorder = Recorder::new()
+            .with_delay(Duration::from_millis(40))
+            .with_scale(1);
+        polygon.record_trace(&mut recorder, MAX_FRAMES);
+        recorder.save(&output).unwrap();
+    }
 
     let steps = parse_steps(&input, Step::parse_2);
-    println!("{}", get_area(&steps));
+    println!("{}", get_polygon(&steps).lattice_points());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{get_polygon, parse_steps, Step};
+
+    #[test]
+    fn test_lagoon() {
+        let input = fs::read_to_string("test.txt").unwrap();
+
+        assert_eq!(
+            get_polygon(&parse_steps(&input, Step::parse)).lattice_points(),
+            62
+        );
+        assert_eq!(
+            get_polygon(&parse_steps(&input, Step::parse_2)).lattice_points(),
+            952408144115
+        );
+    }
+
+    #[test]
+    fn test_square() {
+        let input = fs::read_to_string("test4.txt").unwrap();
+        let polygon = get_polygon(&parse_steps(&input, Step::parse));
+
+        assert_eq!(polygon.perimeter(), 20);
+        assert_eq!(polygon.lattice_points(), 36);
+    }
 }
@@ -1,11 +1,18 @@
-use std::{collections::HashMap, fs};
+use common::interval::Interval;
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    fmt::Display,
+    fs,
+};
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 enum Order {
     Less,
     Greater,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 enum Category {
     X,
     M,
@@ -25,6 +32,20 @@ impl Category {
     }
 }
 
+impl Display for Category {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Category::X => "x",
+            Category::M => "m",
+            Category::A => "a",
+            Category::S => "s",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 enum Destination {
     Accepted,
     Rejected,
@@ -39,43 +60,24 @@ impl Destination {
             _ => Destination::Workflow(s.to_string()),
         }
     }
+
+    fn dot_name(&self) -> &str {
+        match self {
+            Destination::Accepted => "A",
+            Destination::Rejected => "R",
+            Destination::Workflow(name) => name,
+        }
+    }
 }
 
-struct Rule {
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Condition {
     category: Category,
     order: Order,
     threshold: i64,
-    destination: Destination,
 }
 
-impl Rule {
-    fn parse(s: &str) -> Self {
-        let order;
-        let mut order_split;
-        if s.contains("<") {
-            order = Order::Less;
-            order_split = s.split("<");
-        } else if s.contains(">") {
-            order = Order::Greater;
-            order_split = s.split(">");
-        } else {
-            panic!("Cannot parse rule: {}", s);
-        }
-
-        let category = Category::parse(order_split.next().unwrap());
-
-        let mut colon_split = order_split.next().unwrap().split(":");
-        let threshold = colon_split.next().unwrap().parse().unwrap();
-        let destination = Destination::parse(colon_split.next().unwrap());
-
-        Self {
-            category,
-            order,
-            threshold,
-            destination,
-        }
-    }
-
+impl Condition {
     fn satisfies(&self, part: &Part) -> bool {
         let value = match self.category {
             Category::X => part.x,
@@ -93,27 +95,72 @@ impl Rule {
     fn split_interval(&self, part: &IntervalPart) -> (Option<IntervalPart>, Option<IntervalPart>) {
         match self.order {
             Order::Less => {
-                let (lower, higher) = part[&self.category].clone().split(self.threshold - 1);
+                let (lower, higher) = part[&self.category].split(self.threshold - 1);
 
                 let accepted = lower.map(|lower| new_interval_part(part, lower, &self.category));
-                let rejected: Option<HashMap<Category, Interval>> =
-                    higher.map(|higher| new_interval_part(part, higher, &self.category));
+                let rejected = higher.map(|higher| new_interval_part(part, higher, &self.category));
 
-                return (rejected, accepted);
+                (rejected, accepted)
             }
             Order::Greater => {
-                let (lower, higher) = part[&self.category].clone().split(self.threshold);
+                let (lower, higher) = part[&self.category].split(self.threshold);
 
                 let accepted = higher.map(|higher| new_interval_part(part, higher, &self.category));
-                let rejected: Option<HashMap<Category, Interval>> =
-                    lower.map(|lower| new_interval_part(part, lower, &self.category));
+                let rejected = lower.map(|lower| new_interval_part(part, lower, &self.category));
 
-                return (rejected, accepted);
+                (rejected, accepted)
             }
         }
     }
 }
 
+impl Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let order = match self.order {
+            Order::Less => '<',
+            Order::Greater => '>',
+        };
+
+        write!(f, "{}{}{}", self.category, order, self.threshold)
+    }
+}
+
+struct Rule {
+    condition: Condition,
+    destination: Destination,
+}
+
+impl Rule {
+    fn parse(s: &str) -> Self {
+        let order;
+        let mut order_split;
+        if s.contains("<") {
+            order = Order::Less;
+            order_split = s.split("<");
+        } else if s.contains(">") {
+            order = Order::Greater;
+            order_split = s.split(">");
+        } else {
+            panic!("Cannot parse rule: {}", s);
+        }
+
+        let category = Category::parse(order_split.next().unwrap());
+
+        let mut colon_split = order_split.next().unwrap().split(":");
+        let threshold = colon_split.next().unwrap().parse().unwrap();
+        let destination = Destination::parse(colon_split.next().unwrap());
+
+        Self {
+            condition: Condition {
+                category,
+                order,
+                threshold,
+            },
+            destination,
+        }
+    }
+}
+
 struct Workflow {
     rules: Vec<Rule>,
     final_destination: Destination,
@@ -125,65 +172,124 @@ impl Workflow {
         let (last, rules) = split.split_last().unwrap();
 
         Self {
-            rules: rules.iter().map(|rule| Rule::parse(&rule)).collect(),
-            final_destination: Destination::parse(&last),
+            rules: rules.iter().map(|rule| Rule::parse(rule)).collect(),
+            final_destination: Destination::parse(last),
         }
     }
 
-    fn process_part<'a>(&'a self, part: &Part) -> &'a Destination {
-        for rule in &self.rules {
-            if rule.satisfies(&part) {
-                return &rule.destination;
-            }
-        }
-
-        &self.final_destination
-    }
-
-    fn process_interval<'a>(
-        &'a self,
-        mut part: IntervalPart,
-    ) -> Vec<(IntervalPart, &'a Destination)> {
+    fn process_interval(&self, mut part: IntervalPart) -> Vec<(IntervalPart, &Destination)> {
         let mut result = Vec::new();
-        let mut all_satisfied = false;
 
         for rule in &self.rules {
-            let (rejected, accepted) = rule.split_interval(&part);
+            let (rejected, accepted) = rule.condition.split_interval(&part);
 
             if let Some(accepted) = accepted {
                 result.push((accepted, &rule.destination));
             }
 
-            if let Some(rejected) = rejected {
-                part = rejected;
-            } else {
-                all_satisfied = true;
-                break;
+            match rejected {
+                Some(rejected) => part = rejected,
+                None => return result,
+            }
+        }
+
+        result.push((part, &self.final_destination));
+
+        result
+    }
+}
+
+/// Workflows with every rule inlined, so a part is classified by walking a
+/// single binary tree instead of jumping between workflows by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DecisionTree {
+    Accepted,
+    Rejected,
+    Branch {
+        condition: Condition,
+        pass: Box<DecisionTree>,
+        fail: Box<DecisionTree>,
+    },
+}
+
+impl DecisionTree {
+    fn accepts(&self, part: &Part) -> bool {
+        let mut node = self;
+        loop {
+            match node {
+                DecisionTree::Accepted => return true,
+                DecisionTree::Rejected => return false,
+                DecisionTree::Branch {
+                    condition,
+                    pass,
+                    fail,
+                } => {
+                    node = if condition.satisfies(part) {
+                        pass
+                    } else {
+                        fail
+                    }
+                }
             }
         }
+    }
 
-        if !all_satisfied {
-            result.push((part, &self.final_destination));
+    /// Splits `bounds` into the disjoint hyper-rectangles that end up accepted.
+    fn accepted_parts(&self, bounds: IntervalPart) -> Vec<IntervalPart> {
+        let mut result = Vec::new();
+        let mut stack = vec![(self, bounds)];
+
+        while let Some((node, part)) = stack.pop() {
+            match node {
+                DecisionTree::Accepted => result.push(part),
+                DecisionTree::Rejected => (),
+                DecisionTree::Branch {
+                    condition,
+                    pass,
+                    fail,
+                } => {
+                    let (rejected, accepted) = condition.split_interval(&part);
+                    if let Some(accepted) = accepted {
+                        stack.push((pass, accepted));
+                    }
+                    if let Some(rejected) = rejected {
+                        stack.push((fail, rejected));
+                    }
+                }
+            }
         }
 
         result
     }
+
+    fn combinations(&self, bounds: IntervalPart) -> u128 {
+        self.accepted_parts(bounds)
+            .iter()
+            .map(interval_part_values)
+            .sum()
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            DecisionTree::Accepted | DecisionTree::Rejected => 1,
+            DecisionTree::Branch { pass, fail, .. } => 1 + pass.size() + fail.size(),
+        }
+    }
 }
 
 struct WorkflowMap {
     map: HashMap<String, Workflow>,
-    accepted: Vec<Part>,
-    rejected: Vec<Part>,
-    accepted_intervals: Vec<IntervalPart>,
 }
 
+/// Some workflows send parts around in a loop, listed from the first workflow
+/// on it back to that workflow again.
+#[derive(Debug, PartialEq, Eq)]
+struct WorkflowCycle(Vec<String>);
+
 impl WorkflowMap {
     fn parse(s: &str) -> Self {
         let mut result = WorkflowMap {
             map: HashMap::new(),
-            accepted: Vec::new(),
-            rejected: Vec::new(),
-            accepted_intervals: Vec::new(),
         };
 
         for line in s.lines() {
@@ -196,54 +302,147 @@ impl WorkflowMap {
                 .insert(name.to_string(), Workflow::parse(workflow));
         }
 
-        return result;
+        result
     }
 
-    fn process_part(&mut self, part: Part) {
-        let mut workflow = &self.map["in"];
+    /// Inlines all workflows starting from `in` into a decision tree. Rules that
+    /// can't be decided either way within `bounds` are kept, rules that always
+    /// or never match are dropped, and branches with identical outcomes are
+    /// merged.
+    fn compile(&self, bounds: IntervalPart) -> Result<DecisionTree, WorkflowCycle> {
+        self.compile_workflow("in", bounds, &mut Vec::new())
+    }
 
-        while let Destination::Workflow(workflow_name) = workflow.process_part(&part) {
-            workflow = &self.map[workflow_name];
+    fn compile_workflow<'a>(
+        &'a self,
+        name: &'a str,
+        bounds: IntervalPart,
+        path: &mut Vec<&'a str>,
+    ) -> Result<DecisionTree, WorkflowCycle> {
+        if let Some(start) = path.iter().position(|other| *other == name) {
+            let mut cycle: Vec<String> =
+                path[start..].iter().map(|name| name.to_string()).collect();
+            cycle.push(name.to_string());
+            return Err(WorkflowCycle(cycle));
         }
 
-        match workflow.process_part(&part) {
-            Destination::Accepted => self.accepted.push(part),
-            Destination::Rejected => self.rejected.push(part),
-            Destination::Workflow(_) => (),
-        };
+        path.push(name);
+        let tree = self.compile_rules(&self.map[name], 0, bounds, path);
+        path.pop();
+
+        tree
     }
 
-    fn process_interval(&mut self, part: IntervalPart) {
-        let mut stack = vec![(part, &self.map["in"])];
+    fn compile_rules<'a>(
+        &'a self,
+        workflow: &'a Workflow,
+        index: usize,
+        bounds: IntervalPart,
+        path: &mut Vec<&'a str>,
+    ) -> Result<DecisionTree, WorkflowCycle> {
+        let Some(rule) = workflow.rules.get(index) else {
+            return self.compile_destination(&workflow.final_destination, bounds, path);
+        };
 
-        while let Some((part, workflow)) = stack.pop() {
-            for (new_part, new_destination) in workflow.process_interval(part) {
-                match new_destination {
-                    Destination::Accepted => self.accepted_intervals.push(new_part),
-                    Destination::Rejected => (),
-                    Destination::Workflow(name) => stack.push((new_part, &self.map[name])),
+        match rule.condition.split_interval(&bounds) {
+            (None, Some(accepted)) => self.compile_destination(&rule.destination, accepted, path),
+            (Some(rejected), None) => self.compile_rules(workflow, index + 1, rejected, path),
+            (Some(rejected), Some(accepted)) => {
+                let pass = self.compile_destination(&rule.destination, accepted, path)?;
+                let fail = self.compile_rules(workflow, index + 1, rejected, path)?;
+
+                if pass == fail {
+                    Ok(pass)
+                } else {
+                    Ok(DecisionTree::Branch {
+                        condition: rule.condition.clone(),
+                        pass: Box::new(pass),
+                        fail: Box::new(fail),
+                    })
                 }
             }
+            (None, None) => Ok(DecisionTree::Rejected),
         }
     }
 
-    fn sum_accepted(&self) -> i64 {
-        self.accepted
-            .iter()
-            .map(|part| part.x + part.m + part.a + part.s)
-            .sum()
+    fn compile_destination<'a>(
+        &'a self,
+        destination: &'a Destination,
+        bounds: IntervalPart,
+        path: &mut Vec<&'a str>,
+    ) -> Result<DecisionTree, WorkflowCycle> {
+        match destination {
+            Destination::Accepted => Ok(DecisionTree::Accepted),
+            Destination::Rejected => Ok(DecisionTree::Rejected),
+            Destination::Workflow(name) => self.compile_workflow(name, bounds, path),
+        }
     }
 
-    fn combinations(&self) -> i64 {
-        self.accepted_intervals
-            .iter()
-            .map(|part| {
-                part[&Category::X].values()
-                    * part[&Category::M].values()
-                    * part[&Category::A].values()
-                    * part[&Category::S].values()
-            })
-            .sum()
+    /// Names of the workflows that no part within `bounds` can ever reach,
+    /// either because nothing links to them or because every rule leading
+    /// to them is contradicted by the rules before it.
+    fn unreachable_workflows(&self, bounds: IntervalPart) -> Vec<&str> {
+        let mut reachable = HashSet::new();
+        let mut stack = vec![("in", bounds)];
+
+        while let Some((name, part)) = stack.pop() {
+            reachable.insert(name);
+            for (new_part, destination) in self.map[name].process_interval(part) {
+                if let Destination::Workflow(next) = destination {
+                    stack.push((next, new_part));
+                }
+            }
+        }
+
+        let mut result: Vec<&str> = self
+            .map
+            .keys()
+            .map(|name| name.as_str())
+            .filter(|name| !reachable.contains(name))
+            .collect();
+        result.sort();
+
+        result
+    }
+
+    /// Renders the workflow graph in DOT. Each rule is an edge labelled with its
+    /// condition, the fallback edge is dashed and unreachable workflows are
+    /// greyed out.
+    fn to_dot(&self, bounds: IntervalPart) -> String {
+        let unreachable = self.unreachable_workflows(bounds);
+        let mut names: Vec<&String> = self.map.keys().collect();
+        names.sort();
+
+        let mut result = String::from("digraph workflows {\n");
+        result.push_str("    \"A\" [shape=doublecircle, color=green];\n");
+        result.push_str("    \"R\" [shape=doublecircle, color=red];\n");
+
+        for name in names {
+            if unreachable.contains(&name.as_str()) {
+                result.push_str(&format!("    \"{}\" [style=dashed, color=grey];\n", name));
+            } else {
+                result.push_str(&format!("    \"{}\";\n", name));
+            }
+
+            let workflow = &self.map[name];
+            for rule in &workflow.rules {
+                result.push_str(&format!(
+                    "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                    name,
+                    rule.destination.dot_name(),
+                    rule.condition
+                ));
+            }
+            result.push_str(&format!(
+                "    \"{}\" -> \"{}\" [style=dashed];\n",
+                name,
+                workflow.final_destination.dot_name()
+            ));
+        }
+
+        result.push_str("}\n");
+
+        result
     }
 }
 
@@ -271,106 +470,231 @@ impl Part {
             s: Part::parse_value(split.next().unwrap(), "s="),
         }
     }
-}
-
-fn parse_parts(s: &str) -> Vec<Part> {
-    s.lines().map(|line| Part::parse(line)).collect()
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct Interval {
-    start: i64,
-    end: i64,
-}
-
-impl Interval {
-    fn new(start: i64, end: i64) -> Self {
-        return Interval { start, end };
-    }
 
-    fn split(self, mid: i64) -> (Option<Interval>, Option<Interval>) {
-        if self.start > mid {
-            return (None, Some(self));
-        }
-
-        if self.end <= mid {
-            return (Some(self), None);
-        }
-
-        return (
-            Some(Interval::new(self.start, mid)),
-            Some(Interval::new(mid + 1, self.end)),
-        );
+    fn rating(&self) -> i64 {
+        self.x + self.m + self.a + self.s
     }
+}
 
-    fn values(&self) -> i64 {
-        return self.end - self.start + 1;
-    }
+fn parse_parts(s: &str) -> Vec<Part> {
+    s.lines().map(Part::parse).collect()
 }
 
 type IntervalPart = HashMap<Category, Interval>;
 
 fn new_interval_part(part: &IntervalPart, interval: Interval, category: &Category) -> IntervalPart {
-    let mut result = HashMap::new();
-
-    for (key, value) in part {
-        result.insert(key.clone(), value.clone());
-    }
+    let mut result = part.clone();
+    result.insert(*category, interval);
 
-    result.insert(category.clone(), interval);
+    result
+}
 
-    return result;
+fn uniform_interval_part(start: i64, end: i64) -> IntervalPart {
+    [Category::X, Category::M, Category::A, Category::S]
+        .into_iter()
+        .map(|category| (category, Interval::new(start, end)))
+        .collect()
 }
 
-//167409079868000
+fn interval_part_values(part: &IntervalPart) -> u128 {
+    part.values()
+        .map(|interval| interval.values() as u128)
+        .product()
+}
 
 fn main() {
     let input = fs::read_to_string("input.txt").unwrap();
 
     let mut split = input.split("\n\n");
-    let mut wmap = WorkflowMap::parse(split.next().unwrap());
+    let wmap = WorkflowMap::parse(split.next().unwrap());
     let parts = parse_parts(split.next().unwrap());
 
-    for part in parts {
-        wmap.process_part(part);
-    }
-
-    let mut interval_part = IntervalPart::new();
-    interval_part.insert(Category::X, Interval::new(1, 4000));
-    interval_part.insert(Category::M, Interval::new(1, 4000));
-    interval_part.insert(Category::A, Interval::new(1, 4000));
-    interval_part.insert(Category::S, Interval::new(1, 4000));
+    let bounds = uniform_interval_part(1, 4000);
+    let tree = match wmap.compile(bounds.clone()) {
+        Ok(tree) => tree,
+        Err(WorkflowCycle(cycle)) => {
+            println!("Workflow cycle: {}", cycle.join(","));
+            return;
+        }
+    };
+    println!("Decision tree nodes: {}", tree.size());
 
-    wmap.process_interval(interval_part);
+    let unreachable = wmap.unreachable_workflows(bounds.clone());
+    if !unreachable.is_empty() {
+        println!("Unreachable workflows: {}", unreachable.join(","));
+    }
 
-    println!("{}", wmap.combinations());
+    let sum: i64 = parts
+        .iter()
+        .filter(|part| tree.accepts(part))
+        .map(Part::rating)
+        .sum();
+    println!("{}", sum);
+    println!("{}", tree.combinations(bounds.clone()));
+
+    let args: Vec<String> = env::args().collect();
+    if let Some(position) = args.iter().position(|arg| arg == "--dot") {
+        match args.get(position + 1) {
+            Some(path) => fs::write(path, wmap.to_dot(bounds)).unwrap(),
+            None => println!("Usage: --dot <path>"),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Interval;
+    use common::interval::Interval;
+    use std::fs;
+
+    use crate::{
+        parse_parts, uniform_interval_part, Category, DecisionTree, Part, WorkflowCycle,
+        WorkflowMap,
+    };
+
+    fn parse_file(path: &str) -> (WorkflowMap, Vec<Part>) {
+        let input = fs::read_to_string(path).unwrap();
+        let mut split = input.split("\n\n");
+
+        (
+            WorkflowMap::parse(split.next().unwrap()),
+            parse_parts(split.next().unwrap()),
+        )
+    }
 
     #[test]
     fn split_interval() {
         let i = Interval::new(5, 10);
 
         assert_eq!(
-            i.clone().split(7),
+            i.split(7),
             (Some(Interval::new(5, 7)), Some(Interval::new(8, 10)))
         );
 
         assert_eq!(
-            i.clone().split(5),
+            i.split(5),
             (Some(Interval::new(5, 5)), Some(Interval::new(6, 10)))
         );
 
         assert_eq!(
-            i.clone().split(9),
+            i.split(9),
             (Some(Interval::new(5, 9)), Some(Interval::new(10, 10)))
         );
 
-        assert_eq!(i.clone().split(4), (None, Some(Interval::new(5, 10))));
+        assert_eq!(i.split(4), (None, Some(Interval::new(5, 10))));
+
+        assert_eq!(i.split(10), (Some(Interval::new(5, 10)), None));
+    }
+
+    #[test]
+    fn compiled_tree_matches_example() {
+        let (wmap, parts) = parse_file("test.txt");
+        let bounds = uniform_interval_part(1, 4000);
+        let tree = wmap.compile(bounds.clone()).unwrap();
+
+        let sum: i64 = parts
+            .iter()
+            .filter(|part| tree.accepts(part))
+            .map(Part::rating)
+            .sum();
+        assert_eq!(sum, 19114);
+        assert_eq!(tree.combinations(bounds), 167409079868000);
+    }
+
+    #[test]
+    fn combinations_for_other_bounds() {
+        let (wmap, _) = parse_file("test2.txt");
+        let bounds = uniform_interval_part(1, 10);
+        let tree = wmap.compile(bounds.clone()).unwrap();
+
+        assert_eq!(tree.combinations(bounds), 10u128.pow(4));
+
+        let bounds = uniform_interval_part(1000, 2000);
+        let tree = wmap.compile(bounds.clone()).unwrap();
+        assert_eq!(tree.combinations(bounds), 351 * 1001u128.pow(3));
+    }
+
+    #[test]
+    fn compile_drops_redundant_rules() {
+        let wmap = WorkflowMap::parse("in{x<10:lnx,gd}\nlnx{m>1548:A,A}\ngd{a>3333:R,R}");
+        assert_eq!(
+            wmap.compile(uniform_interval_part(1, 4000)),
+            Ok(DecisionTree::Branch {
+                condition: crate::Condition {
+                    category: Category::X,
+                    order: crate::Order::Less,
+                    threshold: 10
+                },
+                pass: Box::new(DecisionTree::Accepted),
+                fail: Box::new(DecisionTree::Rejected),
+            })
+        );
+
+        assert_eq!(
+            wmap.compile(uniform_interval_part(1, 9)),
+            Ok(DecisionTree::Accepted)
+        );
+    }
+
+    #[test]
+    fn reports_workflow_cycles() {
+        let wmap = WorkflowMap::parse("in{x<10:px,A}\npx{m>5:qs,R}\nqs{a<5:px,A}");
+
+        assert_eq!(
+            wmap.compile(uniform_interval_part(1, 4000)),
+            Err(WorkflowCycle(vec![
+                "px".to_string(),
+                "qs".to_string(),
+                "px".to_string()
+            ]))
+        );
+        // The loop can't be entered with these bounds
+        assert_eq!(
+            wmap.compile(uniform_interval_part(10, 4000)),
+            Ok(DecisionTree::Accepted)
+        );
+    }
+
+    #[test]
+    fn reports_unreachable_workflows() {
+        let wmap = WorkflowMap::parse(
+            "in{x<10:px,qs}\npx{x>20:gd,A}\nqs{m<5:R,A}\ngd{a>5:R,A}\nzz{a<5:R,A}",
+        );
+
+        assert_eq!(
+            wmap.unreachable_workflows(uniform_interval_part(1, 4000)),
+            vec!["gd", "zz"]
+        );
+        assert_eq!(
+            wmap.unreachable_workflows(uniform_interval_part(1, 9)),
+            vec!["gd", "qs", "zz"]
+        );
+    }
+
+    #[test]
+    fn accepted_parts_are_disjoint_rectangles() {
+        let (wmap, _) = parse_file("test3.txt");
+        let bounds = uniform_interval_part(1, 4000);
+        let parts = wmap.compile(bounds.clone()).unwrap().accepted_parts(bounds);
+
+        assert_eq!(parts.len(), 2);
+        assert!(parts
+            .iter()
+            .any(|part| part[&Category::X] == Interval::new(2001, 4000)
+                && part[&Category::S] == Interval::new(1351, 4000)));
+        assert!(parts
+            .iter()
+            .any(|part| part[&Category::A] == Interval::new(2006, 4000)
+                && part[&Category::M] == Interval::new(1, 2090)
+                && part[&Category::S] == Interval::new(1, 1350)));
+    }
+
+    #[test]
+    fn dot_export_lists_rules() {
+        let (wmap, _) = parse_file("test2.txt");
+        let dot = wmap.to_dot(uniform_interval_part(1, 4000));
 
-        assert_eq!(i.clone().split(10), (Some(Interval::new(5, 10)), None));
+        assert!(dot.starts_with("digraph workflows {"));
+        assert!(dot.contains("\"in\" -> \"A\" [label=\"s<1351\"];"));
+        assert!(dot.contains("\"in\" -> \"R\" [style=dashed];"));
     }
 }
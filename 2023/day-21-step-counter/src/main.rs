@@ -1,6 +1,7 @@
+use common::polynomial::Polynomial;
 use std::{
     collections::{HashSet, VecDeque},
-    fs,
+    env, fs,
 };
 
 #[derive(Debug, PartialEq, Eq)]
@@ -9,100 +10,281 @@ enum Tile {
     Rock,
 }
 
-fn parse(s: &str) -> (Vec<Vec<Tile>>, usize, usize) {
-    let mut start_i = 0;
-    let mut start_j = 0;
-
-    let garden = s
-        .lines()
-        .enumerate()
-        .map(|(i, line)| {
-            line.chars()
-                .enumerate()
-                .map(|(j, ch)| match ch {
-                    '#' => Tile::Rock,
-                    '.' => Tile::Plot,
-                    'S' => {
-                        start_i = i;
-                        start_j = j;
-                        Tile::Plot
-                    }
-                    _ => panic!("Unknown tile {}", ch),
-                })
-                .collect()
-        })
-        .collect();
+/// How many whole periods of the tiling are simulated when looking for the
+/// quadratic growth.
+const SAMPLED_PERIODS: u64 = 10;
+
+/// How many samples past the ones that pin down the polynomial have to agree
+/// with it before the growth is trusted to stay quadratic.
+const CONFIRMATIONS: usize = 3;
 
-    (garden, start_i, start_j)
+struct Garden {
+    tiles: Vec<Vec<Tile>>,
+    start: (i64, i64),
 }
 
-fn part1(garden: &Vec<Vec<Tile>>, i: usize, j: usize, steps: u64) -> usize {
-    let mut q: VecDeque<(usize, usize)> = VecDeque::new();
-    q.push_back((i, j));
-
-    for _ in 0..steps {
-        let mut next_q: HashSet<(usize, usize)> = HashSet::new();
-
-        while let Some((i, j)) = q.pop_front() {
-            for (di, dj) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
-                let new_i = i as i64 + di;
-                let new_j = j as i64 + dj;
-                if new_i < 0
-                    || new_j < 0
-                    || new_i >= garden.len() as i64
-                    || new_j >= garden[0].len() as i64
-                    || garden[new_i as usize][new_j as usize] == Tile::Rock
+impl Garden {
+    fn parse(s: &str) -> Self {
+        let mut start = (0, 0);
+
+        let tiles = s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                line.chars()
+                    .enumerate()
+                    .map(|(j, ch)| match ch {
+                        '#' => Tile::Rock,
+                        '.' => Tile::Plot,
+                        'S' => {
+                            start = (i as i64, j as i64);
+                            Tile::Plot
+                        }
+                        _ => panic!("Unknown tile {}", ch),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Garden { tiles, start }
+    }
+
+    fn height(&self) -> i64 {
+        self.tiles.len() as i64
+    }
+
+    fn width(&self) -> i64 {
+        self.tiles[0].len() as i64
+    }
+
+    fn is_inside(&self, i: i64, j: i64) -> bool {
+        i >= 0 && j >= 0 && i < self.height() && j < self.width()
+    }
+
+    /// Looks up a tile on the infinite tiling of the garden.
+    fn is_rock(&self, i: i64, j: i64) -> bool {
+        self.tiles[i.rem_euclid(self.height()) as usize][j.rem_euclid(self.width()) as usize]
+            == Tile::Rock
+    }
+
+    fn neighbours(i: i64, j: i64) -> impl Iterator<Item = (i64, i64)> {
+        [(-1, 0), (1, 0), (0, -1), (0, 1)]
+            .into_iter()
+            .map(move |(di, dj)| (i + di, j + dj))
+    }
+
+    /// Plots reachable in exactly `steps` steps without leaving the garden.
+    fn reachable_bounded(&self, steps: u64) -> usize {
+        let mut distances = vec![vec![None; self.tiles[0].len()]; self.tiles.len()];
+        let mut q = VecDeque::from([(self.start, 0)]);
+        distances[self.start.0 as usize][self.start.1 as usize] = Some(0);
+
+        while let Some(((i, j), distance)) = q.pop_front() {
+            if distance == steps {
+                continue;
+            }
+
+            for (new_i, new_j) in Garden::neighbours(i, j) {
+                if !self.is_inside(new_i, new_j)
+                    || self.is_rock(new_i, new_j)
+                    || distances[new_i as usize][new_j as usize].is_some()
                 {
                     continue;
                 }
 
-                next_q.insert((new_i as usize, new_j as usize));
+                distances[new_i as usize][new_j as usize] = Some(distance + 1);
+                q.push_back(((new_i, new_j), distance + 1));
             }
         }
 
-        q = next_q.into_iter().collect::<VecDeque<_>>();
+        distances
+            .iter()
+            .flatten()
+            .filter(|distance| matches!(distance, Some(d) if d % 2 == steps % 2))
+            .count()
     }
 
-    return q.len();
-}
+    /// `counts[s]` is the number of plots reachable in exactly `s` steps on the
+    /// infinite tiling, for every `s` up to `max_steps`. A plot at distance `d`
+    /// is reachable in `s >= d` steps exactly when `s - d` is even, because the
+    /// walk can step back and forth.
+    fn reachable_counts(&self, max_steps: u64) -> Vec<u64> {
+        let mut visited = HashSet::from([self.start]);
+        let mut frontier = vec![self.start];
+        let mut counts = vec![1];
+
+        for step in 1..=max_steps as usize {
+            let mut next_frontier = Vec::new();
+            for (i, j) in frontier {
+                for (new_i, new_j) in Garden::neighbours(i, j) {
+                    if !self.is_rock(new_i, new_j) && visited.insert((new_i, new_j)) {
+                        next_frontier.push((new_i, new_j));
+                    }
+                }
+            }
 
-fn part2(garden: &Vec<Vec<Tile>>, i: i64, j: i64, steps: u64) -> usize {
-    let mut q: VecDeque<(i64, i64)> = VecDeque::new();
-    q.push_back((i, j));
+            let previous = if step >= 2 { counts[step - 2] } else { 0 };
+            counts.push(previous + next_frontier.len() as u64);
+            frontier = next_frontier;
+        }
 
-    for _ in 0..steps {
-        let mut next_q: HashSet<(i64, i64)> = HashSet::new();
+        counts
+    }
 
-        while let Some((i, j)) = q.pop_front() {
-            for (di, dj) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
-                let new_i = i + di;
-                let new_j = j + dj;
-                if garden[new_i.rem_euclid(garden.len() as i64) as usize]
-                    [new_j.rem_euclid(garden[0].len() as i64) as usize]
-                    == Tile::Rock
-                {
-                    continue;
-                }
+    /// Simulates every step on the infinite tiling. Only usable for small step
+    /// counts, but it doesn't rely on anything about the shape of the garden.
+    fn reachable_brute_force(&self, steps: u64) -> usize {
+        let mut positions = HashSet::from([self.start]);
+
+        for _ in 0..steps {
+            positions = positions
+                .into_iter()
+                .flat_map(|(i, j)| Garden::neighbours(i, j))
+                .filter(|(i, j)| !self.is_rock(*i, *j))
+                .collect();
+        }
+
+        positions.len()
+    }
+
+    /// Plots reachable in exactly `steps` steps on the infinite tiling.
+    ///
+    /// Once the frontier has spread over a few copies of the garden, sampling
+    /// every `period` steps gives a sequence that grows quadratically. The
+    /// samples are taken from a single BFS and the quadratic is fitted to the
+    /// tail where they settle, then evaluated at `steps`. Odd periods
+    /// alternate the parity of the reachable plots, so twice the period is
+    /// tried as well. Returns `None` if the growth never settles.
+    fn reachable(&self, steps: u64) -> Option<u64> {
+        let period = lcm(self.height() as u64, self.width() as u64);
+
+        for period in [period, 2 * period] {
+            let remainder = steps % period;
+            let max_steps = remainder + SAMPLED_PERIODS * period;
+            let counts = self.reachable_counts(max_steps.min(steps));
+            if steps <= max_steps {
+                return Some(counts[steps as usize]);
+            }
 
-                next_q.insert((new_i, new_j));
+            let samples: Vec<i128> = (0..=SAMPLED_PERIODS)
+                .map(|k| counts[(remainder + k * period) as usize] as i128)
+                .collect();
+            if let Some(value) =
+                extrapolate_quadratic(&samples, ((steps - remainder) / period) as i128)
+            {
+                return Some(value as u64);
             }
         }
 
-        q = next_q.into_iter().collect::<VecDeque<_>>();
+        None
+    }
+}
+
+/// Finds the earliest point after which `samples` follow a quadratic, with
+/// enough samples left over to confirm it, and evaluates that quadratic at
+/// index `n`.
+fn extrapolate_quadratic(samples: &[i128], n: i128) -> Option<i128> {
+    (0..samples.len()).find_map(|start| {
+        Polynomial::confirmed(&samples[start..], CONFIRMATIONS)
+            .ok()
+            .filter(|polynomial| polynomial.degree() <= 2)
+            .map(|polynomial| polynomial.evaluate(n - start as i128))
+    })
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
     }
+}
 
-    return q.len();
+fn lcm(a: u64, b: u64) -> u64 {
+    a / gcd(a, b) * b
 }
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let brute_force = args.iter().any(|arg| arg == "--brute-force");
+    let mut steps = args.iter().filter_map(|arg| arg.parse::<u64>().ok());
+    let part1_steps = steps.next().unwrap_or(64);
+    let part2_steps = steps.next().unwrap_or(26501365);
+
     let input = fs::read_to_string("input.txt").unwrap();
-    let (garden, start_i, start_j) = parse(&input);
+    let garden = Garden::parse(&input);
+
+    println!("{}", garden.reachable_bounded(part1_steps));
+
+    let reachable = garden
+        .reachable(part2_steps)
+        .expect("Reachable plots don't grow quadratically");
+    println!("{}", reachable);
+
+    if brute_force {
+        let expected = garden.reachable_brute_force(part2_steps) as u64;
+        println!(
+            "Brute force: {} ({})",
+            expected,
+            if expected == reachable {
+                "matches"
+            } else {
+                "MISMATCH"
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{extrapolate_quadratic, Garden};
+
+    fn example() -> Garden {
+        Garden::parse(&fs::read_to_string("test.txt").unwrap())
+    }
+
+    #[test]
+    fn test_reachable_bounded() {
+        let garden = example();
+
+        assert_eq!(garden.reachable_bounded(1), 2);
+        assert_eq!(garden.reachable_bounded(2), 4);
+        assert_eq!(garden.reachable_bounded(3), 6);
+        assert_eq!(garden.reachable_bounded(6), 16);
+    }
 
-    println!("{}", part1(&garden, start_i, start_j, 6));
-    println!(
-        "{}",
-        part2(&garden, start_i as i64, start_j as i64, 26501365)
-    );
+    #[test]
+    fn test_reachable_counts_match_brute_force() {
+        let garden = example();
+        let counts = garden.reachable_counts(60);
 
-    //For part 2, again had to do it by hand, because of the propoerties of the input
+        for steps in [6, 10, 23, 50, 60] {
+            assert_eq!(
+                counts[steps as usize],
+                garden.reachable_brute_force(steps) as u64
+            );
+        }
+        assert_eq!(counts[50], 1594);
+    }
+
+    #[test]
+    fn test_reachable() {
+        let garden = example();
+
+        assert_eq!(garden.reachable(6), Some(16));
+        assert_eq!(garden.reachable(100), Some(6536));
+        assert_eq!(garden.reachable(500), Some(167004));
+        assert_eq!(garden.reachable(1000), Some(668697));
+        assert_eq!(garden.reachable(5000), Some(16733044));
+    }
+
+    #[test]
+    fn test_extrapolate_quadratic() {
+        let samples: Vec<i128> = [7, 3, 1, 4, 9, 16, 25, 36, 49].to_vec();
+
+        assert_eq!(extrapolate_quadratic(&samples, 100), Some(99 * 99));
+        assert_eq!(extrapolate_quadratic(&[1, 2, 4, 8, 16, 32], 10), None);
+    }
 }
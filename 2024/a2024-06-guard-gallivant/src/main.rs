@@ -1,9 +1,11 @@
-use std::collections::HashSet;
+use common::visualize::{Colour, Frame, Output, Recorder};
+use std::env;
 use std::fs::File;
 use std::io::Read;
-use std::time::SystemTime;
+use std::thread;
+use std::time::{Duration, SystemTime};
 
-#[derive(Eq, PartialEq, Clone, Copy, Hash)]
+#[derive(Eq, PartialEq, Clone, Copy, Hash, Debug)]
 enum Direction {
     Up,
     Right,
@@ -11,19 +13,60 @@ enum Direction {
     Left,
 }
 
-fn parse(content: &str) -> (Vec<Vec<char>>, usize, usize, Direction) {
+impl Direction {
+    fn turn_right(self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn arrow(self) -> char {
+        match self {
+            Direction::Up => '^',
+            Direction::Right => '>',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+        }
+    }
+}
+
+/// The most frames `--visualize` records of a patrol; longer ones skip steps.
+const MAX_FRAMES: usize = 400;
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+struct Guard {
+    i: usize,
+    j: usize,
+    direction: Direction,
+}
+
+fn parse(content: &str) -> (Vec<Vec<char>>, Guard) {
     let grid: Vec<Vec<char>> = content.lines().map(|line| line.chars().collect()).collect();
 
     for i in 0..grid.len() {
         for j in 0..grid[i].len() {
             if grid[i][j] != '#' && grid[i][j] != '.' {
                 let direction = char_to_direction(grid[i][j]);
-                return (grid, i, j, direction);
+                return (grid, Guard { i, j, direction });
             }
         }
     }
 
-    (grid, 0, 0, Direction::Up)
+    (
+        grid,
+        Guard {
+            i: 0,
+            j: 0,
+            direction: Direction::Up,
+        },
+    )
 }
 
 fn char_to_direction(ch: char) -> Direction {
@@ -36,130 +79,437 @@ fn char_to_direction(ch: char) -> Direction {
     }
 }
 
-fn visit_grid(
-    grid: &Vec<Vec<char>>,
-    i: usize,
-    j: usize,
-    direction: Direction,
-    visited: &mut HashSet<(usize, usize, Direction)>,
-) -> bool {
-    if visited.contains(&(i, j, direction)) {
-        return true;
+/// The map together with jump tables: `jumps[d][i][j]` is the row (for up and
+/// down) or column (for left and right) of the nearest obstacle seen from
+/// `(i, j)` when looking in direction `d`.
+struct Lab {
+    grid: Vec<Vec<char>>,
+    height: usize,
+    width: usize,
+    jumps: [Vec<Vec<Option<usize>>>; 4],
+}
+
+impl Lab {
+    fn new(grid: Vec<Vec<char>>) -> Self {
+        let height = grid.len();
+        let width = grid[0].len();
+        assert!(
+            grid.iter().all(|row| row.len() == width),
+            "Every row of the map has to be equally long"
+        );
+
+        let mut jumps: [Vec<Vec<Option<usize>>>; 4] =
+            std::array::from_fn(|_| vec![vec![None; width]; height]);
+
+        for i in 0..height {
+            let mut left = None;
+            for j in 0..width {
+                jumps[Direction::Left.index()][i][j] = left;
+                if grid[i][j] == '#' {
+                    left = Some(j);
+                }
+            }
+
+            let mut right = None;
+            for j in (0..width).rev() {
+                jumps[Direction::Right.index()][i][j] = right;
+                if grid[i][j] == '#' {
+                    right = Some(j);
+                }
+            }
+        }
+
+        for j in 0..width {
+            let mut up = None;
+            for i in 0..height {
+                jumps[Direction::Up.index()][i][j] = up;
+                if grid[i][j] == '#' {
+                    up = Some(i);
+                }
+            }
+
+            let mut down = None;
+            for i in (0..height).rev() {
+                jumps[Direction::Down.index()][i][j] = down;
+                if grid[i][j] == '#' {
+                    down = Some(i);
+                }
+            }
+        }
+
+        Self {
+            grid,
+            height,
+            width,
+            jumps,
+        }
     }
 
-    visited.insert((i, j, direction));
+    fn is_blocked(&self, (i, j): (usize, usize), extra: Option<(usize, usize)>) -> bool {
+        self.grid[i][j] == '#' || extra == Some((i, j))
+    }
 
-    if i == 0 || j == 0 || i == grid.len() - 1 || j == grid.len() - 1 {
-        return false;
+    fn next_cell(&self, guard: Guard) -> Option<(usize, usize)> {
+        match guard.direction {
+            Direction::Up if guard.i > 0 => Some((guard.i - 1, guard.j)),
+            Direction::Right if guard.j + 1 < self.width => Some((guard.i, guard.j + 1)),
+            Direction::Down if guard.i + 1 < self.height => Some((guard.i + 1, guard.j)),
+            Direction::Left if guard.j > 0 => Some((guard.i, guard.j - 1)),
+            _ => None,
+        }
     }
 
-    let (next_i, next_j, next_direction) = match direction {
-        Direction::Up => {
-            if grid[i - 1][j] == '#' {
-                (i, j, Direction::Right)
-            } else {
-                (i - 1, j, Direction::Up)
-            }
+    /// Moves the guard a single cell or turns it. `None` once it walks off the
+    /// map.
+    fn step(&self, guard: Guard, extra: Option<(usize, usize)>) -> Option<Guard> {
+        let (i, j) = self.next_cell(guard)?;
+        if self.is_blocked((i, j), extra) {
+            Some(Guard {
+                direction: guard.direction.turn_right(),
+                ..guard
+            })
+        } else {
+            Some(Guard { i, j, ..guard })
         }
-        Direction::Right => {
-            if grid[i][j + 1] == '#' {
-                (i, j, Direction::Down)
-            } else {
-                (i, j + 1, Direction::Right)
+    }
+
+    /// Moves the guard straight up to the next obstacle and turns it, using the
+    /// jump tables. `extra` is an obstacle that isn't on the map.
+    fn jump(&self, guard: Guard, extra: Option<(usize, usize)>) -> Option<Guard> {
+        let Guard { i, j, direction } = guard;
+        let table = self.jumps[direction.index()][i][j];
+
+        let (i, j) = match direction {
+            Direction::Up => {
+                let extra = extra
+                    .filter(|&(oi, oj)| oj == j && oi < i)
+                    .map(|(oi, _)| oi);
+                (table.max(extra)? + 1, j)
+            }
+            Direction::Down => {
+                let extra = extra
+                    .filter(|&(oi, oj)| oj == j && oi > i)
+                    .map(|(oi, _)| oi);
+                (nearest_after(table, extra)? - 1, j)
+            }
+            Direction::Left => {
+                let extra = extra
+                    .filter(|&(oi, oj)| oi == i && oj < j)
+                    .map(|(_, oj)| oj);
+                (i, table.max(extra)? + 1)
             }
+            Direction::Right => {
+                let extra = extra
+                    .filter(|&(oi, oj)| oi == i && oj > j)
+                    .map(|(_, oj)| oj);
+                (i, nearest_after(table, extra)? - 1)
+            }
+        };
+
+        Some(Guard {
+            i,
+            j,
+            direction: direction.turn_right(),
+        })
+    }
+
+    /// Every state of the guard's patrol, in order. `None` if the guard never
+    /// leaves the map.
+    fn patrol(&self, start: Guard) -> Option<Vec<Guard>> {
+        let mut seen = vec![false; self.height * self.width * 4];
+        let mut path = Vec::new();
+        let mut guard = Some(start);
+
+        while let Some(current) = guard {
+            let index = self.state_index(current);
+            if seen[index] {
+                return None;
+            }
+            seen[index] = true;
+            path.push(current);
+            guard = self.step(current, None);
         }
-        Direction::Down => {
-            if grid[i + 1][j] == '#' {
-                (i, j, Direction::Left)
-            } else {
-                (i + 1, j, Direction::Down)
+
+        Some(path)
+    }
+
+    fn state_index(&self, guard: Guard) -> usize {
+        (guard.i * self.width + guard.j) * 4 + guard.direction.index()
+    }
+
+    /// Checks whether the guard gets stuck in a loop after `extra` is added,
+    /// jumping from obstacle to obstacle. `seen` is shared between calls and is
+    /// only considered set where it equals `stamp`, so it never needs
+    /// clearing.
+    fn loops(&self, start: Guard, extra: (usize, usize), seen: &mut [u32], stamp: u32) -> bool {
+        let mut guard = start;
+
+        while let Some(next) = self.jump(guard, Some(extra)) {
+            let index = self.state_index(next);
+            if seen[index] == stamp {
+                return true;
             }
+            seen[index] = stamp;
+            guard = next;
         }
-        Direction::Left => {
-            if grid[i][j - 1] == '#' {
-                (i, j, Direction::Up)
-            } else {
-                (i, j - 1, Direction::Left)
+
+        false
+    }
+
+    /// Draws the path of a guard stuck in a loop because of `extra`, the way
+    /// the puzzle does: `|` and `-` for the directions walked, `+` where both
+    /// happen and `O` for the new obstruction.
+    fn render_loop(&self, start: Guard, extra: (usize, usize)) -> Frame {
+        let mut grid = self.grid.clone();
+        let mut seen = vec![false; self.height * self.width * 4];
+        let mut guard = Some(start);
+
+        while let Some(current) = guard {
+            let index = self.state_index(current);
+            if seen[index] {
+                break;
+            }
+            seen[index] = true;
+
+            let mark = match current.direction {
+                Direction::Up | Direction::Down => '|',
+                Direction::Left | Direction::Right => '-',
+            };
+            let cell = &mut grid[current.i][current.j];
+            *cell = match *cell {
+                '.' => mark,
+                '|' | '-' if *cell != mark => '+',
+                other => other,
+            };
+
+            guard = self.step(current, Some(extra));
+            if let Some(next) = guard {
+                if next.direction != current.direction {
+                    grid[current.i][current.j] = '+';
+                }
             }
         }
-    };
 
-    visit_grid(grid, next_i, next_j, next_direction, visited)
-}
+        grid[start.i][start.j] = start.direction.arrow();
+        grid[extra.0][extra.1] = 'O';
+        frame(&grid)
+    }
 
-fn part_one(
-    grid: &Vec<Vec<char>>,
-    start_i: usize,
-    start_j: usize,
-    direction: Direction,
-) -> HashSet<(usize, usize)> {
-    let mut visited = HashSet::new();
-    visit_grid(grid, start_i, start_j, direction, &mut visited);
-    visited
-        .into_iter()
-        .map(|(i, j, _)| (i, j))
-        .collect::<HashSet<_>>()
-}
+    /// The guard walking its patrol, leaving an `X` on every cell it has
+    /// been on.
+    fn record_patrol(&self, recorder: &mut Recorder, path: &[Guard]) {
+        let every = path.len().div_ceil(MAX_FRAMES).max(1);
+        let mut grid = self.grid.clone();
 
-fn part_two(
-    grid: &mut Vec<Vec<char>>,
-    start_i: usize,
-    start_j: usize,
-    direction: Direction,
-    candidates: HashSet<(usize, usize)>,
-) -> usize {
-    let mut count = 0;
-
-    for (i, j) in candidates {
-        if grid[i][j] == '.' {
-            grid[i][j] = '#';
-            if visit_grid(&grid, start_i, start_j, direction, &mut HashSet::new()) {
-                count += 1;
+        for (k, guard) in path.iter().enumerate() {
+            if k % every == 0 || k + 1 == path.len() {
+                let mut frame = frame(&grid);
+                frame.set(guard.j, guard.i, guard.direction.arrow(), Colour::YELLOW);
+                recorder.push(frame);
             }
-            grid[i][j] = '.';
+            grid[guard.i][guard.j] = 'X';
         }
     }
+}
 
-    count
+fn nearest_after(table: Option<usize>, extra: Option<usize>) -> Option<usize> {
+    match (table, extra) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn part_one(lab: &Lab, start: Guard) -> Vec<Guard> {
+    lab.patrol(start).expect("The guard never leaves the lab")
 }
 
-fn to_string(grid: &Vec<Vec<char>>) -> String {
-    grid.iter()
-        .map(|row| String::from_utf8(row.iter().map(|ch| *ch as u8).collect()).unwrap())
-        .fold(String::new(), |str, row| str + &row + "\n")
+/// Every cell on the patrol (except the start) is a candidate obstruction.
+/// Nothing changes before the guard first reaches it, so each candidate is
+/// checked starting from the state right before that first visit.
+fn candidates(path: &[Guard]) -> Vec<((usize, usize), Guard)> {
+    let start = (path[0].i, path[0].j);
+    let mut seen = std::collections::HashSet::from([start]);
+
+    path.windows(2)
+        .filter(|pair| (pair[0].i, pair[0].j) != (pair[1].i, pair[1].j))
+        .filter(|pair| seen.insert((pair[1].i, pair[1].j)))
+        .map(|pair| ((pair[1].i, pair[1].j), pair[0]))
+        .collect()
+}
+
+fn looping_obstructions(lab: &Lab, path: &[Guard]) -> Vec<(usize, usize)> {
+    let candidates = candidates(path);
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = candidates.len().div_ceil(threads).max(1);
+
+    thread::scope(|scope| {
+        let handles = candidates
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut seen = vec![0; lab.height * lab.width * 4];
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .filter(|(stamp, (obstruction, guard))| {
+                            lab.loops(*guard, *obstruction, &mut seen, *stamp as u32 + 1)
+                        })
+                        .map(|(_, (obstruction, _))| *obstruction)
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+fn frame(grid: &[Vec<char>]) -> Frame {
+    Frame::from_chars(grid, |ch| match ch {
+        '#' => Colour::GREY,
+        'X' | '|' | '-' | '+' => Colour::BLUE,
+        'O' => Colour::RED,
+        '^' | '>' | 'v' | '<' => Colour::YELLOW,
+        _ => Colour::BLACK,
+    })
 }
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+    let show_loop = args.iter().any(|arg| arg == "--show-loop");
     let mut content = String::new();
     File::open("input")
         .unwrap()
         .read_to_string(&mut content)
         .unwrap();
 
-    let (mut grid, start_i, start_j, direction) = parse(&content);
-    let visited = part_one(&grid, start_i, start_j, direction);
-    println!("{}", visited.len());
+    let (grid, start) = parse(&content);
+    let lab = Lab::new(grid);
+    let path = part_one(&lab, start);
+    let visited = candidates(&path).len() + 1;
+    println!("{}", visited);
 
-    let before = SystemTime::now();
-    let part2 = part_two(&mut grid, start_i, start_j, direction, visited);
-    println!(
-        "{part2} time {}s",
-        SystemTime::now()
-            .duration_since(before)
-            .unwrap()
-            .as_secs_f32()
-    );
+    if let Some(output) = Output::from_args(&args) {
+        let mut recorder = Recorder::new()
+            .with_delay(Duration::from_millis(40))
+            .with_scale(2);
+        lab.record_patrol(&mut recorder, &path);
+        recorder.save(&output).unwrap();
+    }
 
-    let visited = (0..grid.len())
-        .flat_map(|i| vec![i; grid[i].len()].into_iter().zip(0..grid[i].len()))
-        .collect::<HashSet<_>>();
     let before = SystemTime::now();
-    let part2 = part_two(&mut grid, start_i, start_j, direction, visited);
+    let obstructions = looping_obstructions(&lab, &path);
     println!(
-        "{part2} time {}s",
+        "{} time {}s",
+        obstructions.len(),
         SystemTime::now()
             .duration_since(before)
             .unwrap()
             .as_secs_f32()
     );
+
+    if show_loop {
+        if let Some(obstruction) = obstructions.first() {
+            print!("{}", lab.render_loop(start, *obstruction));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, fs};
+
+    use crate::{candidates, looping_obstructions, parse, part_one, Direction, Guard, Lab};
+
+    fn lab(content: &str) -> (Lab, Guard) {
+        let (grid, start) = parse(content);
+        (Lab::new(grid), start)
+    }
+
+    /// Whether the guard loops once `extra` is added, walking one cell at a
+    /// time from the very start of the patrol.
+    fn loops_from_start(lab: &Lab, start: Guard, extra: (usize, usize)) -> bool {
+        let mut seen = HashSet::new();
+        let mut guard = Some(start);
+        while let Some(current) = guard {
+            if !seen.insert(lab.state_index(current)) {
+                return true;
+            }
+            guard = lab.step(current, Some(extra));
+        }
+
+        false
+    }
+
+    /// Every obstruction that makes the guard loop, trying each free cell.
+    fn brute_force(lab: &Lab, start: Guard) -> HashSet<(usize, usize)> {
+        (0..lab.height)
+            .flat_map(|i| (0..lab.width).map(move |j| (i, j)))
+            .filter(|&cell| lab.grid[cell.0][cell.1] == '.' && cell != (start.i, start.j))
+            .filter(|&cell| loops_from_start(lab, start, cell))
+            .collect()
+    }
+
+    fn check(content: &str, visited: usize, obstructions: usize) {
+        let (lab, start) = lab(content);
+        let path = part_one(&lab, start);
+        assert_eq!(candidates(&path).len() + 1, visited);
+
+        let found: HashSet<(usize, usize)> =
+            looping_obstructions(&lab, &path).into_iter().collect();
+        assert_eq!(found.len(), obstructions);
+        assert_eq!(found, brute_force(&lab, start));
+    }
+
+    #[test]
+    fn test_example() {
+        check(&fs::read_to_string("test").unwrap(), 41, 6);
+    }
+
+    #[test]
+    fn test_non_square() {
+        let sample = fs::read_to_string("test").unwrap();
+
+        let wide: String = sample
+            .lines()
+            .map(|line| format!("{}.....\n", line))
+            .collect();
+        check(&wide, 41, 6);
+
+        let tall = format!("{}\n{}", sample.trim_end(), "..........\n".repeat(5));
+        check(&tall, 46, 6);
+    }
+
+    #[test]
+    fn test_candidates_match_full_patrol() {
+        let (lab, start) = lab(&fs::read_to_string("test").unwrap());
+        let path = part_one(&lab, start);
+        let mut seen = vec![0; lab.height * lab.width * 4];
+
+        for (stamp, (obstruction, guard)) in candidates(&path).into_iter().enumerate() {
+            assert_eq!(
+                lab.loops(guard, obstruction, &mut seen, stamp as u32 + 1),
+                loops_from_start(&lab, start, obstruction),
+                "{:?}",
+                obstruction
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_loop_arrow() {
+        let (lab, start) = lab(&fs::read_to_string("test").unwrap());
+        let start = Guard {
+            direction: Direction::Left,
+            ..start
+        };
+        let path = part_one(&lab, start);
+        let obstruction = looping_obstructions(&lab, &path)[0];
+        let frame = lab.render_loop(start, obstruction);
+
+        assert_eq!(frame.get(start.j, start.i).symbol, '<');
+    }
 }
@@ -1,140 +1,222 @@
-use std::collections::HashMap;
-use std::{fs::File, io::Read};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::fmt::Display;
+use std::{env, fs::File, io::Read};
 
-#[derive(Clone)]
-enum Block {
-    Free(usize),
-    File(usize),
+/// `length` consecutive blocks of file `file_id` starting at block `offset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Extent {
+    file_id: usize,
+    offset: usize,
+    length: usize,
 }
 
-impl Block {
-    fn is_free(&self) -> bool {
-        match self {
-            Block::Free(_) => true,
-            Block::File(_) => false,
-        }
+impl Extent {
+    fn end(&self) -> usize {
+        self.offset + self.length
     }
 
-    fn is_file(&self) -> bool {
-        !self.is_free()
+    fn checksum(&self) -> usize {
+        // sum of file_id * position for every position in offset..end
+        self.file_id * (self.offset + self.end() - 1) * self.length / 2
     }
+}
 
-    fn get_file_id(&self) -> Option<usize> {
-        match self {
-            Block::Free(_) => None,
-            Block::File(file_id) => Some(*file_id),
+/// A disk as a list of file extents sorted by offset. Everything between them
+/// is free space.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Disk {
+    extents: Vec<Extent>,
+    size: usize,
+}
+
+impl Disk {
+    fn parse(content: &str) -> Self {
+        let mut extents = Vec::new();
+        let mut offset = 0;
+        for (i, ch) in content.trim().chars().enumerate() {
+            let length = ch.to_digit(10).unwrap() as usize;
+            if i.is_multiple_of(2) && length > 0 {
+                extents.push(Extent {
+                    file_id: i / 2,
+                    offset,
+                    length,
+                });
+            }
+            offset += length;
+        }
+
+        Disk {
+            extents,
+            size: offset,
         }
     }
-}
 
-fn parse(content: &str) -> (Vec<Block>, HashMap<usize, usize>, HashMap<usize, usize>) {
-    let parsed = content.chars().map(|ch| ch.to_digit(10).unwrap() as usize);
-
-    let mut disk = Vec::new();
-    let mut file_sizes = HashMap::new();
-    let mut free_sizes = HashMap::new();
-    for (i, blocks) in parsed.enumerate() {
-        let block_id = if i % 2 == 0 {
-            Block::File(i / 2)
-        } else {
-            Block::Free(i / 2)
-        };
-        let mut new_blocks = vec![block_id; blocks];
-        disk.append(&mut new_blocks);
-        if i % 2 == 0 {
-            file_sizes.insert(i / 2, blocks);
-        } else {
-            free_sizes.insert(i / 2, blocks);
+    /// Free space between and after the extents as `(offset, length)`.
+    fn gaps(&self) -> Vec<(usize, usize)> {
+        let mut gaps = Vec::new();
+        let mut position = 0;
+        for extent in &self.extents {
+            if extent.offset > position {
+                gaps.push((position, extent.offset - position));
+            }
+            position = extent.end();
+        }
+        if self.size > position {
+            gaps.push((position, self.size - position));
         }
+
+        gaps
     }
 
-    (disk, file_sizes, free_sizes)
-}
+    /// Moves single blocks from the end of the disk into the leftmost free
+    /// block until there are no gaps left, splitting files as needed.
+    fn compact_blocks(&self) -> Disk {
+        let mut files: VecDeque<Extent> = self.extents.iter().copied().collect();
+        let mut extents = Vec::new();
+        let mut position = 0;
+
+        while let Some(front) = files.pop_front() {
+            let mut gap = front.offset - position;
+            while gap > 0 {
+                let Some(back) = files.pop_back() else {
+                    break;
+                };
+
+                let taken = gap.min(back.length);
+                extents.push(Extent {
+                    file_id: back.file_id,
+                    offset: position,
+                    length: taken,
+                });
+                position += taken;
+                gap -= taken;
+
+                if back.length > taken {
+                    files.push_back(Extent {
+                        length: back.length - taken,
+                        ..back
+                    });
+                }
+            }
 
-fn format1(disk: &mut Vec<Block>) {
-    let mut free_index = 0;
-    let mut file_index = disk.len() - 1;
+            extents.push(Extent {
+                offset: position,
+                ..front
+            });
+            position += front.length;
+        }
 
-    loop {
-        while disk[free_index].is_file() {
-            free_index += 1;
+        Disk {
+            extents,
+            size: self.size,
         }
+    }
 
-        while disk[file_index].is_free() {
-            file_index -= 1;
+    /// Moves every whole file, highest id first, into the leftmost gap before
+    /// it that is big enough. The gaps are indexed by length in min-heaps of
+    /// offsets, so finding the leftmost fitting gap only looks at one heap top
+    /// per possible gap length.
+    fn compact_files(&self) -> Disk {
+        let gaps = self.gaps();
+        let max_gap = gaps.iter().map(|(_, length)| *length).max().unwrap_or(0);
+        let mut free: Vec<BinaryHeap<Reverse<usize>>> = vec![BinaryHeap::new(); max_gap + 1];
+        for (offset, length) in gaps {
+            free[length].push(Reverse(offset));
         }
 
-        if free_index >= file_index {
-            return;
+        let mut extents = self.extents.clone();
+        extents.sort_by_key(|extent| Reverse(extent.file_id));
+
+        for extent in extents.iter_mut() {
+            let best = (extent.length..=max_gap)
+                .filter_map(|length| free[length].peek().map(|offset| (offset.0, length)))
+                .filter(|(offset, _)| *offset < extent.offset)
+                .min();
+
+            if let Some((offset, length)) = best {
+                free[length].pop();
+                extent.offset = offset;
+                if length > extent.length {
+                    free[length - extent.length].push(Reverse(offset + extent.length));
+                }
+            }
         }
 
-        disk.swap(free_index, file_index);
-        free_index += 1;
-        file_index -= 1;
+        extents.sort_by_key(|extent| extent.offset);
+        Disk {
+            extents,
+            size: self.size,
+        }
     }
-}
 
-fn part_one(mut disk: &mut Vec<Block>) -> usize {
-    format1(&mut disk);
-    calculate_checksum(&disk)
+    fn checksum(&self) -> usize {
+        self.extents.iter().map(Extent::checksum).sum()
+    }
+
+    /// Writes the layout back in the dense format. File ids are implied by
+    /// position there, so the ids are renumbered in disk order. Runs longer
+    /// than 9 blocks and files that touch are separated by zero-length entries.
+    fn to_dense(&self) -> String {
+        let mut dense = Dense::default();
+        let mut position = 0;
+        for extent in &self.extents {
+            dense.push(false, extent.offset - position);
+            dense.push(true, extent.length);
+            position = extent.end();
+        }
+        dense.push(false, self.size - position);
+
+        dense.digits
+    }
 }
 
-fn calculate_checksum(disk: &Vec<Block>) -> usize {
-    disk.iter()
-        .enumerate()
-        .fold(0, |checksum, (index, block)| match block {
-            Block::File(file_id) => checksum + index * file_id,
-            Block::Free(_) => checksum,
-        })
+/// Builds the dense format, where digits alternate between file and free
+/// lengths starting with a file.
+#[derive(Default)]
+struct Dense {
+    digits: String,
 }
 
-fn format2(
-    disk: &Vec<Block>,
-    mut file_sizes: HashMap<usize, usize>,
-    mut free_sizes: HashMap<usize, usize>,
-) -> Vec<Block> {
-    let mut formatted = disk.clone();
-    let mut file_index = disk.len() - 1;
-    let max_file_id = file_sizes.keys().max().unwrap();
-    for file_id in (0..max_file_id + 1).rev() {
-        if file_id % 100 == 0 {
-            println!("{file_id}");
+impl Dense {
+    fn next_is_file(&self) -> bool {
+        self.digits.len().is_multiple_of(2)
+    }
+
+    fn push(&mut self, is_file: bool, mut length: usize) {
+        if length == 0 {
+            return;
         }
-        while formatted[file_index].is_free()
-            || formatted[file_index].get_file_id().unwrap() != file_id
-        {
-            file_index -= 1;
+
+        if self.next_is_file() != is_file {
+            self.digits.push('0');
         }
 
-        let mut free_index = 0;
-        while free_index < file_index {
-            match formatted[free_index] {
-                Block::Free(free_id) => {
-                    if free_sizes[&free_id] < file_sizes[&file_id] {
-                        free_index += free_sizes[&free_id];
-                    } else {
-                        for i in 0..file_sizes[&file_id] {
-                            formatted.swap(free_index + i, file_index - i);
-                        }
-                        *free_sizes.get_mut(&free_id).unwrap() -= file_sizes[&file_id];
-                        break;
-                    }
-                }
-                Block::File(_) => free_index += 1,
-            }
+        while length > 9 {
+            self.digits.push('9');
+            self.digits.push('0');
+            length -= 9;
         }
+        self.digits
+            .push(char::from_digit(length as u32, 10).unwrap());
     }
-
-    formatted
 }
 
-fn part_two(
-    mut disk: &mut Vec<Block>,
-    file_sizes: HashMap<usize, usize>,
-    mut free_sizes: HashMap<usize, usize>,
-) -> usize {
-    let formatted = format2(&mut disk, file_sizes, free_sizes);
-    calculate_checksum(&formatted)
+/// Draws one character per block like the puzzle text: the file id for used
+/// blocks and `.` for free ones. Ids wrap around after 9, so only small disks
+/// read unambiguously.
+impl Display for Disk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut position = 0;
+        for extent in &self.extents {
+            write!(f, "{}", ".".repeat(extent.offset - position))?;
+            let id = char::from_digit((extent.file_id % 10) as u32, 10).unwrap();
+            write!(f, "{}", id.to_string().repeat(extent.length))?;
+            position = extent.end();
+        }
+
+        write!(f, "{}", ".".repeat(self.size - position))
+    }
 }
 
 fn main() {
@@ -144,7 +226,105 @@ fn main() {
         .read_to_string(&mut content)
         .unwrap();
 
-    let (mut disk, file_sizes, free_sizes) = parse(&content);
-    println!("{}", part_one(&mut disk.clone()));
-    println!("{}", part_two(&mut disk, file_sizes, free_sizes));
+    let disk = Disk::parse(&content);
+    let blocks = disk.compact_blocks();
+    let files = disk.compact_files();
+
+    if env::args().any(|arg| arg == "--show") {
+        println!("{}\n{}\n{}", disk, blocks, files);
+        println!("{}\n{}", blocks.to_dense(), files.to_dense());
+    }
+
+    println!("{}", blocks.checksum());
+    println!("{}", files.checksum());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{Disk, Extent};
+
+    fn example() -> Disk {
+        Disk::parse(&fs::read_to_string("test").unwrap())
+    }
+
+    /// The same layout with the files numbered in disk order, the way
+    /// `to_dense` numbers them.
+    fn renumbered(disk: &Disk) -> Disk {
+        Disk {
+            extents: disk
+                .extents
+                .iter()
+                .enumerate()
+                .map(|(file_id, extent)| Extent { file_id, ..*extent })
+                .collect(),
+            size: disk.size,
+        }
+    }
+
+    /// Which blocks are used, ignoring the file ids.
+    fn layout(disk: &Disk) -> String {
+        disk.to_string()
+            .chars()
+            .map(|ch| if ch == '.' { '.' } else { '#' })
+            .collect()
+    }
+
+    #[test]
+    fn test_checksums() {
+        let disk = example();
+
+        assert_eq!(disk.compact_blocks().checksum(), 1928);
+        assert_eq!(disk.compact_files().checksum(), 2858);
+    }
+
+    #[test]
+    fn test_display() {
+        let disk = example();
+
+        assert_eq!(
+            disk.to_string(),
+            "00...111...2...333.44.5555.6666.777.888899"
+        );
+        assert_eq!(
+            disk.compact_blocks().to_string(),
+            "0099811188827773336446555566.............."
+        );
+        assert_eq!(
+            disk.compact_files().to_string(),
+            "00992111777.44.333....5555.6666.....8888.."
+        );
+    }
+
+    #[test]
+    fn test_dense_round_trip() {
+        let disk = example();
+        assert_eq!(Disk::parse(&disk.to_dense()), disk);
+
+        let files = disk.compact_files();
+        assert_eq!(Disk::parse(&files.to_dense()), renumbered(&files));
+
+        let blocks = disk.compact_blocks();
+        assert_eq!(layout(&Disk::parse(&blocks.to_dense())), layout(&blocks));
+
+        // Runs longer than 9 blocks and files that touch come back as several
+        // files, but the blocks stay where they were.
+        let disk = Disk {
+            extents: vec![
+                Extent {
+                    file_id: 0,
+                    offset: 12,
+                    length: 20,
+                },
+                Extent {
+                    file_id: 1,
+                    offset: 32,
+                    length: 3,
+                },
+            ],
+            size: 45,
+        };
+        assert_eq!(layout(&Disk::parse(&disk.to_dense())), layout(&disk));
+    }
 }
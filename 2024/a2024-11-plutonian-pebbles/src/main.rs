@@ -1,10 +1,14 @@
-use std::collections::HashMap;
+use common::cycle::{self, Cycle};
+use num_bigint::BigUint;
+use std::collections::{BTreeSet, HashMap};
+use std::env;
 use std::fs::File;
 use std::io::Read;
+use std::str::FromStr;
 
 fn parse(content: &str) -> Vec<u64> {
     content
-        .split(" ")
+        .split_whitespace()
         .map(|stone| stone.parse().unwrap())
         .collect()
 }
@@ -19,87 +23,266 @@ fn digit_count(mut stone: u64) -> u32 {
     count
 }
 
-fn blink(stones: &Vec<u64>) -> Vec<u64> {
-    let mut new_stones = Vec::with_capacity(stones.len());
-    for stone in stones {
-        if *stone == 0 {
-            new_stones.push(1);
-        } else {
-            let digits = digit_count(*stone);
-            if digits % 2 == 0 {
-                let half_digits = 10u64.pow(digits / 2);
-                let first = stone / half_digits;
-                let second = stone % half_digits;
-                new_stones.push(first);
-                new_stones.push(second);
-            } else {
-                let first = stone * 2024;
-                new_stones.push(first);
-            }
-        }
-    }
-    new_stones
+/// What a single stone turns into when blinking. Stones never affect each
+/// other, so this is all a rule set has to define.
+trait Rules {
+    fn blink(&self, stone: u64) -> Vec<u64>;
 }
 
-fn blink_recursive(
-    stone: u64,
-    times: usize,
-    mut cache: &mut HashMap<(u64, usize), usize>,
-) -> usize {
-    if let Some(stone_count) = cache.get(&(stone, times)) {
-        return *stone_count;
-    }
+/// The rules from the puzzle: `0` becomes `1`, a stone with an even number of
+/// digits splits into its two halves and any other stone is multiplied by
+/// `multiplier`.
+struct Plutonian {
+    multiplier: u64,
+}
 
-    if times == 0 {
-        return 1;
+impl Default for Plutonian {
+    fn default() -> Self {
+        Self { multiplier: 2024 }
     }
+}
+
+impl Rules for Plutonian {
+    fn blink(&self, stone: u64) -> Vec<u64> {
+        if stone == 0 {
+            return vec![1];
+        }
 
-    let stone_count = if stone == 0 {
-        blink_recursive(1, times - 1, &mut cache)
-    } else {
         let digits = digit_count(stone);
-        if digits % 2 == 0 {
+        if digits.is_multiple_of(2) {
             let half_digits = 10u64.pow(digits / 2);
-            let left_half = stone / half_digits;
-            let right_half = stone % half_digits;
-            blink_recursive(left_half, times - 1, &mut cache)
-                + blink_recursive(right_half, times - 1, &mut cache)
+            vec![stone / half_digits, stone % half_digits]
         } else {
-            blink_recursive(stone * 2024, times - 1, &mut cache)
+            vec![stone
+                .checked_mul(self.multiplier)
+                .unwrap_or_else(|| panic!("Stone {} grew past u64", stone))]
         }
-    };
+    }
+}
+
+/// The stones as a multiset: how many stones carry each number. The order of
+/// the stones never matters for counting them, and there are only a few
+/// thousand distinct numbers even when the total no longer fits in a `u128`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Stones {
+    counts: HashMap<u64, BigUint>,
+}
+
+impl Stones {
+    fn new(stones: &[u64]) -> Self {
+        let mut counts = HashMap::new();
+        for stone in stones {
+            *counts.entry(*stone).or_insert_with(BigUint::default) += 1u32;
+        }
+
+        Self { counts }
+    }
 
-    cache.insert((stone, times), stone_count);
-    stone_count
+    fn blink(&self, rules: &impl Rules) -> Self {
+        let mut counts = HashMap::with_capacity(self.counts.len());
+        for (stone, count) in &self.counts {
+            for next in rules.blink(*stone) {
+                *counts.entry(next).or_insert_with(BigUint::default) += count;
+            }
+        }
+
+        Self { counts }
+    }
+
+    fn distinct(&self) -> usize {
+        self.counts.len()
+    }
+
+    fn total(&self) -> BigUint {
+        self.counts.values().sum()
+    }
+}
+
+/// The stones after one blink.
+struct BlinkStats {
+    distinct: usize,
+    total: BigUint,
 }
 
-fn part_one(stones: &Vec<u64>, times: usize) -> usize {
-    let mut current_stones = stones.clone();
-    for i in 0..times {
-        if i % 5 == 0 {
-            println!("{i}");
+/// Statistics before the first blink and after each of the next `times`
+/// blinks.
+fn evolve(stones: &[u64], rules: &impl Rules, times: usize) -> Vec<BlinkStats> {
+    let mut current = Stones::new(stones);
+    let mut stats = Vec::with_capacity(times + 1);
+
+    for i in 0..=times {
+        stats.push(BlinkStats {
+            distinct: current.distinct(),
+            total: current.total(),
+        });
+        if i < times {
+            current = current.blink(rules);
         }
-        current_stones = blink(&current_stones);
     }
 
-    current_stones.len()
+    stats
 }
 
-fn part_two(stones: &Vec<u64>, times: usize) -> usize {
-    let mut cache = HashMap::new();
-    stones
-        .iter()
-        .map(|stone| blink_recursive(*stone, times, &mut cache))
-        .sum()
+/// With the puzzle's rules the set of distinct stone values eventually stops
+/// changing (or keeps repeating), even though the stone count keeps growing.
+/// Other rules may keep making new values, so this gives up after `blinks`.
+fn distinct_stones_cycle(stones: &[u64], rules: &impl Rules, blinks: u64) -> Option<Cycle> {
+    let distinct: BTreeSet<u64> = stones.iter().copied().collect();
+
+    cycle::find_cycle_within(&distinct, blinks, |distinct| {
+        *distinct = distinct
+            .iter()
+            .flat_map(|stone| rules.blink(*stone))
+            .collect();
+    })
+}
+
+/// The blink count after `flag`, or `Err` with a usage line if it is missing
+/// or not a number.
+fn blink_count<T: FromStr>(args: &[String], flag: &str) -> Result<Option<T>, String> {
+    let Some(position) = args.iter().position(|arg| arg == flag) else {
+        return Ok(None);
+    };
+
+    args.get(position + 1)
+        .and_then(|count| count.parse().ok())
+        .map(Some)
+        .ok_or_else(|| format!("Usage: {} <blinks>", flag))
 }
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+    let (blinks, limit) = match (
+        blink_count::<usize>(&args, "--blinks"),
+        blink_count::<u64>(&args, "--distinct-cycle"),
+    ) {
+        (Ok(blinks), Ok(limit)) => (blinks, limit),
+        (Err(usage), _) | (_, Err(usage)) => {
+            println!("{}", usage);
+            return;
+        }
+    };
+
     let mut content = String::new();
     File::open("input")
         .unwrap()
         .read_to_string(&mut content)
         .unwrap();
     let stones = parse(&content);
-    println!("{:?}", part_one(&stones, 25));
-    println!("{:?}", part_two(&stones, 75));
+    let rules = Plutonian::default();
+
+    let stats = evolve(&stones, &rules, blinks.unwrap_or(75).max(75));
+    println!("{}", stats[25].total);
+    println!("{}", stats[75].total);
+
+    if let Some(blinks) = blinks {
+        for (i, blink) in stats.iter().enumerate().take(blinks + 1) {
+            println!("{} {} {}", i, blink.distinct, blink.total);
+        }
+    }
+
+    if let Some(limit) = limit {
+        match distinct_stones_cycle(&stones, &rules, limit) {
+            Some(distinct) => println!(
+                "Distinct stones repeat from blink {} every {} blinks",
+                distinct.start, distinct.length
+            ),
+            None => println!("Distinct stones don't repeat within {} blinks", limit),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+    use std::collections::HashSet;
+
+    use crate::{distinct_stones_cycle, evolve, parse, Plutonian, Rules};
+
+    /// Every stone doubles its number and never splits.
+    struct Doubling;
+
+    impl Rules for Doubling {
+        fn blink(&self, stone: u64) -> Vec<u64> {
+            vec![stone * 2]
+        }
+    }
+
+    /// Odd stones split into two equal halves rounded down and up, even ones
+    /// stay as they are.
+    struct Halving;
+
+    impl Rules for Halving {
+        fn blink(&self, stone: u64) -> Vec<u64> {
+            if stone % 2 == 1 && stone > 1 {
+                vec![stone / 2, stone / 2 + 1]
+            } else {
+                vec![stone]
+            }
+        }
+    }
+
+    /// Blinks one stone at a time, keeping every stone in a list.
+    fn naive(stones: &[u64], rules: &impl Rules, times: usize) -> Vec<(usize, BigUint)> {
+        let mut stones = stones.to_vec();
+        let mut stats = Vec::new();
+        for i in 0..=times {
+            let distinct: HashSet<u64> = stones.iter().copied().collect();
+            stats.push((distinct.len(), BigUint::from(stones.len())));
+            if i < times {
+                stones = stones
+                    .iter()
+                    .flat_map(|stone| rules.blink(*stone))
+                    .collect();
+            }
+        }
+
+        stats
+    }
+
+    fn check_naive(stones: &[u64], rules: &impl Rules, times: usize) {
+        let stats: Vec<(usize, BigUint)> = evolve(stones, rules, times)
+            .into_iter()
+            .map(|blink| (blink.distinct, blink.total))
+            .collect();
+
+        assert_eq!(stats, naive(stones, rules, times));
+    }
+
+    #[test]
+    fn test_example() {
+        let stats = evolve(&parse("125 17"), &Plutonian::default(), 25);
+
+        assert_eq!(stats[6].total, BigUint::from(22u32));
+        assert_eq!(stats[25].total, BigUint::from(55312u32));
+    }
+
+    #[test]
+    fn test_matches_naive() {
+        check_naive(&parse("125 17"), &Plutonian::default(), 20);
+        check_naive(&parse("0 1 10 99 999"), &Plutonian::default(), 20);
+        check_naive(&parse("125 17"), &Plutonian { multiplier: 3 }, 20);
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let stats = evolve(&[3, 5], &Doubling, 10);
+        assert_eq!(stats[10].total, BigUint::from(2u32));
+        assert_eq!(stats[10].distinct, 2);
+        check_naive(&[3, 5], &Doubling, 20);
+
+        check_naive(&[1000001, 77], &Halving, 20);
+        let stats = evolve(&[7], &Halving, 3);
+        // 7 -> 3 4 -> 1 2 4 -> 1 2 4
+        assert_eq!(
+            stats.iter().map(|blink| blink.distinct).collect::<Vec<_>>(),
+            vec![1, 2, 3, 3]
+        );
+    }
+
+    #[test]
+    fn test_distinct_stones_cycle() {
+        assert!(distinct_stones_cycle(&parse("125 17"), &Plutonian::default(), 200).is_some());
+        assert_eq!(distinct_stones_cycle(&[3, 5], &Doubling, 40), None);
+    }
 }
@@ -1,44 +1,95 @@
+use common::rational::{gcd, Rational};
 use std::fs::File;
 use std::io::Read;
 
-#[derive(Debug)]
+const A_TOKENS: i128 = 3;
+const B_TOKENS: i128 = 1;
+
+/// The inverse of `a` modulo `m`, for `a` and `m` coprime.
+fn mod_inverse(a: i128, m: i128) -> i128 {
+    let (mut old_r, mut r) = (a.rem_euclid(m), m);
+    let (mut old_s, mut s) = (1, 0);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_s, s) = (s, old_s - quotient * s);
+    }
+
+    old_s.rem_euclid(m)
+}
+
+fn div_ceil(a: i128, b: i128) -> i128 {
+    -(-a).div_euclid(b)
+}
+
+/// The presses of a machine whose buttons move the claw along the same line:
+/// `b = free + coef * a`.
+#[derive(Debug, PartialEq)]
 pub struct SolutionFormula {
-    pub free: f64,
-    pub coef: f64,
+    pub free: Rational,
+    pub coef: Rational,
 }
 
 impl SolutionFormula {
-    pub fn new(free: f64, coef: f64) -> Self {
+    pub fn new(free: Rational, coef: Rational) -> Self {
         Self { free, coef }
     }
 
-    pub fn find_optimal(&self) -> Option<u64> {
-        for a in 0..101 {
-            if let Some(b) = self.get_b(a) {
-                if b < 0 {
+    /// The fewest tokens over every integer point of the line with both press
+    /// counts non-negative and at most `limit`, if given. The cost is linear
+    /// along the line, so the optimum is one of the two ends of the valid
+    /// stretch.
+    pub fn find_optimal(&self, limit: Option<u64>) -> Option<u64> {
+        // b = (f + c * a) / d
+        let d = self.free.denominator / gcd(self.free.denominator, self.coef.denominator)
+            * self.coef.denominator;
+        let f = self.free.numerator * (d / self.free.denominator);
+        let c = self.coef.numerator * (d / self.coef.denominator);
+
+        // b is an integer exactly when c * a = -f (mod d), which holds for
+        // every `step`-th a starting at `first`.
+        let g = gcd(c, d);
+        if f.rem_euclid(g) != 0 {
+            return None;
+        }
+        let step = d / g;
+        let first = ((-f / g).rem_euclid(step) * mod_inverse(c / g, step)).rem_euclid(step);
+
+        let limit = limit.map(|limit| limit as i128);
+        let mut low = 0;
+        let mut high = limit;
+        let mut bound_below = |bound: i128| low = low.max(bound);
+        match c.signum() {
+            1 => {
+                bound_below(div_ceil(-f, c));
+                if let Some(limit) = limit {
+                    high = Some((limit * d - f).div_euclid(c).min(limit));
+                }
+            }
+            -1 => {
+                if let Some(limit) = limit {
+                    bound_below(div_ceil(f - limit * d, -c));
+                }
+                high = Some(high.map_or(f.div_euclid(-c), |high| high.min(f.div_euclid(-c))));
+            }
+            _ => {
+                if f < 0 || limit.is_some_and(|limit| f > limit * d) {
                     return None;
                 }
-
-                return Some(3 * a + b as u64);
             }
         }
 
-        None
-    }
-
-    fn get_b(&self, a: u64) -> Option<i64> {
-        let b = self.free + self.coef * (a as f64);
-        if b > 100.0 || (b.round() - b).abs() > DELTA {
+        let a = if A_TOKENS * d + B_TOKENS * c >= 0 {
+            low + (first - low).rem_euclid(step)
+        } else {
+            let high = high?;
+            high - (high - first).rem_euclid(step)
+        };
+        if a < low || high.is_some_and(|high| a > high) {
             return None;
         }
 
-        Some(b.round() as i64)
-    }
-}
-
-impl PartialEq for SolutionFormula {
-    fn eq(&self, other: &Self) -> bool {
-        (self.free - other.free).abs() < DELTA && (self.coef - other.coef).abs() < DELTA
+        Some(tokens(a, (f + c * a) / d))
     }
 }
 
@@ -49,56 +100,70 @@ pub enum Solution {
     None,
 }
 
+/// `a_x * a + b_x * b = p_x` and `a_y * a + b_y * b = p_y`, stored as
+/// `coef = [[a_x, b_x], [a_y, b_y]]` and `free = [p_x, p_y]`.
 pub struct Equation {
-    coef: [[f64; 2]; 2],
-    free: [f64; 2],
+    coef: [[i64; 2]; 2],
+    free: [i64; 2],
 }
 
-const DELTA: f64 = 0.001;
 impl Equation {
-    pub fn new(coef: [[f64; 2]; 2], free: [f64; 2]) -> Self {
+    pub fn new(coef: [[i64; 2]; 2], free: [i64; 2]) -> Self {
         Self { coef, free }
     }
 
-    pub fn solve(&mut self) -> Solution {
-        self.gauss();
+    /// Solves the system exactly with Cramer's rule. When the determinant is
+    /// zero the buttons are collinear and either no presses or a whole line of
+    /// them reach the prize.
+    pub fn solve(&self) -> Solution {
+        let [[ax, bx], [ay, by]] = self.coef.map(|row| row.map(|value| value as i128));
+        let [px, py] = self.free.map(|value| value as i128);
 
-        if self.coef[1][1].abs() < DELTA {
-            if self.free[1] > DELTA {
+        let determinant = ax * by - bx * ay;
+        if determinant != 0 {
+            let a = px * by - bx * py;
+            let b = ax * py - px * ay;
+            if a % determinant != 0 || b % determinant != 0 {
                 return Solution::None;
             }
 
-            Solution::Formula(SolutionFormula::new(
-                self.free[0] / self.coef[0][1],
-                -self.coef[0][0] / self.coef[0][1],
-            ))
-        } else {
-            let b_presses = self.free[1] / self.coef[1][1];
-            let a_presses = (self.free[0] - b_presses * self.coef[0][1]) / self.coef[0][0];
-
-            if (b_presses - b_presses.round()).abs() > DELTA
-                || (a_presses - a_presses.round()).abs() > DELTA
-                || a_presses < 0.0
-                || b_presses < 0.0
-            {
+            let (a, b) = (a / determinant, b / determinant);
+            if a < 0 || b < 0 {
                 return Solution::None;
             }
 
-            Solution::Unique(a_presses.round() as u64, b_presses.round() as u64)
+            return Solution::Unique(a as u64, b as u64);
+        }
+
+        // With a zero determinant the equations agree only if every other
+        // minor of the augmented matrix vanishes too.
+        if ax * py != px * ay || bx * py != px * by {
+            return Solution::None;
+        }
+
+        let rows = [(ax, bx, px), (ay, by, py)];
+        if let Some((a_coef, b_coef, free)) = rows.iter().find(|row| row.1 != 0) {
+            return Solution::Formula(SolutionFormula::new(
+                Rational::new(*free, *b_coef),
+                Rational::new(-a_coef, *b_coef),
+            ));
         }
-    }
 
-    fn gauss(&mut self) {
-        let g = -self.coef[1][0] / self.coef[0][0];
-        self.coef[1][0] = 0.0;
-        self.coef[1][1] += g * self.coef[0][1];
-        self.free[1] += g * self.free[0];
+        // Button B doesn't move the claw, so it is never worth pressing.
+        match rows.iter().find(|row| row.0 != 0) {
+            Some((a_coef, _, free)) if free % a_coef == 0 && free / a_coef >= 0 => {
+                Solution::Unique((free / a_coef) as u64, 0)
+            }
+            Some(_) => Solution::None,
+            None if px == 0 && py == 0 => Solution::Unique(0, 0),
+            None => Solution::None,
+        }
     }
 }
 
 impl From<&str> for Equation {
     fn from(value: &str) -> Self {
-        let mut rows = value
+        let rows = value
             .lines()
             .map(|line| {
                 line.split(": ")
@@ -107,10 +172,10 @@ impl From<&str> for Equation {
                     .split(", ")
                     .map(|coord| {
                         coord
-                            .split(|ch| ch == '+' || ch == '=')
+                            .split(['+', '='])
                             .nth(1)
                             .unwrap()
-                            .parse::<f64>()
+                            .parse::<i64>()
                             .unwrap()
                     })
                     .collect::<Vec<_>>()
@@ -124,15 +189,23 @@ impl From<&str> for Equation {
     }
 }
 
+fn tokens(a: i128, b: i128) -> u64 {
+    (A_TOKENS * a + B_TOKENS * b) as u64
+}
+
+fn cheapest(equation: &Equation, limit: Option<u64>) -> Option<u64> {
+    match equation.solve() {
+        Solution::Unique(a, b) => Some(tokens(a as i128, b as i128)),
+        Solution::Formula(formula) => formula.find_optimal(limit),
+        Solution::None => None,
+    }
+}
+
 fn part_one(content: &str) -> u64 {
     content
         .split("\n\n")
-        .map(|equation| Equation::from(equation))
-        .filter_map(|mut equation| match equation.solve() {
-            Solution::Unique(a, b) => Some(3 * a + b),
-            Solution::Formula(formula) => formula.find_optimal(),
-            Solution::None => None,
-        })
+        .map(Equation::from)
+        .filter_map(|equation| cheapest(&equation, Some(100)))
         .sum()
 }
 
@@ -143,14 +216,10 @@ fn part_two(content: &str) -> u64 {
             let e = Equation::from(equation);
             Equation::new(
                 e.coef,
-                [10000000000000.0 + e.free[0], 10000000000000.0 + e.free[1]],
+                [10000000000000 + e.free[0], 10000000000000 + e.free[1]],
             )
         })
-        .filter_map(|mut equation| match equation.solve() {
-            Solution::Unique(a, b) => Some(3 * a + b),
-            Solution::Formula(formula) => formula.find_optimal(),
-            Solution::None => None,
-        })
+        .filter_map(|equation| cheapest(&equation, None))
         .sum()
 }
 
@@ -166,45 +235,114 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use crate::{Equation, Solution, SolutionFormula};
+    use std::fs;
+
+    use common::rational::Rational;
+
+    use crate::{part_one, part_two, Equation, Solution, SolutionFormula};
 
     #[test]
     fn test_solve() {
         assert_eq!(
-            Equation::new([[2.0, 1.0], [1.0, 1.0]], [5.0, 3.0]).solve(),
+            Equation::new([[2, 1], [1, 1]], [5, 3]).solve(),
             Solution::Unique(2, 1)
         );
         assert_eq!(
-            Equation::new([[2.0, 1.0], [4.0, 1.0]], [2.0, 3.0]).solve(),
+            Equation::new([[2, 1], [4, 1]], [2, 3]).solve(),
             Solution::None
         );
         assert_eq!(
-            Equation::new([[1.0, 1.0], [2.0, 1.0]], [3.0, 1.0]).solve(),
+            Equation::new([[1, 1], [2, 1]], [3, 1]).solve(),
             Solution::None
         );
         assert_eq!(
-            Equation::new([[1.0, 2.0], [1.0, 4.0]], [2.0, 3.0]).solve(),
+            Equation::new([[1, 2], [1, 4]], [2, 3]).solve(),
             Solution::None
         );
         assert_eq!(
-            Equation::new([[1.0, 1.0], [1.0, 2.0]], [3.0, 1.0]).solve(),
+            Equation::new([[1, 1], [1, 2]], [3, 1]).solve(),
             Solution::None
         );
 
         assert_eq!(
-            Equation::new([[1.0, 2.0], [2.0, 4.0]], [2.0, 4.0]).solve(),
-            Solution::Formula(SolutionFormula::new(1.0, -0.5))
+            Equation::new([[1, 2], [2, 4]], [2, 4]).solve(),
+            Solution::Formula(SolutionFormula::new(
+                Rational::new(1, 1),
+                Rational::new(-1, 2)
+            ))
         );
         assert_eq!(
-            Equation::new([[1.0, 2.0], [2.0, 4.0]], [2.0, 5.0]).solve(),
+            Equation::new([[1, 2], [2, 4]], [2, 5]).solve(),
             Solution::None
         );
     }
 
     #[test]
     fn test_find_optimal() {
-        assert_eq!(SolutionFormula::new(1.5, -0.5).find_optimal(), Some(4));
-        assert_eq!(SolutionFormula::new(1.7, -0.3).find_optimal(), None);
-        assert_eq!(SolutionFormula::new(1000.0, -1.0).find_optimal(), None);
+        assert_eq!(
+            SolutionFormula::new(Rational::new(3, 2), Rational::new(-1, 2)).find_optimal(Some(100)),
+            Some(4)
+        );
+        assert_eq!(
+            SolutionFormula::new(Rational::new(17, 10), Rational::new(-3, 10))
+                .find_optimal(Some(100)),
+            None
+        );
+        assert_eq!(
+            SolutionFormula::new(Rational::new(1000, 1), Rational::new(-1, 1))
+                .find_optimal(Some(100)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_optimal_without_limit() {
+        // b = 1000 - a, cheapest with as few A presses as possible
+        assert_eq!(
+            SolutionFormula::new(Rational::new(1000, 1), Rational::new(-1, 1)).find_optimal(None),
+            Some(1000)
+        );
+        // b = 1000 - 4a, where A is worth more than its three tokens
+        assert_eq!(
+            SolutionFormula::new(Rational::new(1000, 1), Rational::new(-4, 1)).find_optimal(None),
+            Some(750)
+        );
+        // a and b both grow along the line, so the fewest A presses with an
+        // integer b win: a = 1, b = 5
+        assert_eq!(
+            SolutionFormula::new(Rational::new(-2, 3), Rational::new(17, 3)).find_optimal(None),
+            Some(8)
+        );
+    }
+
+    #[test]
+    fn test_collinear_brute_force() {
+        for ax in 1..6 {
+            for bx in 1..6 {
+                for k in 1..4 {
+                    for px in 0..60 {
+                        let expected = (0..=100)
+                            .flat_map(|a| (0..=100).map(move |b| (a, b)))
+                            .filter(|(a, b)| a * ax + b * bx == px)
+                            .map(|(a, b)| 3 * a as u64 + b as u64)
+                            .min();
+                        let equation = Equation::new([[ax, bx], [k * ax, k * bx]], [px, k * px]);
+                        let actual = match equation.solve() {
+                            Solution::Unique(a, b) => Some(3 * a + b),
+                            Solution::Formula(formula) => formula.find_optimal(Some(100)),
+                            Solution::None => None,
+                        };
+                        assert_eq!(actual, expected, "{} {} {} {}", ax, bx, k, px);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_example() {
+        let content = fs::read_to_string("test").unwrap();
+        assert_eq!(part_one(&content), 480);
+        assert_eq!(part_two(&content), 875318608908);
     }
 }
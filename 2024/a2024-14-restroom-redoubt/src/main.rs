@@ -1,8 +1,14 @@
+use common::rational::gcd;
+use common::visualize::{Colour, Frame, Output, Recorder};
 use std::collections::HashMap;
+use std::env;
 use std::fs::File;
-use std::io;
 use std::io::Read;
 
+/// How many seconds leading up to the tree `--visualize` shows.
+const RECORDED_SECONDS: i64 = 30;
+
+#[derive(Clone, PartialEq, Eq)]
 struct Robot {
     x: i64,
     y: i64,
@@ -82,7 +88,7 @@ impl From<&str> for Robot {
 fn solve(content: &str, time: i64, size_x: i64, size_y: i64) -> u64 {
     content
         .lines()
-        .map(|line| Robot::from(line))
+        .map(Robot::from)
         .map(|robot| robot.position_after(time, size_x, size_y))
         .fold(HashMap::<Quadrant, u64>::new(), |mut quadrants, robot| {
             let robot_quadrant = robot.get_quadrant(size_x, size_y);
@@ -98,29 +104,27 @@ fn solve(content: &str, time: i64, size_x: i64, size_y: i64) -> u64 {
         .product()
 }
 
-fn part_two(content: &str) {
-    let mut robots = content
-        .lines()
-        .map(|line| Robot::from(line))
-        .collect::<Vec<_>>();
-    let mut buffer = String::new();
-    let mut time = 0;
-    loop {
-        let robot_map = robots.iter().map(|r| ((r.x, r.y), r)).collect();
-        if is_tree(&robot_map) {
-            print_robots(&robot_map, 101, 103);
-            println!("Time {}", time);
-            io::stdin().read_line(&mut buffer);
-        } else if time % 100 == 0 {
-            println!("Time {}", time);
-        }
-        time += 1;
+fn parse(content: &str) -> Vec<Robot> {
+    content.lines().map(Robot::from).collect()
+}
 
-        robots = robots
-            .into_iter()
-            .map(|robot| robot.position_after(1, 101, 103))
-            .collect();
-    }
+fn positions_at(robots: &[Robot], time: i64, size_x: i64, size_y: i64) -> Vec<Robot> {
+    robots
+        .iter()
+        .map(|robot| robot.position_after(time, size_x, size_y))
+        .collect()
+}
+
+/// Every robot is back where it started after a multiple of both sizes, so
+/// only the times before their least common multiple can show the tree.
+fn part_two(content: &str, size_x: i64, size_y: i64) -> Option<i64> {
+    let robots = parse(content);
+    let period = size_x / gcd(size_x.into(), size_y.into()) as i64 * size_y;
+
+    (0..period).find(|time| {
+        let moved = positions_at(&robots, *time, size_x, size_y);
+        is_tree(&moved.iter().map(|r| ((r.x, r.y), r)).collect())
+    })
 }
 
 fn is_tree(robots: &HashMap<(i64, i64), &Robot>) -> bool {
@@ -137,15 +141,25 @@ fn is_tree(robots: &HashMap<(i64, i64), &Robot>) -> bool {
     })
 }
 
-fn print_robots(robots: &HashMap<(i64, i64), &Robot>, size_x: i64, size_y: i64) {
-    for y in 0..size_y {
-        for x in 0..size_x {
-            match robots.get(&(x, y)) {
-                None => print!("."),
-                Some(_) => print!("*"),
-            }
-        }
-        print!("\n");
+fn frame(robots: &[Robot], size_x: i64, size_y: i64) -> Frame {
+    let mut frame = Frame::new(size_x as usize, size_y as usize);
+    frame.fill('.', Colour::BLACK);
+    for robot in robots {
+        frame.set(robot.x as usize, robot.y as usize, '*', Colour::GREEN);
+    }
+
+    frame
+}
+
+/// The robots moving through the last few seconds before `time`.
+fn record(recorder: &mut Recorder, content: &str, time: i64, size_x: i64, size_y: i64) {
+    let robots = parse(content);
+    for time in (time - RECORDED_SECONDS + 1).max(0)..=time {
+        recorder.push(frame(
+            &positions_at(&robots, time, size_x, size_y),
+            size_x,
+            size_y,
+        ));
     }
 }
 
@@ -156,5 +170,17 @@ fn main() {
         .read_to_string(&mut content)
         .unwrap();
     println!("{}", solve(&content, 100, 101, 103));
-    part_two(&content);
+    match part_two(&content, 101, 103) {
+        Some(time) => {
+            let robots = positions_at(&parse(&content), time, 101, 103);
+            print!("{}", frame(&robots, 101, 103));
+            println!("Time {}", time);
+            if let Some(output) = Output::from_args(&env::args().collect::<Vec<_>>()) {
+                let mut recorder = Recorder::new().with_scale(3);
+                record(&mut recorder, &content, time, 101, 103);
+                recorder.save(&output).unwrap();
+            }
+        }
+        None => println!("No tree before the robots repeat"),
+    }
 }
@@ -1,7 +1,11 @@
+use common::visualize::{self, Colour, Frame, Recorder};
+use std::env;
 use std::fs::File;
 use std::io::Read;
+use std::path::PathBuf;
+use std::time::Duration;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum Move {
     Up,
     Right,
@@ -9,300 +13,317 @@ enum Move {
     Left,
 }
 
+impl Move {
+    fn opposite(&self) -> Move {
+        match self {
+            Move::Up => Move::Down,
+            Move::Right => Move::Left,
+            Move::Down => Move::Up,
+            Move::Left => Move::Right,
+        }
+    }
+}
+
 #[derive(Eq, PartialEq, Debug, Clone, Copy)]
 enum Cell {
     Empty,
-    Box,
     Wall,
+    /// Part of the box with the given index in `Warehouse::boxes`.
+    Box(usize),
 }
 
-fn parse_grid(content: &str) -> (Vec<Vec<Cell>>, usize, usize) {
-    let mut robot_x = 0;
-    let mut robot_y = 0;
-    let grid = content
-        .lines()
-        .enumerate()
-        .map(|(y, line)| {
-            line.chars()
-                .enumerate()
-                .map(|(x, ch)| match ch {
-                    '.' => Cell::Empty,
-                    'O' => Cell::Box,
-                    '#' => Cell::Wall,
-                    '@' => {
-                        robot_x = x;
-                        robot_y = y;
-                        Cell::Empty
-                    }
-                    _ => panic!("Unknown character {}", ch),
-                })
-                .collect()
-        })
-        .collect();
+/// A box occupying `width` cells to the right of `(x, y)`.
+#[derive(Debug, Clone, Copy)]
+struct Crate {
+    x: usize,
+    y: usize,
+    width: usize,
+}
 
-    (grid, robot_x, robot_y)
+/// What a single move changed, so it can be undone.
+struct Step {
+    robot: (usize, usize),
+    current_move: Move,
+    pushed: Vec<usize>,
 }
 
-fn parse_moves(content: &str) -> Vec<Move> {
-    content
-        .lines()
-        .flat_map(|line| {
-            line.chars().map(|ch| match ch {
-                '^' => Move::Up,
-                '>' => Move::Right,
-                'v' => Move::Down,
-                '<' => Move::Left,
-                _ => panic!("Uknown move character {}", ch),
-            })
-        })
-        .collect()
+struct Warehouse {
+    grid: Vec<Vec<Cell>>,
+    boxes: Vec<Crate>,
+    robot: (usize, usize),
+    history: Vec<Step>,
 }
 
-fn parse(content: &str) -> (Vec<Vec<Cell>>, usize, usize, Vec<Move>) {
-    let mut splitted = content.split("\n\n");
-    let (grid, robot_x, robot_y) = parse_grid(splitted.next().unwrap());
-    let moves = parse_moves(splitted.next().unwrap());
+impl Warehouse {
+    /// Parses the map with every tile stretched to `scale` cells. Walls become
+    /// `scale` wall cells, boxes become a single box `scale` cells wide and the
+    /// robot stays on the leftmost cell of its tile.
+    fn parse(content: &str, scale: usize) -> Self {
+        let mut boxes = Vec::new();
+        let mut robot = (0, 0);
+        let grid = content
+            .lines()
+            .enumerate()
+            .map(|(y, line)| {
+                line.chars()
+                    .enumerate()
+                    .flat_map(|(x, ch)| {
+                        let cell = match ch {
+                            '.' => Cell::Empty,
+                            'O' => {
+                                boxes.push(Crate {
+                                    x: x * scale,
+                                    y,
+                                    width: scale,
+                                });
+                                Cell::Box(boxes.len() - 1)
+                            }
+                            '#' => Cell::Wall,
+                            '@' => {
+                                robot = (x * scale, y);
+                                Cell::Empty
+                            }
+                            _ => panic!("Unknown character {}", ch),
+                        };
+                        vec![cell; scale]
+                    })
+                    .collect()
+            })
+            .collect();
 
-    (grid, robot_x, robot_y, moves)
-}
+        Self {
+            grid,
+            boxes,
+            robot,
+            history: Vec::new(),
+        }
+    }
 
-fn traverse(mut grid: &mut Vec<Vec<Cell>>, mut x: usize, mut y: usize, moves: &Vec<Move>) {
-    for current_move in moves {
-        let (x_to_swap, y_to_swap) = match current_move {
-            Move::Up => {
-                let mut y_to_swap = y - 1;
-                while y_to_swap >= 1 && grid[y_to_swap][x] == Cell::Box {
-                    y_to_swap -= 1;
+    /// Checks whether whatever is at `coord` can be pushed one cell in
+    /// `current_move`, collecting every box that would move along with it.
+    fn can_move(&self, coord: (usize, usize), current_move: Move, pushed: &mut Vec<usize>) -> bool {
+        let (x, y) = coord;
+        match self.grid[y][x] {
+            Cell::Empty => true,
+            Cell::Wall => false,
+            Cell::Box(id) => {
+                if pushed.contains(&id) {
+                    return true;
                 }
+                pushed.push(id);
 
-                (x, y_to_swap)
-            }
-            Move::Right => {
-                let mut x_to_swap = x + 1;
-                while x_to_swap < grid[0].len() - 1 && grid[y][x_to_swap] == Cell::Box {
-                    x_to_swap += 1;
+                let Crate { x, y, width } = self.boxes[id];
+                match current_move {
+                    Move::Left => {
+                        self.can_move(next_coord((x, y), current_move), current_move, pushed)
+                    }
+                    Move::Right => self.can_move(
+                        next_coord((x + width - 1, y), current_move),
+                        current_move,
+                        pushed,
+                    ),
+                    Move::Up | Move::Down => (x..x + width).all(|x| {
+                        self.can_move(next_coord((x, y), current_move), current_move, pushed)
+                    }),
                 }
-
-                (x_to_swap, y)
             }
-            Move::Down => {
-                let mut y_to_swap = y + 1;
-                while y_to_swap < grid.len() && grid[y_to_swap][x] == Cell::Box {
-                    y_to_swap += 1;
-                }
+        }
+    }
 
-                (x, y_to_swap)
+    /// Shifts the given boxes one cell in `current_move`. All of them are
+    /// lifted before any is put down, so the order doesn't matter.
+    fn move_cells(&mut self, pushed: &[usize], current_move: Move) {
+        for id in pushed {
+            let Crate { x, y, width } = self.boxes[*id];
+            for x in x..x + width {
+                self.grid[y][x] = Cell::Empty;
             }
-            Move::Left => {
-                let mut x_to_swap = x - 1;
-                while x_to_swap >= 1 && grid[y][x_to_swap] == Cell::Box {
-                    x_to_swap -= 1;
-                }
+        }
 
-                (x_to_swap, y)
+        for id in pushed {
+            let moved = &mut self.boxes[*id];
+            (moved.x, moved.y) = next_coord((moved.x, moved.y), current_move);
+            for x in moved.x..moved.x + moved.width {
+                self.grid[moved.y][x] = Cell::Box(*id);
             }
-        };
+        }
+    }
 
-        match grid[y_to_swap][x_to_swap] {
-            Cell::Empty => {
-                match current_move {
-                    Move::Up => y -= 1,
-                    Move::Right => x += 1,
-                    Move::Down => y += 1,
-                    Move::Left => x -= 1,
-                };
+    fn apply(&mut self, current_move: Move) {
+        let next = next_coord(self.robot, current_move);
+        let mut pushed = Vec::new();
+        let moved = self.can_move(next, current_move, &mut pushed);
+        if !moved {
+            pushed.clear();
+        }
 
-                let t = grid[y][x];
-                grid[y][x] = grid[y_to_swap][x_to_swap];
-                grid[y_to_swap][x_to_swap] = t;
-            }
-            Cell::Box => panic!(
-                "Not possible to have box at ({},{}) after trying to move",
-                y_to_swap, x_to_swap
-            ),
-            Cell::Wall => {}
+        self.history.push(Step {
+            robot: self.robot,
+            current_move,
+            pushed: pushed.clone(),
+        });
+
+        if moved {
+            self.move_cells(&pushed, current_move);
+            self.robot = next;
         }
     }
-}
 
-fn grid_to_string(grid: &Vec<Vec<Cell>>, x: usize, y: usize) -> String {
-    let mut s = String::new();
-    for row in grid {
-        for cell in row {
-            match cell {
-                Cell::Empty => s.push('.'),
-                Cell::Box => s.push('O'),
-                Cell::Wall => s.push('#'),
+    /// Reverts the last move. Returns `false` if there is nothing to undo.
+    fn undo(&mut self) -> bool {
+        let Some(step) = self.history.pop() else {
+            return false;
+        };
+
+        self.move_cells(&step.pushed, step.current_move.opposite());
+        self.robot = step.robot;
+
+        true
+    }
+
+    fn gps_sum(&self) -> usize {
+        self.boxes.iter().map(|b| 100 * b.y + b.x).sum()
+    }
+
+    fn grid_to_string(&self) -> String {
+        let mut s = String::new();
+        for (y, row) in self.grid.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let ch = match cell {
+                    _ if (x, y) == self.robot => '@',
+                    Cell::Empty => '.',
+                    Cell::Wall => '#',
+                    Cell::Box(id) => {
+                        let b = self.boxes[*id];
+                        match (b.width, x - b.x) {
+                            (1, _) => 'O',
+                            (_, 0) => '[',
+                            (width, offset) if offset == width - 1 => ']',
+                            _ => '=',
+                        }
+                    }
+                };
+                s.push(ch);
             }
+            s.push('\n');
         }
-        s.push('\n');
-    }
-    let robot_index = y * (grid[0].len() + 1) + x;
-    s.replace_range(robot_index..robot_index + 1, "@");
 
-    s
-}
+        s
+    }
 
-fn part_one(mut grid: &mut Vec<Vec<Cell>>, x: usize, y: usize, moves: &Vec<Move>) -> usize {
-    traverse(grid, x, y, moves);
-
-    grid.iter()
-        .enumerate()
-        .map(|(y, row)| {
-            row.iter()
-                .enumerate()
-                .filter_map(|(x, cell)| match cell {
-                    Cell::Empty => None,
-                    Cell::Box => Some(100 * y + x),
-                    Cell::Wall => None,
-                })
-                .sum::<usize>()
+    fn frame(&self) -> Frame {
+        Frame::from_text(&self.grid_to_string(), |ch| match ch {
+            '@' => Colour::YELLOW,
+            '#' => Colour::GREY,
+            'O' | '[' | ']' | '=' => Colour::ORANGE,
+            _ => Colour::BLACK,
         })
-        .sum()
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Cell2 {
-    Empty,
-    BoxLeft,
-    BoxRight,
-    Wall,
+fn next_coord(coord: (usize, usize), current_move: Move) -> (usize, usize) {
+    let (x, y) = coord;
+    match current_move {
+        Move::Up => (x, y - 1),
+        Move::Right => (x + 1, y),
+        Move::Down => (x, y + 1),
+        Move::Left => (x - 1, y),
+    }
 }
 
-fn double(grid: &Vec<Vec<Cell>>) -> Vec<Vec<Cell2>> {
-    grid.iter()
-        .map(|row| {
-            row.iter()
-                .flat_map(|cell| match cell {
-                    Cell::Empty => vec![Cell2::Empty, Cell2::Empty],
-                    Cell::Box => vec![Cell2::BoxLeft, Cell2::BoxRight],
-                    Cell::Wall => vec![Cell2::Wall, Cell2::Wall],
-                })
-                .collect()
+fn parse_moves(content: &str) -> Vec<Move> {
+    content
+        .lines()
+        .flat_map(|line| {
+            line.chars().map(|ch| match ch {
+                '^' => Move::Up,
+                '>' => Move::Right,
+                'v' => Move::Down,
+                '<' => Move::Left,
+                _ => panic!("Uknown move character {}", ch),
+            })
         })
         .collect()
 }
 
-fn next_coord(x: usize, y: usize, current_move: &Move) -> (usize, usize) {
-    match current_move {
-        Move::Up => (y - 1, x),
-        Move::Right => (y, x + 1),
-        Move::Down => (y + 1, x),
-        Move::Left => (y, x - 1),
-    }
+/// `--animate [N]` replays every `N`-th frame in the terminal, `--dump DIR`
+/// writes them to images in `DIR` instead and `--rewind` also plays the run
+/// backwards by undoing every move. `--visualize [path]` picks any output the
+/// recorder supports, keeping every `N`-th frame with `--every N`. Each run is
+/// recorded on its own.
+struct Animation {
+    every: usize,
+    rewind: bool,
+    output: Option<visualize::Output>,
+    recorder: Recorder,
 }
 
-fn can_move(grid: &Vec<Vec<Cell2>>, coord: (usize, usize), current_move: &Move) -> bool {
-    let (y, x) = coord;
-    match grid[y][x] {
-        Cell2::Empty => true,
-        Cell2::BoxLeft => match current_move {
-            Move::Up | Move::Down => {
-                can_move(grid, next_coord(x, y, current_move), current_move)
-                    && can_move(grid, next_coord(x + 1, y, current_move), current_move)
-            }
-            Move::Right | Move::Left => {
-                can_move(grid, next_coord(x, y, current_move), current_move)
-            }
-        },
+impl Animation {
+    fn from_args() -> Result<Self, String> {
+        let args: Vec<String> = env::args().skip(1).collect();
+        let argument = |name: &str| {
+            args.iter()
+                .position(|arg| arg == name)
+                .map(|position| args.get(position + 1).cloned())
+        };
 
-        Cell2::BoxRight => match current_move {
-            Move::Up | Move::Down => {
-                can_move(grid, next_coord(x, y, current_move), current_move)
-                    && can_move(grid, next_coord(x - 1, y, current_move), current_move)
-            }
-            Move::Right | Move::Left => {
-                can_move(grid, next_coord(x, y, current_move), current_move)
-            }
-        },
-        Cell2::Wall => false,
+        let every = argument("--animate");
+        let output = match argument("--dump") {
+            Some(Some(directory)) => Some(visualize::Output::Ppm(PathBuf::from(directory))),
+            Some(None) => return Err("Usage: --dump <directory>".to_string()),
+            None => visualize::Output::from_args(&args)
+                .or_else(|| every.is_some().then_some(visualize::Output::Terminal)),
+        };
+
+        Ok(Animation {
+            every: every
+                .or_else(|| argument("--every"))
+                .flatten()
+                .and_then(|every| every.parse().ok())
+                .unwrap_or(1),
+            rewind: args.iter().any(|arg| arg == "--rewind"),
+            output,
+            recorder: Recorder::new().with_delay(Duration::from_millis(30)),
+        })
     }
-}
 
-fn move_cells(mut grid: &mut Vec<Vec<Cell2>>, coord: (usize, usize), current_move: &Move) {
-    let (y, x) = coord;
-    match grid[y][x] {
-        Cell2::Empty => {}
-        Cell2::BoxLeft => {
-            let (left_y, left_x) = next_coord(x, y, current_move);
-            move_cells(grid, (left_y, left_x), current_move);
-            grid[left_y][left_x] = Cell2::BoxLeft;
-            grid[y][x] = Cell2::Empty;
-            match current_move {
-                Move::Up | Move::Down => {
-                    let (right_y, right_x) = next_coord(x + 1, y, current_move);
-                    move_cells(grid, (right_y, right_x), current_move);
-                    grid[right_y][right_x] = Cell2::BoxRight;
-                    grid[y][x + 1] = Cell2::Empty;
-                }
-                _ => {}
-            };
+    fn frame(&mut self, warehouse: &Warehouse, index: usize) {
+        if self.output.is_some() && index.is_multiple_of(self.every) {
+            self.recorder.push(warehouse.frame());
         }
-        Cell2::BoxRight => {
-            let (right_y, right_x) = next_coord(x, y, current_move);
-            move_cells(grid, (right_y, right_x), current_move);
-            grid[right_y][right_x] = Cell2::BoxRight;
-            grid[y][x] = Cell2::Empty;
-            match current_move {
-                Move::Up | Move::Down => {
-                    let (left_y, left_x) = next_coord(x - 1, y, current_move);
-                    move_cells(grid, (left_y, left_x), current_move);
-                    grid[left_y][left_x] = Cell2::BoxLeft;
-                    grid[y][x - 1] = Cell2::Empty;
-                }
-                _ => {}
-            };
-        }
-        Cell2::Wall => panic!("Cannot move a wall ({},{})", y, x),
     }
-}
 
-fn traverse2(grid: &mut Vec<Vec<Cell2>>, mut x: usize, mut y: usize, moves: &Vec<Move>) {
-    for current_move in moves {
-        let (next_y, next_x) = next_coord(x, y, current_move);
-        if can_move(grid, (next_y, next_x), current_move) {
-            move_cells(grid, (next_y, next_x), current_move);
-            x = next_x;
-            y = next_y;
+    /// Saves the frames recorded for the run called `name` and starts over.
+    fn finish(&mut self, name: &str) {
+        if let Some(output) = &self.output {
+            self.recorder.save(&output.named(name)).unwrap();
         }
+        self.recorder.clear();
     }
-    println!("{}", grid_to_string2(grid, x, y));
 }
 
-fn part_two(grid: &mut Vec<Vec<Cell2>>, x: usize, y: usize, moves: &Vec<Move>) -> usize {
-    traverse2(grid, 2 * x, y, moves);
-    grid.iter()
-        .enumerate()
-        .map(|(y, row)| {
-            row.iter()
-                .enumerate()
-                .filter_map(|(x, cell)| match cell {
-                    Cell2::BoxLeft => Some(100 * y + x),
-                    _ => None,
-                })
-                .sum::<usize>()
-        })
-        .sum()
-}
+fn run(map: &str, scale: usize, moves: &[Move], animation: &mut Animation) -> usize {
+    let name = format!("scale{}", scale);
+    let mut warehouse = Warehouse::parse(map, scale);
+    animation.frame(&warehouse, 0);
 
-fn grid_to_string2(grid: &Vec<Vec<Cell2>>, x: usize, y: usize) -> String {
-    println!("({y},{x})");
-    let mut s = String::new();
-    for row in grid {
-        for cell in row {
-            match cell {
-                Cell2::Empty => s.push('.'),
-                Cell2::BoxLeft => s.push('['),
-                Cell2::BoxRight => s.push(']'),
-                Cell2::Wall => s.push('#'),
-            }
+    for (i, current_move) in moves.iter().enumerate() {
+        warehouse.apply(*current_move);
+        animation.frame(&warehouse, i + 1);
+    }
+
+    let gps_sum = warehouse.gps_sum();
+    animation.finish(&name);
+    if animation.rewind {
+        let mut index = moves.len();
+        while warehouse.undo() {
+            index -= 1;
+            animation.frame(&warehouse, index);
         }
-        s.push('\n');
+        animation.finish(&format!("{}_rewind", name));
     }
-    let robot_index = y * (grid[0].len() + 1) + x;
-    s.replace_range(robot_index..robot_index + 1, "@");
 
-    s
+    gps_sum
 }
 
 fn main() {
@@ -312,8 +333,72 @@ fn main() {
         .read_to_string(&mut content)
         .unwrap();
 
-    let (mut grid, x, y, moves) = parse(&content);
-    let mut grid2 = double(&grid);
-    println!("{}", part_one(&mut grid, x, y, &moves));
-    println!("{}", part_two(&mut grid2, x, y, &moves));
+    let mut splitted = content.split("\n\n");
+    let map = splitted.next().unwrap();
+    let moves = parse_moves(splitted.next().unwrap());
+    let mut animation = match Animation::from_args() {
+        Ok(animation) => animation,
+        Err(usage) => {
+            println!("{}", usage);
+            return;
+        }
+    };
+
+    println!("{}", run(map, 1, &moves, &mut animation));
+    println!("{}", run(map, 2, &moves, &mut animation));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{parse_moves, Move, Warehouse};
+
+    fn example(path: &str) -> (String, Vec<Move>) {
+        let content = fs::read_to_string(path).unwrap();
+        let (map, moves) = content.split_once("\n\n").unwrap();
+
+        (map.to_string(), parse_moves(moves))
+    }
+
+    fn gps_sum(map: &str, scale: usize, moves: &[Move]) -> usize {
+        let mut warehouse = Warehouse::parse(map, scale);
+        for current_move in moves {
+            warehouse.apply(*current_move);
+        }
+
+        warehouse.gps_sum()
+    }
+
+    #[test]
+    fn test_gps_sum() {
+        let (map, moves) = example("test1");
+        assert_eq!(gps_sum(&map, 1, &moves), 2028);
+
+        let (map, moves) = example("test2");
+        assert_eq!(gps_sum(&map, 1, &moves), 10092);
+        assert_eq!(gps_sum(&map, 2, &moves), 9021);
+    }
+
+    #[test]
+    fn test_undo() {
+        let (map, moves) = example("test2");
+        for scale in [1, 2, 3] {
+            let mut warehouse = Warehouse::parse(&map, scale);
+            let start = warehouse.grid_to_string();
+
+            let mut states = Vec::new();
+            for current_move in &moves {
+                states.push(warehouse.grid_to_string());
+                warehouse.apply(*current_move);
+            }
+
+            while let Some(state) = states.pop() {
+                assert!(warehouse.undo());
+                assert_eq!(warehouse.grid_to_string(), state);
+            }
+            assert!(!warehouse.undo());
+            assert_eq!(warehouse.grid_to_string(), start);
+        }
+    }
 }
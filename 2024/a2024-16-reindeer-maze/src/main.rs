@@ -1,5 +1,7 @@
+use common::visualize::{Colour, Frame, Output, Recorder};
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashSet};
+use std::env;
 use std::fs::File;
 use std::io::Read;
 
@@ -134,13 +136,14 @@ impl Ord for State {
     }
 }
 
-fn dijkstra(
-    grid: &Vec<Vec<char>>,
-    start: &Coordinates,
-) -> (
-    Vec<Vec<Vec<u64>>>,
-    Vec<Vec<Vec<HashSet<(Coordinates, usize)>>>>,
-) {
+/// The lowest score reaching each cell facing each direction.
+type Distances = Vec<Vec<Vec<u64>>>;
+
+/// The cells and directions each cell and direction is reached from on its
+/// best paths.
+type Paths = Vec<Vec<Vec<HashSet<(Coordinates, usize)>>>>;
+
+fn dijkstra(grid: &[Vec<char>], start: &Coordinates) -> (Distances, Paths) {
     let mut min_distances = vec![vec![vec![u64::MAX; 4]; grid[0].len()]; grid.len()];
     let mut paths = vec![vec![vec![HashSet::new(); 4]; grid[0].len()]; grid.len()];
     let mut pq = BinaryHeap::new();
@@ -173,9 +176,8 @@ fn dijkstra(
 }
 
 fn mark_paths(
-    mut grid: &mut Vec<Vec<char>>,
-    paths: &Vec<Vec<Vec<HashSet<(Coordinates, usize)>>>>,
-    min_distances: &Vec<Vec<Vec<u64>>>,
+    grid: &mut [Vec<char>],
+    paths: &Paths,
     coordinates: &Coordinates,
     direction: usize,
     start: &Coordinates,
@@ -187,27 +189,44 @@ fn mark_paths(
 
     for (previous_coord, previous_direction) in &paths[coordinates.row][coordinates.col][direction]
     {
-        mark_paths(
-            grid,
-            paths,
-            min_distances,
-            previous_coord,
-            *previous_direction,
-            start,
-        );
+        mark_paths(grid, paths, previous_coord, *previous_direction, start);
     }
 }
 
-fn print_grid(grid: &Vec<Vec<char>>) {
-    for row in grid {
-        for cell in row {
-            print!("{cell}");
+fn frame(grid: &[Vec<char>]) -> Frame {
+    Frame::from_chars(grid, |cell| match cell {
+        '#' => Colour::GREY,
+        'O' => Colour::YELLOW,
+        '~' => Colour::BLUE,
+        _ => Colour::BLACK,
+    })
+}
+
+/// The search spreading out from the start, one frame for every `step` of
+/// score, ending on the best paths.
+fn record_search(
+    recorder: &mut Recorder,
+    grid: &[Vec<char>],
+    marked: &[Vec<char>],
+    min_distances: &Distances,
+    limit: u64,
+    step: u64,
+) {
+    let mut explored = grid.to_vec();
+    for threshold in (0..=limit).step_by(step as usize) {
+        for (i, row) in min_distances.iter().enumerate() {
+            for (j, distances) in row.iter().enumerate() {
+                if distances.iter().any(|distance| *distance <= threshold) {
+                    explored[i][j] = '~';
+                }
+            }
         }
-        print!("\n");
+        recorder.push(frame(&explored));
     }
+    recorder.push(frame(marked));
 }
 
-fn find_tiles_on_path(grid: &Vec<Vec<char>>) -> usize {
+fn find_tiles_on_path(grid: &[Vec<char>]) -> usize {
     grid.iter()
         .map(|row| row.iter().filter(|cell| **cell == 'O').count())
         .sum()
@@ -220,17 +239,32 @@ fn main() {
         .read_to_string(&mut content)
         .unwrap();
 
-    let (mut grid, start, end) = parse(&content);
+    let (grid, start, end) = parse(&content);
     let (min_distances, paths) = dijkstra(&grid, &start);
     let min_distance = *min_distances[end.row][end.col].iter().min().unwrap();
     println!("{}", min_distance);
+    let mut marked = grid.clone();
     for (direction, _) in min_distances[end.row][end.col]
         .iter()
         .enumerate()
         .filter(|(_, dir)| **dir == min_distance)
     {
-        mark_paths(&mut grid, &paths, &min_distances, &end, direction, &start);
+        mark_paths(&mut marked, &paths, &end, direction, &start);
+    }
+    print!("{}", frame(&marked));
+    println!("{}", find_tiles_on_path(&marked));
+
+    if let Some(output) = Output::from_args(&env::args().collect::<Vec<_>>()) {
+        let mut recorder = Recorder::new();
+        let step = (min_distance / 100).max(1);
+        record_search(
+            &mut recorder,
+            &grid,
+            &marked,
+            &min_distances,
+            min_distance,
+            step,
+        );
+        recorder.save(&output).unwrap();
     }
-    print_grid(&grid);
-    println!("{}", find_tiles_on_path(&grid));
 }
@@ -1,4 +1,6 @@
+use common::visualize::{Colour, Frame, Output, Recorder};
 use std::collections::{HashSet, VecDeque};
+use std::env;
 use std::fs::File;
 use std::io::Read;
 
@@ -21,13 +23,29 @@ enum Cell {
     Corrupted,
 }
 
-fn simulate(grid: &mut Vec<Vec<Cell>>, blocks: &[(usize, usize)]) {
+/// A free grid with a wall of corrupted cells around it, so coordinates are
+/// shifted by one.
+fn fenced_grid(grid_size: usize) -> Vec<Vec<Cell>> {
+    let mut grid = vec![vec![Cell::Free; grid_size + 2]; grid_size + 2];
+    for (i, row) in grid.iter_mut().enumerate() {
+        if i == 0 || i == grid_size + 1 {
+            row.fill(Cell::Corrupted);
+        } else {
+            row[0] = Cell::Corrupted;
+            row[grid_size + 1] = Cell::Corrupted;
+        }
+    }
+
+    grid
+}
+
+fn simulate(grid: &mut [Vec<Cell>], blocks: &[(usize, usize)]) {
     for (x, y) in blocks {
         grid[*y][*x] = Cell::Corrupted
     }
 }
 
-fn bfs(grid: &Vec<Vec<Cell>>) -> Option<(usize, Vec<(usize, usize)>)> {
+fn bfs(grid: &[Vec<Cell>]) -> Option<(usize, Vec<(usize, usize)>)> {
     let mut visited = vec![vec![false; grid[0].len()]; grid.len()];
     let mut q = VecDeque::new();
     q.push_back((0, 1, 1));
@@ -38,7 +56,7 @@ fn bfs(grid: &Vec<Vec<Cell>>) -> Option<(usize, Vec<(usize, usize)>)> {
             return Some((length, calculate_path(&paths, x, y)));
         }
 
-        for (new_x, new_y) in vec![(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+        for (new_x, new_y) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
             if grid[new_y][new_x] == Cell::Free && !visited[new_y][new_x] {
                 visited[new_y][new_x] = true;
                 q.push_back((length + 1, new_x, new_y));
@@ -51,7 +69,7 @@ fn bfs(grid: &Vec<Vec<Cell>>) -> Option<(usize, Vec<(usize, usize)>)> {
 }
 
 fn calculate_path(
-    paths: &Vec<Vec<Option<(usize, usize)>>>,
+    paths: &[Vec<Option<(usize, usize)>>],
     x: usize,
     y: usize,
 ) -> Vec<(usize, usize)> {
@@ -66,32 +84,40 @@ fn calculate_path(
     path
 }
 
-fn print_grid(grid: &Vec<Vec<Cell>>) {
-    for row in grid {
-        for cell in row {
-            match cell {
-                Cell::Free => print!("."),
-                Cell::Corrupted => print!("#"),
-            }
+/// The grid with `path` drawn over it and `fallen`, the byte that just
+/// landed, highlighted.
+fn frame(
+    grid: &[Vec<Cell>],
+    path: &HashSet<(usize, usize)>,
+    fallen: Option<(usize, usize)>,
+) -> Frame {
+    let mut frame = Frame::new(grid[0].len(), grid.len());
+    for (y, row) in grid.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let (symbol, colour) = if fallen == Some((x, y)) {
+                ('#', Colour::RED)
+            } else if *cell == Cell::Corrupted {
+                ('#', Colour::GREY)
+            } else if path.contains(&(x, y)) {
+                ('O', Colour::YELLOW)
+            } else {
+                ('.', Colour::BLACK)
+            };
+            frame.set(x, y, symbol, colour);
         }
-        print!("\n");
     }
+
+    frame
 }
 
-fn part_one(blocks: &Vec<(usize, usize)>, grid_size: usize, blocks_to_simulate: usize) -> usize {
-    let mut grid = vec![vec![Cell::Free; grid_size + 2]; grid_size + 2];
-    for i in 0..grid_size + 2 {
-        grid[0][i] = Cell::Corrupted;
-        grid[grid_size + 1][i] = Cell::Corrupted;
-        grid[i][0] = Cell::Corrupted;
-        grid[i][grid_size + 1] = Cell::Corrupted;
-    }
+fn part_one(blocks: &[(usize, usize)], grid_size: usize, blocks_to_simulate: usize) -> usize {
+    let mut grid = fenced_grid(grid_size);
     let new_blocks = blocks
         .iter()
         .map(|(x, y)| (x + 1, y + 1))
         .collect::<Vec<(usize, usize)>>();
     simulate(&mut grid, &new_blocks[..blocks_to_simulate]);
-    print_grid(&grid);
+    print!("{}", frame(&grid, &HashSet::new(), None));
 
     let (length, path) = bfs(&grid).unwrap();
     println!("{:?}", path);
@@ -99,29 +125,33 @@ fn part_one(blocks: &Vec<(usize, usize)>, grid_size: usize, blocks_to_simulate:
     length
 }
 
+/// Drops the remaining bytes until one cuts the exit off, rerouting only
+/// when a byte lands on the current path. Every reroute, and the byte that
+/// blocks the way for good, is recorded as a frame.
 fn part_two(
-    blocks: &Vec<(usize, usize)>,
+    blocks: &[(usize, usize)],
     grid_size: usize,
     blocks_to_simulate: usize,
+    mut recorder: Option<&mut Recorder>,
 ) -> (usize, usize) {
-    let mut grid = vec![vec![Cell::Free; grid_size + 2]; grid_size + 2];
-    for i in 0..grid_size + 2 {
-        grid[0][i] = Cell::Corrupted;
-        grid[grid_size + 1][i] = Cell::Corrupted;
-        grid[i][0] = Cell::Corrupted;
-        grid[i][grid_size + 1] = Cell::Corrupted;
-    }
+    let mut grid = fenced_grid(grid_size);
     let new_blocks = blocks
         .iter()
         .map(|(x, y)| (x + 1, y + 1))
         .collect::<Vec<(usize, usize)>>();
     simulate(&mut grid, &new_blocks[..blocks_to_simulate]);
-    print_grid(&grid);
+    print!("{}", frame(&grid, &HashSet::new(), None));
     let (_, path) = bfs(&grid).unwrap();
     let mut path_set = path.into_iter().collect::<HashSet<_>>();
+    if let Some(recorder) = recorder.as_deref_mut() {
+        recorder.push(frame(&grid, &path_set, None));
+    }
     for (x, y) in &new_blocks[blocks_to_simulate..] {
         grid[*y][*x] = Cell::Corrupted;
         if path_set.contains(&(*x, *y)) {
+            if let Some(recorder) = recorder.as_deref_mut() {
+                recorder.push(frame(&grid, &path_set, Some((*x, *y))));
+            }
             match bfs(&grid) {
                 None => return (*x - 1, *y - 1), // Convert back to non fenced coordinates
                 Some((_, path)) => {
@@ -143,5 +173,13 @@ fn main() {
     let blocks = parse(&content);
 
     println!("{}", part_one(&blocks, 71, 1024));
-    println!("{:?}", part_two(&blocks, 71, 1024));
+    let output = Output::from_args(&env::args().collect::<Vec<_>>());
+    let mut recorder = Recorder::new();
+    println!(
+        "{:?}",
+        part_two(&blocks, 71, 1024, output.as_ref().map(|_| &mut recorder))
+    );
+    if let Some(output) = output {
+        recorder.save(&output).unwrap();
+    }
 }
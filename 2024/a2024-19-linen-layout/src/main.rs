@@ -1,7 +1,9 @@
-use std::collections::{HashMap, VecDeque};
+use num_bigint::BigUint;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
 
+/// A trie of towel patterns. `end` marks nodes where a whole pattern ends.
 #[derive(Debug)]
 struct Patterns {
     end: bool,
@@ -9,68 +11,156 @@ struct Patterns {
 }
 
 impl Patterns {
-    fn possible_ways(&self, design: &Vec<char>, patterns: &Patterns) -> usize {
-        // Contain the number of possible ways to get the design from the ith character
-        let mut possible_from = vec![0; design.len()];
-        for (i, _) in design.iter().enumerate().rev() {
-            let mut q = VecDeque::new();
-            q.push_back((patterns, i));
-            while let Some((current_patterns, current)) = q.pop_front() {
-                // If the index of the current character is out of bound,
-                // Then see if the current trie is an ending of some pattern
-                // If it is, add 1 to the possible_form[i], since the ending cannot be combined
-                // with other patterns.
-                if current == design.len() {
-                    if current_patterns.end {
-                        possible_from[i] += 1;
-                    }
-                    continue;
-                }
-
-                // If the current tree is an ending of some pattern, then we can split the
-                // design at the current character and make it with however many ways we can
-                // make the design from the current character
-                if current_patterns.end {
-                    possible_from[i] += possible_from[current];
-                }
-
-                // Now, if we can find patterns which can be continued by the current character
-                // We should also perform the same calculation on this continuation.
-                if let Some(pattern) = current_patterns.map.get(&design[current]) {
-                    q.push_back((pattern, current + 1));
-                }
+    fn new() -> Self {
+        Self {
+            end: false,
+            map: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, pattern: &str) {
+        let mut current_pattern = self;
+        for ch in pattern.chars() {
+            current_pattern = current_pattern.map.entry(ch).or_insert_with(Self::new);
+        }
+        current_pattern.end = true;
+    }
+
+    /// Every pattern in the trie, in no particular order.
+    fn patterns(&self) -> Vec<String> {
+        let mut patterns = Vec::new();
+        let mut stack = vec![(self, String::new())];
+        while let Some((node, prefix)) = stack.pop() {
+            if node.end {
+                patterns.push(prefix.clone());
+            }
+            for (ch, child) in &node.map {
+                stack.push((child, format!("{}{}", prefix, ch)));
             }
         }
 
-        possible_from[0]
+        patterns
+    }
+
+    /// The lengths of the patterns that `design[start..]` starts with. Walking
+    /// the trie stops as soon as no pattern continues, so this never looks
+    /// further than the longest pattern.
+    fn matches(&self, design: &[char], start: usize) -> Vec<usize> {
+        let mut lengths = Vec::new();
+        let mut current_pattern = self;
+        for (i, ch) in design[start..].iter().enumerate() {
+            match current_pattern.map.get(ch) {
+                Some(next) => current_pattern = next,
+                None => break,
+            }
+            if current_pattern.end {
+                lengths.push(i + 1);
+            }
+        }
+
+        lengths
+    }
+
+    /// `ways[i]` is the number of ways to make `design[i..]`, filled in from
+    /// the back.
+    fn ways_from(&self, design: &[char]) -> Vec<BigUint> {
+        let mut ways = vec![BigUint::default(); design.len() + 1];
+        ways[design.len()] = BigUint::from(1u32);
+        for i in (0..design.len()).rev() {
+            for length in self.matches(design, i) {
+                let rest = ways[i + length].clone();
+                ways[i] += rest;
+            }
+        }
+
+        ways
+    }
+
+    fn possible_ways(&self, design: &[char]) -> BigUint {
+        self.ways_from(design).swap_remove(0)
+    }
+
+    /// Any one way to make the design, or `None` if there is none.
+    fn decomposition(&self, design: &[char]) -> Option<Vec<String>> {
+        let ways = self.ways_from(design);
+        let mut decomposition = Vec::new();
+        let mut i = 0;
+        while i < design.len() {
+            let length = self
+                .matches(design, i)
+                .into_iter()
+                .find(|length| ways[i + length] != BigUint::default())?;
+            decomposition.push(design[i..i + length].iter().collect());
+            i += length;
+        }
+
+        Some(decomposition)
+    }
+
+    /// Every way to make the design. Only use this when `possible_ways` is
+    /// small enough to list them all.
+    fn decompositions(&self, design: &[char]) -> Vec<Vec<String>> {
+        let ways = self.ways_from(design);
+        let mut decompositions = Vec::new();
+        self.collect(design, &ways, 0, &mut Vec::new(), &mut decompositions);
+
+        decompositions
+    }
+
+    fn collect(
+        &self,
+        design: &[char],
+        ways: &[BigUint],
+        i: usize,
+        current: &mut Vec<String>,
+        decompositions: &mut Vec<Vec<String>>,
+    ) {
+        if i == design.len() {
+            decompositions.push(current.clone());
+            return;
+        }
+
+        for length in self.matches(design, i) {
+            if ways[i + length] == BigUint::default() {
+                continue;
+            }
+            current.push(design[i..i + length].iter().collect());
+            self.collect(design, ways, i + length, current, decompositions);
+            current.pop();
+        }
+    }
+
+    /// Patterns that can also be made out of other, shorter patterns. Every
+    /// pattern makes itself in one way, so these have more than one way.
+    fn composable(&self) -> Vec<String> {
+        let mut composable: Vec<String> = self
+            .patterns()
+            .into_iter()
+            .filter(|pattern| {
+                let pattern: Vec<char> = pattern.chars().collect();
+                self.possible_ways(&pattern) > BigUint::from(1u32)
+            })
+            .collect();
+        composable.sort();
+
+        composable
     }
 }
 
 impl From<&str> for Patterns {
     fn from(value: &str) -> Self {
-        let mut patterns = Self {
-            end: false,
-            map: HashMap::new(),
-        };
-
+        let mut patterns = Self::new();
         for pattern in value.split(", ") {
-            let mut current_pattern = &mut patterns;
-            for ch in pattern.chars() {
-                current_pattern = current_pattern.map.entry(ch).or_insert(Self {
-                    end: false,
-                    map: HashMap::new(),
-                });
-            }
-            current_pattern.end = true;
+            patterns.insert(pattern);
         }
 
         patterns
     }
 }
 
-fn parse(content: &str) -> (Patterns, Vec<String>) {
+fn parse(content: &str) -> (Patterns, Vec<Vec<char>>) {
     let mut splitted = content.split("\n\n");
-    let patterns = Patterns::from(splitted.next().unwrap());
+    let patterns = Patterns::from(splitted.next().unwrap().trim());
 
     (
         patterns,
@@ -78,27 +168,23 @@ fn parse(content: &str) -> (Patterns, Vec<String>) {
             .next()
             .unwrap()
             .lines()
-            .map(|line| line.to_string())
+            .map(|line| line.chars().collect())
             .collect(),
     )
 }
 
-fn part_one(patterns: &Patterns, designs: &Vec<String>) -> usize {
-    let count = designs
+fn part_one(patterns: &Patterns, designs: &[Vec<char>]) -> usize {
+    designs
         .iter()
-        .filter(|design| patterns.possible_ways(&design.chars().collect(), patterns) > 0)
-        .count();
-
-    count
+        .filter(|design| patterns.decomposition(design).is_some())
+        .count()
 }
 
-fn part_two(patterns: &Patterns, designs: &Vec<String>) -> usize {
-    let sum = designs
+fn part_two(patterns: &Patterns, designs: &[Vec<char>]) -> BigUint {
+    designs
         .iter()
-        .map(|design| patterns.possible_ways(&design.chars().collect(), patterns))
-        .sum();
-
-    sum
+        .map(|design| patterns.possible_ways(design))
+        .sum()
 }
 
 fn main() {
@@ -111,4 +197,91 @@ fn main() {
     let (patterns, designs) = parse(&content);
     println!("{}", part_one(&patterns, &designs));
     println!("{}", part_two(&patterns, &designs));
+
+    let composable = patterns.composable();
+    println!(
+        "{} of {} patterns are made of other patterns",
+        composable.len(),
+        patterns.patterns().len()
+    );
+
+    for design in designs.iter().take(3) {
+        let design_string: String = design.iter().collect();
+        let ways = patterns.possible_ways(design);
+        if ways > BigUint::from(10u32) {
+            println!(
+                "{}: {} and {} more",
+                design_string,
+                patterns.decomposition(design).unwrap().join(" "),
+                ways - 1u32
+            );
+        } else if ways == BigUint::default() {
+            println!("{}: impossible", design_string);
+        } else {
+            for way in patterns.decompositions(design) {
+                println!("{}: {}", design_string, way.join(" "));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+    use std::fs;
+
+    use crate::{parse, part_one, part_two, Patterns};
+
+    fn example() -> (Patterns, Vec<Vec<char>>) {
+        parse(&fs::read_to_string("test").unwrap())
+    }
+
+    fn design(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    #[test]
+    fn test_example() {
+        let (patterns, designs) = example();
+
+        assert_eq!(part_one(&patterns, &designs), 6);
+        assert_eq!(part_two(&patterns, &designs), BigUint::from(16u32));
+    }
+
+    #[test]
+    fn test_possible_ways() {
+        let (patterns, designs) = example();
+        let ways: Vec<BigUint> = designs
+            .iter()
+            .map(|design| patterns.possible_ways(design))
+            .collect();
+
+        assert_eq!(ways, [2u32, 1, 4, 6, 0, 1, 2, 0].map(BigUint::from));
+    }
+
+    #[test]
+    fn test_decompositions() {
+        let (patterns, _) = example();
+
+        let mut ways = patterns.decompositions(&design("brwrr"));
+        ways.sort();
+        assert_eq!(ways, vec![vec!["b", "r", "wr", "r"], vec!["br", "wr", "r"]]);
+        assert_eq!(
+            patterns.decomposition(&design("bggr")),
+            Some(vec![
+                "b".to_string(),
+                "g".to_string(),
+                "g".to_string(),
+                "r".to_string()
+            ])
+        );
+        assert_eq!(patterns.decomposition(&design("ubwu")), None);
+    }
+
+    #[test]
+    fn test_composable() {
+        let (patterns, _) = example();
+
+        assert_eq!(patterns.composable(), vec!["br", "gb", "rb"]);
+    }
 }
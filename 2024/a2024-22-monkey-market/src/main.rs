@@ -1,10 +1,13 @@
-use std::cmp::max;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
 use std::time::SystemTime;
 
+/// Changes are in `-9..=9`, so a window of four of them is a four digit base
+/// 19 number.
+const WINDOWS: usize = 19 * 19 * 19 * 19;
+
 fn parse(content: &str) -> Vec<u64> {
     content.lines().map(|line| line.parse().unwrap()).collect()
 }
@@ -16,7 +19,19 @@ fn next(number: u64) -> u64 {
     next
 }
 
-fn part_one(seeds: &Vec<u64>) -> u64 {
+/// `next` for many secret numbers at once. Secrets fit in 24 bits and the loop
+/// body is branch free, so the compiler runs it on several lanes at a time.
+fn next_batch(numbers: &mut [u32]) {
+    const MASK: u32 = 16777216 - 1;
+    for number in numbers.iter_mut() {
+        let mut next = ((*number << 6) ^ *number) & MASK;
+        next = ((next >> 5) ^ next) & MASK;
+        next = ((next << 11) ^ next) & MASK;
+        *number = next;
+    }
+}
+
+fn part_one(seeds: &[u64]) -> u64 {
     seeds
         .iter()
         .map(|seed| {
@@ -29,17 +44,29 @@ fn part_one(seeds: &Vec<u64>) -> u64 {
         .sum()
 }
 
-fn generate_prices(seed: u64, count: usize) -> Vec<u64> {
-    let mut number = seed;
-    let mut prices = vec![seed % 10];
+/// The seed's price followed by the next `count` prices, for every seed.
+fn generate_prices(seeds: &[u64], count: usize) -> Vec<Vec<u64>> {
+    let mut numbers: Vec<u32> = seeds.iter().map(|seed| *seed as u32).collect();
+    let mut prices: Vec<Vec<u64>> = seeds
+        .iter()
+        .map(|seed| {
+            let mut prices = Vec::with_capacity(count + 1);
+            prices.push(seed % 10);
+            prices
+        })
+        .collect();
+
     for _ in 0..count {
-        number = next(number);
-        prices.push(number % 10);
+        next_batch(&mut numbers);
+        for (prices, number) in prices.iter_mut().zip(&numbers) {
+            prices.push(*number as u64 % 10);
+        }
     }
+
     prices
 }
 
-fn get_changes(prices: &Vec<u64>) -> Vec<i64> {
+fn get_changes(prices: &[u64]) -> Vec<i64> {
     prices[0..prices.len() - 1]
         .iter()
         .zip(prices[1..prices.len()].iter())
@@ -47,16 +74,15 @@ fn get_changes(prices: &Vec<u64>) -> Vec<i64> {
         .collect()
 }
 
+/// The price index at which each window of four changes first ends.
 fn get_change_indexes(changes: &[i64]) -> HashMap<[i64; 4], usize> {
     let mut sequence = [changes[0], changes[1], changes[2], changes[3]];
     let mut sequences = HashMap::new();
-    sequences.insert(sequence.clone(), 4);
-    for i in 4..changes.len() {
-        sequence[0] = sequence[1];
-        sequence[1] = sequence[2];
-        sequence[2] = sequence[3];
-        sequence[3] = changes[i];
-        if let Entry::Vacant(entry) = sequences.entry(sequence.clone()) {
+    sequences.insert(sequence, 4);
+    for (i, change) in changes.iter().enumerate().skip(4) {
+        sequence.rotate_left(1);
+        sequence[3] = *change;
+        if let Entry::Vacant(entry) = sequences.entry(sequence) {
             entry.insert(i + 1);
         }
     }
@@ -64,46 +90,76 @@ fn get_change_indexes(changes: &[i64]) -> HashMap<[i64; 4], usize> {
     sequences
 }
 
-fn part_two(seeds: &Vec<u64>) -> u64 {
-    let prices_per_seed = seeds
-        .iter()
-        .map(|seed| generate_prices(*seed, 2000))
-        .collect::<Vec<_>>();
+fn window_sequence(mut index: usize) -> [i64; 4] {
+    let mut sequence = [0; 4];
+    for change in sequence.iter_mut().rev() {
+        *change = (index % 19) as i64 - 9;
+        index /= 19;
+    }
 
-    let changes_per_seed = prices_per_seed
-        .iter()
-        .map(|prices| get_changes(prices))
-        .collect::<Vec<Vec<i64>>>();
-    let change_indexes_per_seed = changes_per_seed
-        .iter()
-        .map(|changes| get_change_indexes(changes))
-        .collect::<Vec<_>>();
-    let mut needle = [0; 4];
-    let mut max_bananas = 0;
-    for c1 in -9..10 {
-        println!("{c1}");
-        needle[0] = c1;
-        for c2 in -9..10 {
-            needle[1] = c2;
-            for c3 in -9..10 {
-                needle[2] = c3;
-                for c4 in -9..10 {
-                    needle[3] = c4;
-                    let total_bananas = changes_per_seed
-                        .iter()
-                        .enumerate()
-                        .filter_map(|(i, changes)| {
-                            change_indexes_per_seed[i]
-                                .get(&needle)
-                                .map(|index| prices_per_seed[i][*index])
-                        })
-                        .sum();
-                    max_bananas = max(total_bananas, max_bananas)
+    sequence
+}
+
+/// The prices of every buyer together with how many bananas each sequence of
+/// four changes would get when selling to all of them.
+struct Market {
+    prices: Vec<Vec<u64>>,
+    bananas: Vec<u64>,
+}
+
+impl Market {
+    /// Scans every buyer once, rolling the last four changes into a window
+    /// index. `seen` remembers which buyer last sold on each window, so only
+    /// the first occurrence counts and it never needs clearing.
+    fn new(seeds: &[u64], count: usize) -> Self {
+        let prices = generate_prices(seeds, count);
+        let mut bananas = vec![0; WINDOWS];
+        let mut seen = vec![0u32; WINDOWS];
+
+        for (buyer, prices) in prices.iter().enumerate() {
+            let stamp = buyer as u32 + 1;
+            let mut index = 0;
+            for i in 1..prices.len() {
+                let change = (prices[i] + 9 - prices[i - 1]) as usize;
+                index = (index * 19 + change) % WINDOWS;
+                if i >= 4 && seen[index] != stamp {
+                    seen[index] = stamp;
+                    bananas[index] += prices[i];
                 }
             }
         }
+
+        Self { prices, bananas }
+    }
+
+    fn best(&self) -> ([i64; 4], u64) {
+        self.top(1)[0]
+    }
+
+    /// The `k` sequences selling for the most bananas, best first.
+    fn top(&self, k: usize) -> Vec<([i64; 4], u64)> {
+        let mut windows: Vec<usize> = (0..WINDOWS).collect();
+        windows.sort_by_key(|index| std::cmp::Reverse(self.bananas[*index]));
+
+        windows
+            .into_iter()
+            .take(k)
+            .map(|index| (window_sequence(index), self.bananas[index]))
+            .collect()
+    }
+
+    /// How many bananas each buyer pays for `sequence`, zero if it never comes
+    /// up for them.
+    fn contributions(&self, sequence: [i64; 4]) -> Vec<u64> {
+        self.prices
+            .iter()
+            .map(|prices| {
+                get_change_indexes(&get_changes(prices))
+                    .get(&sequence)
+                    .map_or(0, |index| prices[*index])
+            })
+            .collect()
     }
-    max_bananas
 }
 
 fn main() {
@@ -115,14 +171,31 @@ fn main() {
     let seeds = parse(&content);
     println!("{}", part_one(&seeds));
     let before = SystemTime::now();
-    let result = part_two(&seeds);
+    let market = Market::new(&seeds, 2000);
+    let (sequence, bananas) = market.best();
     let time = SystemTime::now().duration_since(before).unwrap();
-    println!("{}, {}s", result, time.as_secs_f32());
+    println!("{}, {:?}, {}s", bananas, sequence, time.as_secs_f32());
+
+    let contributions = market.contributions(sequence);
+    println!(
+        "{} of {} buyers sell, {} bananas at most from one",
+        contributions.iter().filter(|bananas| **bananas > 0).count(),
+        contributions.len(),
+        contributions.iter().max().unwrap_or(&0)
+    );
+
+    for (sequence, bananas) in market.top(5).iter().skip(1) {
+        println!("{:?} {}", sequence, bananas);
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{generate_prices, get_change_indexes, get_changes, next};
+    use std::fs;
+
+    use crate::{
+        generate_prices, get_change_indexes, get_changes, next, next_batch, parse, part_one, Market,
+    };
 
     #[test]
     fn test_next() {
@@ -137,10 +210,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_next_batch() {
+        let mut numbers: Vec<u32> = (0..100).map(|i| i * 167_773).collect();
+        let expected: Vec<u32> = numbers
+            .iter()
+            .map(|number| next(*number as u64) as u32)
+            .collect();
+
+        next_batch(&mut numbers);
+        assert_eq!(numbers, expected);
+    }
+
+    #[test]
+    fn test_part_one() {
+        let seeds = parse(&fs::read_to_string("test").unwrap());
+        assert_eq!(part_one(&seeds), 37327623);
+    }
+
     #[test]
     fn test_changes() {
         let number = 123;
-        let prices = generate_prices(number, 9);
+        let prices = generate_prices(&[number], 9).remove(0);
         let changes = get_changes(&prices);
         assert_eq!(changes, vec![-3, 6, -1, -1, 0, 2, -2, 0, -2])
     }
@@ -148,11 +239,27 @@ mod tests {
     #[test]
     fn test_get_change_index() {
         let number = 123;
-        let prices = generate_prices(number, 9);
+        let prices = generate_prices(&[number], 9).remove(0);
         let changes = get_changes(&prices);
         let change_indexes = get_change_indexes(&changes);
         assert_eq!(change_indexes.get(&[-1, -1, 0, 2]), Some(&6));
         assert_eq!(change_indexes.get(&[9, -1, 0, 2]), None);
         assert_eq!(change_indexes.get(&[2, -2, 0, -2]), Some(&9));
     }
+
+    #[test]
+    fn test_market() {
+        let seeds = parse(&fs::read_to_string("test2").unwrap());
+        let market = Market::new(&seeds, 2000);
+
+        assert_eq!(market.best(), ([-2, 1, -1, 3], 23));
+        assert_eq!(market.contributions([-2, 1, -1, 3]), vec![7, 7, 0, 9]);
+
+        let top = market.top(10);
+        assert_eq!(top[0], market.best());
+        assert!(top.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        for (sequence, bananas) in top {
+            assert_eq!(market.contributions(sequence).iter().sum::<u64>(), bananas);
+        }
+    }
 }
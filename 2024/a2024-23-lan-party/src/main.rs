@@ -1,6 +1,10 @@
-use std::collections::{HashMap, HashSet};
-use std::fs::File;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::env;
+use std::fs::{self, File};
 use std::io::Read;
+use std::time::SystemTime;
 
 fn parse(content: &str) -> Vec<(&str, &str)> {
     content
@@ -12,75 +16,399 @@ fn parse(content: &str) -> Vec<(&str, &str)> {
         .collect()
 }
 
-fn make_graph<'a>(edges: &'a [(&str, &str)]) -> HashMap<&'a str, HashSet<&'a str>> {
-    let mut graph: HashMap<&str, HashSet<&str>> = HashMap::new();
-    for (from, to) in edges {
-        graph.entry(from).or_default().insert(to);
-        graph.entry(to).or_default().insert(from);
+/// The computers numbered in the order they first show up. `order` is a
+/// degeneracy ordering and `later` keeps only the neighbours that come after
+/// each computer in it, so every clique is found exactly once from its first
+/// computer and no `later` list is longer than the degeneracy of the graph.
+struct Network<'a> {
+    names: Vec<&'a str>,
+    neighbours: Vec<HashSet<usize>>,
+    order: Vec<usize>,
+    later: Vec<Vec<usize>>,
+}
+
+impl<'a> Network<'a> {
+    fn new(edges: &[(&'a str, &'a str)]) -> Self {
+        let mut ids: HashMap<&str, usize> = HashMap::new();
+        let mut names = Vec::new();
+        let mut neighbours: Vec<HashSet<usize>> = Vec::new();
+        let mut id = |name: &'a str, neighbours: &mut Vec<HashSet<usize>>| {
+            *ids.entry(name).or_insert_with(|| {
+                names.push(name);
+                neighbours.push(HashSet::new());
+                names.len() - 1
+            })
+        };
+
+        for (from, to) in edges {
+            let from = id(from, &mut neighbours);
+            let to = id(to, &mut neighbours);
+            neighbours[from].insert(to);
+            neighbours[to].insert(from);
+        }
+
+        let order = degeneracy_order(&neighbours);
+        let mut rank = vec![0; order.len()];
+        for (i, node) in order.iter().enumerate() {
+            rank[*node] = i;
+        }
+        let later = neighbours
+            .iter()
+            .enumerate()
+            .map(|(node, neighbours)| {
+                let mut later: Vec<usize> = neighbours
+                    .iter()
+                    .filter(|other| rank[**other] > rank[node])
+                    .copied()
+                    .collect();
+                later.sort_by_key(|other| rank[*other]);
+                later
+            })
+            .collect();
+
+        Self {
+            names,
+            neighbours,
+            order,
+            later,
+        }
     }
 
-    graph
-}
+    fn triangles(&self) -> Vec<[usize; 3]> {
+        let mut triangles = Vec::new();
+        for a in 0..self.names.len() {
+            for b in &self.later[a] {
+                for c in &self.later[*b] {
+                    if self.neighbours[a].contains(c) {
+                        triangles.push([a, *b, *c]);
+                    }
+                }
+            }
+        }
+
+        triangles
+    }
+
+    /// The number of sets of `k` computers that are all connected to each
+    /// other.
+    fn count_cliques(&self, k: usize) -> u64 {
+        if k == 0 {
+            return 1;
+        }
+
+        (0..self.names.len())
+            .map(|node| self.count_extensions(&self.later[node], k - 1))
+            .sum()
+    }
+
+    /// Counts the ways to pick `k` more computers out of `candidates`, which
+    /// are all connected to the clique picked so far.
+    fn count_extensions(&self, candidates: &[usize], k: usize) -> u64 {
+        if k == 0 {
+            return 1;
+        }
+        if candidates.len() < k {
+            return 0;
+        }
+
+        candidates
+            .iter()
+            .map(|node| {
+                let next: Vec<usize> = self.later[*node]
+                    .iter()
+                    .filter(|other| candidates.contains(other))
+                    .copied()
+                    .collect();
+                self.count_extensions(&next, k - 1)
+            })
+            .sum()
+    }
 
-fn find_triplets<'a>(
-    graph: &'a HashMap<&str, HashSet<&str>>,
-    edges: &'a [(&str, &str)],
-) -> Vec<(&'a str, &'a str, &'a str)> {
-    let mut result = vec![];
-    for (from, to) in edges {
-        for third in graph[*from].intersection(&graph[*to]) {
-            result.push((*from, *to, *third));
+    /// Every maximal clique, by Bron–Kerbosch with pivoting. The outer level
+    /// goes through the degeneracy ordering so each call starts with at most
+    /// degeneracy many candidates.
+    fn maximal_cliques(&self) -> Vec<Vec<usize>> {
+        let mut cliques = Vec::new();
+        for (i, node) in self.order.iter().enumerate() {
+            let candidates = self.later[*node].clone();
+            let excluded = self.order[..i]
+                .iter()
+                .filter(|other| self.neighbours[*node].contains(other))
+                .copied()
+                .collect();
+            self.bron_kerbosch(&mut vec![*node], candidates, excluded, &mut cliques);
         }
+
+        cliques
     }
 
-    result
+    fn bron_kerbosch(
+        &self,
+        clique: &mut Vec<usize>,
+        mut candidates: Vec<usize>,
+        mut excluded: Vec<usize>,
+        cliques: &mut Vec<Vec<usize>>,
+    ) {
+        if candidates.is_empty() {
+            if excluded.is_empty() {
+                cliques.push(clique.clone());
+            }
+            return;
+        }
+
+        // Any maximal clique has to contain the pivot or one of its
+        // non-neighbours, so only those need to be tried.
+        let pivot = *candidates
+            .iter()
+            .chain(&excluded)
+            .max_by_key(|node| {
+                candidates
+                    .iter()
+                    .filter(|other| self.neighbours[**node].contains(other))
+                    .count()
+            })
+            .unwrap();
+        let branches: Vec<usize> = candidates
+            .iter()
+            .filter(|node| !self.neighbours[pivot].contains(node))
+            .copied()
+            .collect();
+
+        for node in branches {
+            let neighbours = &self.neighbours[node];
+            clique.push(node);
+            self.bron_kerbosch(
+                clique,
+                candidates
+                    .iter()
+                    .filter(|other| neighbours.contains(other))
+                    .copied()
+                    .collect(),
+                excluded
+                    .iter()
+                    .filter(|other| neighbours.contains(other))
+                    .copied()
+                    .collect(),
+                cliques,
+            );
+            clique.pop();
+
+            candidates.retain(|other| *other != node);
+            excluded.push(node);
+        }
+    }
+
+    fn maximum_clique(&self) -> Vec<usize> {
+        self.maximal_cliques()
+            .into_iter()
+            .max_by_key(|clique| clique.len())
+            .unwrap_or_default()
+    }
+
+    /// The names in the clique, sorted and joined by commas.
+    fn password(&self, clique: &[usize]) -> String {
+        let mut names: Vec<&str> = clique.iter().map(|node| self.names[*node]).collect();
+        names.sort();
+        names.join(",")
+    }
+
+    /// Renders the network in DOT with the computers and connections of
+    /// `highlight` filled in red.
+    fn to_dot(&self, highlight: &[usize]) -> String {
+        let mut result = String::from("graph lan {\n");
+        for (node, name) in self.names.iter().enumerate() {
+            if highlight.contains(&node) {
+                result.push_str(&format!(
+                    "    \"{}\" [style=filled, fillcolor=red];\n",
+                    name
+                ));
+            }
+        }
+
+        for (node, later) in self.later.iter().enumerate() {
+            for other in later {
+                let style = if highlight.contains(&node) && highlight.contains(other) {
+                    " [color=red, penwidth=2]"
+                } else {
+                    ""
+                };
+                result.push_str(&format!(
+                    "    \"{}\" -- \"{}\"{};\n",
+                    self.names[node], self.names[*other], style
+                ));
+            }
+        }
+
+        result.push_str("}\n");
+
+        result
+    }
 }
 
-fn find_maximum_clique<'a>(
-    graph: &'a HashMap<&str, HashSet<&str>>,
-    edges: &'a [(&str, &str)],
-) -> Vec<&'a str> {
-    let mut cliques = edges
+/// Orders the computers by repeatedly taking out one with the fewest
+/// remaining connections.
+fn degeneracy_order(neighbours: &[HashSet<usize>]) -> Vec<usize> {
+    let mut degrees: Vec<usize> = neighbours.iter().map(|set| set.len()).collect();
+    let mut queue: BTreeSet<(usize, usize)> = degrees
         .iter()
-        .map(|(from, to)| vec![*from, *to])
-        .collect::<HashSet<_>>();
-    while cliques.len() > 1 {
-        let mut bigger_cliques = HashSet::new();
-
-        for clique in &cliques {
-            let mut intersection = graph[clique[0]].clone();
-            for node in &clique[1..] {
-                intersection = intersection
-                    .intersection(&graph[*node])
-                    .map(|s| *s)
-                    .collect::<HashSet<_>>();
-            }
+        .enumerate()
+        .map(|(node, degree)| (*degree, node))
+        .collect();
+    let mut removed = vec![false; neighbours.len()];
+    let mut order = Vec::with_capacity(neighbours.len());
 
-            for new_node in intersection {
-                let mut new_clique = clique.clone();
-                new_clique.push(new_node);
-                new_clique.sort();
-                bigger_cliques.insert(new_clique);
+    while let Some((_, node)) = queue.pop_first() {
+        removed[node] = true;
+        order.push(node);
+        for other in &neighbours[node] {
+            if !removed[*other] {
+                queue.remove(&(degrees[*other], *other));
+                degrees[*other] -= 1;
+                queue.insert((degrees[*other], *other));
             }
         }
-        cliques = bigger_cliques;
     }
 
-    cliques.into_iter().next().unwrap()
+    order
+}
+
+/// `edges` distinct random connections between `nodes` computers, or every
+/// possible one if there aren't that many.
+fn random_edges(nodes: usize, edges: usize, seed: u64) -> Vec<(String, String)> {
+    let edges = edges.min(nodes * nodes.saturating_sub(1) / 2);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut seen = HashSet::new();
+    while seen.len() < edges {
+        let from = rng.random_range(0..nodes);
+        let to = rng.random_range(0..nodes);
+        if from != to {
+            seen.insert((from.min(to), from.max(to)));
+        }
+    }
+
+    seen.into_iter()
+        .map(|(from, to)| (format!("n{}", from), format!("n{}", to)))
+        .collect()
+}
+
+fn benchmark(nodes: usize) {
+    let names = random_edges(nodes, nodes * 10, 23);
+    let edges: Vec<(&str, &str)> = names
+        .iter()
+        .map(|(from, to)| (from.as_str(), to.as_str()))
+        .collect();
+
+    let time = |name: &str, run: &dyn Fn() -> String| {
+        let before = SystemTime::now();
+        let result = run();
+        let time = SystemTime::now().duration_since(before).unwrap();
+        println!("{}: {} in {}s", name, result, time.as_secs_f32());
+    };
+
+    let network = Network::new(&edges);
+    time("triangles", &|| network.triangles().len().to_string());
+    time("3-cliques", &|| network.count_cliques(3).to_string());
+    time("4-cliques", &|| network.count_cliques(4).to_string());
+    time("maximal cliques", &|| {
+        network.maximal_cliques().len().to_string()
+    });
+    time("maximum clique", &|| {
+        network.maximum_clique().len().to_string()
+    });
 }
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+    if let Some(position) = args.iter().position(|arg| arg == "--bench") {
+        match args.get(position + 1).and_then(|nodes| nodes.parse().ok()) {
+            Some(nodes) => benchmark(nodes),
+            None => println!("Usage: --bench <nodes>"),
+        }
+        return;
+    }
+
     let mut content = String::new();
     File::open("input")
         .unwrap()
         .read_to_string(&mut content)
         .unwrap();
     let edges = parse(&content);
-    let graph = make_graph(&edges);
-    let triplets = find_triplets(&graph, &edges);
-    let t_triplets = triplets
+    let network = Network::new(&edges);
+    let t_triangles = network
+        .triangles()
         .iter()
-        .filter(|(a, b, c)| a.starts_with("t") || b.starts_with("t") || c.starts_with("t"));
-    println!("{}", t_triplets.count() / 3);
-    println!("{}", find_maximum_clique(&graph, &edges).join(","));
+        .filter(|triangle| {
+            triangle
+                .iter()
+                .any(|node| network.names[*node].starts_with("t"))
+        })
+        .count();
+    println!("{}", t_triangles);
+
+    let clique = network.maximum_clique();
+    println!("{}", network.password(&clique));
+    if let Some(position) = args.iter().position(|arg| arg == "--dot") {
+        match args.get(position + 1) {
+            Some(path) => fs::write(path, network.to_dot(&clique)).unwrap(),
+            None => println!("Usage: --dot <path>"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{parse, random_edges, Network};
+
+    #[test]
+    fn test_example() {
+        let content = fs::read_to_string("test").unwrap();
+        let edges = parse(&content);
+        let network = Network::new(&edges);
+
+        let triangles = network.triangles();
+        assert_eq!(triangles.len(), 12);
+        assert_eq!(
+            triangles
+                .iter()
+                .filter(|triangle| triangle
+                    .iter()
+                    .any(|node| network.names[*node].starts_with("t")))
+                .count(),
+            7
+        );
+
+        let clique = network.maximum_clique();
+        assert_eq!(network.password(&clique), "co,de,ka,ta");
+    }
+
+    #[test]
+    fn test_count_cliques() {
+        let content = fs::read_to_string("test").unwrap();
+        let edges = parse(&content);
+        let network = Network::new(&edges);
+
+        assert_eq!(network.count_cliques(1), 16);
+        assert_eq!(network.count_cliques(2), edges.len() as u64);
+        assert_eq!(network.count_cliques(3), 12);
+        assert_eq!(network.count_cliques(4), 1);
+        assert_eq!(network.count_cliques(5), 0);
+    }
+
+    #[test]
+    fn test_complete_graph() {
+        // Asking for more edges than there are pairs gives every pair
+        let names = random_edges(6, 100, 1);
+        assert_eq!(names.len(), 15);
+
+        let edges: Vec<(&str, &str)> = names
+            .iter()
+            .map(|(from, to)| (from.as_str(), to.as_str()))
+            .collect();
+        let network = Network::new(&edges);
+        assert_eq!(network.count_cliques(3), 20);
+        assert_eq!(network.maximal_cliques().len(), 1);
+        assert_eq!(network.maximum_clique().len(), 6);
+    }
 }
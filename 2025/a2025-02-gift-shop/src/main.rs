@@ -1,17 +1,68 @@
-use std::{fs::File, io::Read, ops::RangeInclusive};
+use common::interval::{Interval, IntervalSet};
+use std::{fs::File, io::Read};
 
-fn parse(input: &str) -> Vec<RangeInclusive<u64>> {
+fn parse(input: &str) -> IntervalSet {
     input
+        .trim()
         .split(",")
         .map(|range| {
             let dash_index = range.find('-').unwrap();
             let start = range[..dash_index].parse().unwrap();
             let end = range[dash_index + 1..].parse().unwrap();
-            start..=end
+            Interval::new(start, end)
         })
         .collect()
 }
 
+fn ids(ranges: &IntervalSet) -> impl Iterator<Item = i64> + '_ {
+    ranges
+        .intervals()
+        .iter()
+        .flat_map(|interval| interval.start..=interval.end)
+}
+
+fn is_repeated_twice(id: i64) -> bool {
+    let digits = id.ilog10() + 1;
+    if digits % 2 == 1 {
+        return false;
+    }
+
+    let half_mask = 10i64.pow(digits / 2);
+    id / half_mask == id % half_mask
+}
+
+fn is_repeated(id: i64) -> bool {
+    let id = id.to_string();
+    for sequence_length in 1..=id.len() / 2 {
+        if !id.len().is_multiple_of(sequence_length) {
+            continue;
+        }
+
+        let sequence = &id[..sequence_length];
+        let mut found = true;
+        for start in (sequence_length..id.len()).step_by(sequence_length) {
+            if sequence != &id[start..start + sequence_length] {
+                found = false;
+                break;
+            }
+        }
+
+        if found {
+            return true;
+        }
+    }
+
+    false
+}
+
+fn part_one(ranges: &IntervalSet) -> i64 {
+    ids(ranges).filter(|id| is_repeated_twice(*id)).sum()
+}
+
+fn part_two(ranges: &IntervalSet) -> i64 {
+    ids(ranges).filter(|id| is_repeated(*id)).sum()
+}
+
 fn main() {
     let mut input = String::new();
     File::open("input")
@@ -19,47 +70,29 @@ fn main() {
         .read_to_string(&mut input)
         .unwrap();
     let ranges = parse(&input);
-    let result = ranges
-        .iter()
-        .flat_map(|range| range.clone().into_iter())
-        .filter(|id| {
-            let digits = id.ilog10() + 1;
-            if digits % 2 == 1 {
-                return false;
-            }
 
-            let half_mask = 10u64.pow(digits / 2);
-            return id / half_mask == id % half_mask;
-        })
-        .sum::<u64>();
-    println!("{}", result);
+    println!("{}", part_one(&ranges));
+    println!("{}", part_two(&ranges));
+}
 
-    let result = ranges
-        .iter()
-        .flat_map(|range| range.clone().into_iter())
-        .filter(|id| {
-            let id = id.to_string();
-            for sequence_length in 1..=id.len() / 2 {
-                if id.len() % sequence_length != 0 {
-                    continue;
-                }
-
-                let sequence = &id[..sequence_length];
-                let mut found = true;
-                for start in (sequence_length..id.len()).step_by(sequence_length) {
-                    if sequence != &id[start..start + sequence_length] {
-                        found = false;
-                        break;
-                    }
-                }
-
-                if found {
-                    return true;
-                }
-            }
+#[cfg(test)]
+mod tests {
+    use std::fs;
 
-            false
-        })
-        .sum::<u64>();
-    println!("{}", result);
+    use crate::{parse, part_one, part_two};
+
+    #[test]
+    fn test_example() {
+        let ranges = parse(&fs::read_to_string("test").unwrap());
+
+        assert_eq!(part_one(&ranges), 1227775554);
+        assert_eq!(part_two(&ranges), 4174379265);
+    }
+
+    #[test]
+    fn test_overlapping_ranges() {
+        // Merged ranges count every id once.
+        assert_eq!(part_one(&parse("10-50,20-60")), 11 + 22 + 33 + 44 + 55);
+        assert_eq!(part_two(&parse("100-120,110-115")), 111);
+    }
 }
@@ -0,0 +1,187 @@
+use std::{collections::HashMap, hash::Hash};
+
+/// The shape of a sequence `x0, f(x0), f(f(x0)), ...` that eventually repeats:
+/// the state at index `start` is the first one to come back, `length` steps
+/// later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: u64,
+    pub length: u64,
+}
+
+impl Cycle {
+    /// The smallest index whose state equals the state at index `n`.
+    pub fn equivalent_step(&self, n: u64) -> u64 {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.length
+        }
+    }
+}
+
+/// Finds the cycle with Brent's algorithm, keeping only two states around at a
+/// time. Never returns if the sequence doesn't repeat.
+pub fn find_cycle<S, F>(initial: &S, mut step: F) -> Cycle
+where
+    S: Clone + Eq,
+    F: FnMut(&mut S),
+{
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = initial.clone();
+    step(&mut hare);
+
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        step(&mut hare);
+        length += 1;
+    }
+
+    let mut tortoise = initial.clone();
+    let mut hare = initial.clone();
+    for _ in 0..length {
+        step(&mut hare);
+    }
+
+    let mut start = 0;
+    while tortoise != hare {
+        step(&mut tortoise);
+        step(&mut hare);
+        start += 1;
+    }
+
+    Cycle { start, length }
+}
+
+/// Like `find_cycle`, but remembers every state and gives up once `limit`
+/// steps have passed without any state coming back.
+pub fn find_cycle_within<S, F>(initial: &S, limit: u64, mut step: F) -> Option<Cycle>
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&mut S),
+{
+    let mut seen = HashMap::new();
+    let mut state = initial.clone();
+
+    for i in 0..=limit {
+        if let Some(start) = seen.insert(state.clone(), i) {
+            return Some(Cycle {
+                start,
+                length: i - start,
+            });
+        }
+
+        step(&mut state);
+    }
+
+    None
+}
+
+/// Applies `step` to `initial` `steps` times, remembering every state seen.
+/// As soon as a state repeats, the remaining steps are cut down to whatever is
+/// left over after skipping whole cycles.
+pub fn simulate<S, F>(mut state: S, steps: u64, mut step: F) -> S
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&mut S),
+{
+    let mut seen = HashMap::new();
+
+    for i in 0..steps {
+        if let Some(start) = seen.insert(state.clone(), i) {
+            let remaining = (steps - i) % (i - start);
+            for _ in 0..remaining {
+                step(&mut state);
+            }
+
+            return state;
+        }
+
+        step(&mut state);
+    }
+
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cycle::{find_cycle, find_cycle_within, simulate, Cycle};
+
+    // 0 -> 1 -> 2 -> 3 -> 4 -> 5 -> 6 -> 7 -> 3
+    fn step(x: &mut u64) {
+        *x = if *x == 7 { 3 } else { *x + 1 };
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        assert_eq!(
+            find_cycle(&0, step),
+            Cycle {
+                start: 3,
+                length: 5
+            }
+        );
+        assert_eq!(
+            find_cycle(&5, step),
+            Cycle {
+                start: 0,
+                length: 5
+            }
+        );
+        assert_eq!(
+            find_cycle(&1, |x: &mut u64| *x = 1),
+            Cycle {
+                start: 0,
+                length: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_find_cycle_within() {
+        for initial in 0..8 {
+            assert_eq!(
+                find_cycle_within(&initial, 100, step),
+                Some(find_cycle(&initial, step))
+            );
+        }
+
+        // From 0 the first repeat is 3 coming back at step 8.
+        assert_eq!(find_cycle_within(&0, 7, step), None);
+        assert!(find_cycle_within(&0, 8, step).is_some());
+        assert_eq!(find_cycle_within(&0, 1000, |x: &mut u64| *x += 1), None);
+    }
+
+    #[test]
+    fn test_equivalent_step() {
+        let cycle = Cycle {
+            start: 3,
+            length: 5,
+        };
+
+        assert_eq!(cycle.equivalent_step(2), 2);
+        assert_eq!(cycle.equivalent_step(3), 3);
+        assert_eq!(cycle.equivalent_step(8), 3);
+        assert_eq!(cycle.equivalent_step(1_000_000_000), 5);
+    }
+
+    #[test]
+    fn test_simulate() {
+        for steps in 0..40 {
+            let mut expected = 0;
+            for _ in 0..steps {
+                step(&mut expected);
+            }
+
+            assert_eq!(simulate(0, steps, step), expected);
+        }
+
+        assert_eq!(simulate(0, 1_000_000_000, step), 5);
+    }
+}
@@ -0,0 +1,431 @@
+use std::cmp::Ordering;
+
+/// The integers `start..=end`. Never empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interval {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Interval {
+    pub fn new(start: i64, end: i64) -> Self {
+        assert!(start <= end, "Empty interval {}..={}", start, end);
+        Interval { start, end }
+    }
+
+    /// The number of integers in the interval.
+    pub fn values(&self) -> u64 {
+        (self.end - self.start) as u64 + 1
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        self.start <= value && value <= self.end
+    }
+
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+        (start <= end).then(|| Interval::new(start, end))
+    }
+
+    /// Splits into the values up to `mid` and the values after it.
+    pub fn split(self, mid: i64) -> (Option<Interval>, Option<Interval>) {
+        if self.start > mid {
+            return (None, Some(self));
+        }
+
+        if self.end <= mid {
+            return (Some(self), None);
+        }
+
+        (
+            Some(Interval::new(self.start, mid)),
+            Some(Interval::new(mid + 1, self.end)),
+        )
+    }
+
+    pub fn shift(&self, offset: i64) -> Interval {
+        Interval::new(self.start + offset, self.end + offset)
+    }
+}
+
+/// A set of integers kept as sorted intervals that neither overlap nor touch,
+/// so equal sets always have equal representations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intervals(&self) -> &[Interval] {
+        &self.intervals
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// The number of integers in the set.
+    pub fn len(&self) -> u64 {
+        self.intervals.iter().map(Interval::values).sum()
+    }
+
+    pub fn min(&self) -> Option<i64> {
+        self.intervals.first().map(|interval| interval.start)
+    }
+
+    pub fn max(&self) -> Option<i64> {
+        self.intervals.last().map(|interval| interval.end)
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        self.intervals
+            .binary_search_by(|interval| {
+                if value < interval.start {
+                    Ordering::Greater
+                } else if value > interval.end {
+                    Ordering::Less
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .is_ok()
+    }
+
+    /// Whether every value of `other` is also in the set.
+    pub fn is_superset(&self, other: &IntervalSet) -> bool {
+        other.difference(self).is_empty()
+    }
+
+    pub fn insert(&mut self, interval: Interval) {
+        *self = self.union(&IntervalSet {
+            intervals: vec![interval],
+        });
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        self.intervals
+            .iter()
+            .chain(&other.intervals)
+            .copied()
+            .collect()
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut intervals = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a, b) = (self.intervals[i], other.intervals[j]);
+            if let Some(common) = a.intersection(&b) {
+                intervals.push(common);
+            }
+
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        IntervalSet { intervals }
+    }
+
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut intervals = Vec::new();
+        let mut j = 0;
+        for interval in &self.intervals {
+            let mut rest = Some(*interval);
+            while let Some(current) = rest {
+                // Skip whatever lies completely before the current interval.
+                while j < other.intervals.len() && other.intervals[j].end < current.start {
+                    j += 1;
+                }
+
+                let Some(hole) = other
+                    .intervals
+                    .get(j)
+                    .filter(|hole| hole.start <= current.end)
+                else {
+                    intervals.push(current);
+                    break;
+                };
+
+                if hole.start > current.start {
+                    intervals.push(Interval::new(current.start, hole.start - 1));
+                }
+                rest = (hole.end < current.end).then(|| Interval::new(hole.end + 1, current.end));
+            }
+        }
+
+        IntervalSet { intervals }
+    }
+}
+
+impl FromIterator<Interval> for IntervalSet {
+    /// Sorts the intervals and merges every overlapping or touching pair.
+    fn from_iter<T: IntoIterator<Item = Interval>>(iter: T) -> Self {
+        let mut sorted: Vec<Interval> = iter.into_iter().collect();
+        sorted.sort();
+
+        let mut intervals: Vec<Interval> = Vec::with_capacity(sorted.len());
+        for interval in sorted {
+            match intervals.last_mut() {
+                Some(last) if last.end.saturating_add(1) >= interval.start => {
+                    last.end = last.end.max(interval.end);
+                }
+                _ => intervals.push(interval),
+            }
+        }
+
+        IntervalSet { intervals }
+    }
+}
+
+/// A map on the integers that adds a constant offset on each of its pieces
+/// and leaves every value outside them alone. Pieces are sorted, disjoint and
+/// never have a zero offset, so equal maps compare equal.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PiecewiseMap {
+    pieces: Vec<(Interval, i64)>,
+}
+
+impl PiecewiseMap {
+    pub fn identity() -> Self {
+        Self::default()
+    }
+
+    /// Builds the map from `(interval, offset)` pieces. Where pieces overlap
+    /// the one listed first wins, which is how the puzzle's range lists are
+    /// read.
+    pub fn from_pieces(pieces: impl IntoIterator<Item = (Interval, i64)>) -> Self {
+        let mut covered = IntervalSet::new();
+        let mut result = Vec::new();
+        for (interval, offset) in pieces {
+            let new = IntervalSet::from_iter([interval]).difference(&covered);
+            result.extend(new.intervals().iter().map(|part| (*part, offset)));
+            covered.insert(interval);
+        }
+
+        Self::normalised(result)
+    }
+
+    fn normalised(mut pieces: Vec<(Interval, i64)>) -> Self {
+        pieces.retain(|(_, offset)| *offset != 0);
+        pieces.sort();
+
+        let mut merged: Vec<(Interval, i64)> = Vec::with_capacity(pieces.len());
+        for (interval, offset) in pieces {
+            match merged.last_mut() {
+                Some((last, last_offset))
+                    if *last_offset == offset && last.end + 1 == interval.start =>
+                {
+                    last.end = interval.end;
+                }
+                _ => merged.push((interval, offset)),
+            }
+        }
+
+        Self { pieces: merged }
+    }
+
+    pub fn pieces(&self) -> &[(Interval, i64)] {
+        &self.pieces
+    }
+
+    /// The pieces together with the identity stretches between them, covering
+    /// every integer exactly once.
+    fn segments(&self) -> Vec<(Interval, i64)> {
+        let mut segments = Vec::with_capacity(self.pieces.len() * 2 + 1);
+        let mut next = i64::MIN;
+        for (interval, offset) in &self.pieces {
+            if interval.start > next {
+                segments.push((Interval::new(next, interval.start - 1), 0));
+            }
+            segments.push((*interval, *offset));
+            next = interval.end.saturating_add(1);
+        }
+        if self
+            .pieces
+            .last()
+            .is_none_or(|(last, _)| last.end < i64::MAX)
+        {
+            segments.push((Interval::new(next, i64::MAX), 0));
+        }
+
+        segments
+    }
+
+    pub fn apply(&self, value: i64) -> i64 {
+        let offset = self
+            .pieces
+            .iter()
+            .find(|(interval, _)| interval.contains(value))
+            .map_or(0, |(_, offset)| *offset);
+
+        value + offset
+    }
+
+    /// The image of a whole set of values.
+    pub fn apply_set(&self, set: &IntervalSet) -> IntervalSet {
+        let segments = self.segments();
+        set.intervals()
+            .iter()
+            .flat_map(|interval| {
+                segments.iter().filter_map(|(segment, offset)| {
+                    interval
+                        .intersection(segment)
+                        .map(|common| common.shift(*offset))
+                })
+            })
+            .collect()
+    }
+
+    /// The map that applies `self` and then `next`.
+    pub fn then(&self, next: &PiecewiseMap) -> PiecewiseMap {
+        let next_segments = next.segments();
+        let mut pieces = Vec::new();
+        for (interval, offset) in self.segments() {
+            // The identity stretches are unbounded, so they can't be shifted
+            // without overflowing. Their offset is zero anyway.
+            let image = if offset == 0 {
+                interval
+            } else {
+                interval.shift(offset)
+            };
+
+            for (next_interval, next_offset) in &next_segments {
+                if let Some(common) = image.intersection(next_interval) {
+                    let source = if offset == 0 {
+                        common
+                    } else {
+                        common.shift(-offset)
+                    };
+                    pieces.push((source, offset + next_offset));
+                }
+            }
+        }
+
+        Self::normalised(pieces)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interval::{Interval, IntervalSet, PiecewiseMap};
+
+    fn set(intervals: &[(i64, i64)]) -> IntervalSet {
+        intervals
+            .iter()
+            .map(|(start, end)| Interval::new(*start, *end))
+            .collect()
+    }
+
+    fn brute_force(set: &IntervalSet) -> Vec<i64> {
+        (-5..40).filter(|value| set.contains(*value)).collect()
+    }
+
+    #[test]
+    fn test_split() {
+        let i = Interval::new(5, 10);
+
+        assert_eq!(
+            i.split(7),
+            (Some(Interval::new(5, 7)), Some(Interval::new(8, 10)))
+        );
+        assert_eq!(i.split(4), (None, Some(i)));
+        assert_eq!(i.split(10), (Some(i), None));
+    }
+
+    #[test]
+    fn test_normalise() {
+        let merged = set(&[
+            (0, 5),
+            (2, 8),
+            (8, 10),
+            (11, 14),
+            (16, 17),
+            (18, 20),
+            (30, 40),
+            (31, 35),
+            (36, 39),
+        ]);
+
+        assert_eq!(merged, set(&[(0, 14), (16, 20), (30, 40)]));
+        assert_eq!(merged.len(), 15 + 5 + 11);
+        assert!(merged.contains(14));
+        assert!(!merged.contains(15));
+        assert_eq!(merged.min(), Some(0));
+        assert_eq!(merged.max(), Some(40));
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = set(&[(0, 5), (10, 20), (30, 30)]);
+        let b = set(&[(3, 12), (18, 35)]);
+
+        type Membership = fn(bool, bool) -> bool;
+        let cases: [(IntervalSet, Membership); 3] = [
+            (a.union(&b), |x, y| x || y),
+            (a.intersection(&b), |x, y| x && y),
+            (a.difference(&b), |x, y| x && !y),
+        ];
+        for (result, expected) in cases {
+            let expected: Vec<i64> = (-5..40)
+                .filter(|value| expected(a.contains(*value), b.contains(*value)))
+                .collect();
+            assert_eq!(brute_force(&result), expected);
+        }
+
+        assert_eq!(a.difference(&b), set(&[(0, 2), (13, 17)]));
+        assert!(a.union(&b).is_superset(&a));
+        assert!(!a.is_superset(&b));
+        assert!(a.difference(&a).is_empty());
+    }
+
+    #[test]
+    fn test_piecewise_map() {
+        // seed-to-soil from day 5
+        let map =
+            PiecewiseMap::from_pieces([(Interval::new(98, 99), -48), (Interval::new(50, 97), 2)]);
+
+        assert_eq!(map.apply(98), 50);
+        assert_eq!(map.apply(97), 99);
+        assert_eq!(map.apply(49), 49);
+        assert_eq!(map.apply(100), 100);
+        assert_eq!(map.apply_set(&set(&[(40, 109)])), set(&[(40, 109)]));
+        assert_eq!(map.apply_set(&set(&[(79, 92)])), set(&[(81, 94)]));
+    }
+
+    #[test]
+    fn test_first_piece_wins() {
+        let map =
+            PiecewiseMap::from_pieces([(Interval::new(0, 9), 100), (Interval::new(5, 14), 200)]);
+
+        assert_eq!(map.apply(7), 107);
+        assert_eq!(map.apply(12), 212);
+    }
+
+    #[test]
+    fn test_then() {
+        let first =
+            PiecewiseMap::from_pieces([(Interval::new(0, 9), 10), (Interval::new(20, 24), -20)]);
+        let second =
+            PiecewiseMap::from_pieces([(Interval::new(5, 14), 3), (Interval::new(15, 19), -15)]);
+        let composed = first.then(&second);
+
+        for value in -10..40 {
+            assert_eq!(
+                composed.apply(value),
+                second.apply(first.apply(value)),
+                "{}",
+                value
+            );
+        }
+        assert_eq!(first.then(&PiecewiseMap::identity()), first);
+        assert_eq!(PiecewiseMap::identity().then(&first), first);
+    }
+}
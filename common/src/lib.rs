@@ -0,0 +1,6 @@
+pub mod cycle;
+pub mod interval;
+pub mod polygon;
+pub mod polynomial;
+pub mod rational;
+pub mod visualize;
@@ -0,0 +1,385 @@
+use std::fmt::Display;
+
+use crate::visualize::{Colour, Frame, Recorder};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+/// A lattice point. `y` grows downwards, the same way rows do in the puzzle
+/// grids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Direction {
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Right => Direction::Left,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+        }
+    }
+}
+
+impl Point {
+    pub fn new(x: i64, y: i64) -> Self {
+        Point { x, y }
+    }
+
+    pub fn get_next(&self, direction: Direction, length: i64) -> Point {
+        match direction {
+            Direction::Up => Point::new(self.x, self.y - length),
+            Direction::Right => Point::new(self.x + length, self.y),
+            Direction::Down => Point::new(self.x, self.y + length),
+            Direction::Left => Point::new(self.x - length, self.y),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PolygonError {
+    TooFewVertices,
+    DiagonalEdge(Point, Point),
+    NotClosed(Point),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Winding {
+    Clockwise,
+    CounterClockwise,
+}
+
+/// A closed polygon whose edges are all horizontal or vertical, with vertices
+/// on the integer lattice. The boundary is assumed not to cross itself, which
+/// is what Pick's theorem needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polygon {
+    vertices: Vec<Point>,
+}
+
+impl Polygon {
+    /// Builds a polygon from its vertices in walking order. The last vertex is
+    /// implicitly joined back to the first one.
+    pub fn from_vertices(mut vertices: Vec<Point>) -> Result<Self, PolygonError> {
+        vertices.dedup();
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+
+        if vertices.len() < 4 {
+            return Err(PolygonError::TooFewVertices);
+        }
+
+        for (i, p1) in vertices.iter().enumerate() {
+            let p2 = vertices[(i + 1) % vertices.len()];
+            if p1.x != p2.x && p1.y != p2.y {
+                return Err(PolygonError::DiagonalEdge(*p1, p2));
+            }
+        }
+
+        Ok(Polygon { vertices })
+    }
+
+    /// Walks the steps starting from the origin. The walk has to end back at
+    /// the origin.
+    pub fn from_steps<I>(steps: I) -> Result<Self, PolygonError>
+    where
+        I: IntoIterator<Item = (Direction, i64)>,
+    {
+        let origin = Point::new(0, 0);
+        let mut vertices = vec![origin];
+        for (direction, length) in steps {
+            let next = vertices.last().unwrap().get_next(direction, length);
+            vertices.push(next);
+        }
+
+        let end = vertices.pop().unwrap();
+        if end != origin {
+            return Err(PolygonError::NotClosed(end));
+        }
+
+        Polygon::from_vertices(vertices)
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        self.vertices
+            .iter()
+            .enumerate()
+            .map(|(i, p1)| (*p1, self.vertices[(i + 1) % self.vertices.len()]))
+    }
+
+    /// Shoelace sum, positive when the walk is clockwise on screen.
+    fn double_signed_area(&self) -> i64 {
+        self.edges().map(|(p1, p2)| p1.x * p2.y - p1.y * p2.x).sum()
+    }
+
+    /// Geometric area enclosed by the boundary, treating vertices as points.
+    pub fn area(&self) -> i64 {
+        self.double_signed_area().abs() / 2
+    }
+
+    /// Length of the boundary, which is also the number of lattice points on
+    /// it.
+    pub fn perimeter(&self) -> i64 {
+        self.edges()
+            .map(|(p1, p2)| (p1.x - p2.x).abs() + (p1.y - p2.y).abs())
+            .sum()
+    }
+
+    /// Lattice points strictly inside the boundary, by Pick's theorem.
+    pub fn interior_points(&self) -> i64 {
+        self.area() - self.perimeter() / 2 + 1
+    }
+
+    /// Lattice points inside or on the boundary, i.e. the number of unit cells
+    /// covered when every lattice point is a cell.
+    pub fn lattice_points(&self) -> i64 {
+        self.interior_points() + self.perimeter()
+    }
+
+    pub fn winding(&self) -> Winding {
+        if self.double_signed_area() >= 0 {
+            Winding::Clockwise
+        } else {
+            Winding::CounterClockwise
+        }
+    }
+
+    pub fn on_boundary(&self, point: Point) -> bool {
+        self.edges().any(|(p1, p2)| {
+            point.x >= p1.x.min(p2.x)
+                && point.x <= p1.x.max(p2.x)
+                && point.y >= p1.y.min(p2.y)
+                && point.y <= p1.y.max(p2.y)
+        })
+    }
+
+    /// How many times the boundary winds around `point`, counted positive for
+    /// clockwise turns. Points on the boundary return 0.
+    pub fn winding_number(&self, point: Point) -> i64 {
+        if self.on_boundary(point) {
+            return 0;
+        }
+
+        self.edges()
+            .filter(|(p1, p2)| p1.x == p2.x && p1.x > point.x)
+            .map(|(p1, p2)| {
+                if p1.y <= point.y && point.y < p2.y {
+                    1
+                } else if p2.y <= point.y && point.y < p1.y {
+                    -1
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.on_boundary(point) || self.winding_number(point) != 0
+    }
+
+    fn bounds(&self) -> (Point, Point) {
+        let min = Point::new(
+            self.vertices.iter().map(|p| p.x).min().unwrap(),
+            self.vertices.iter().map(|p| p.y).min().unwrap(),
+        );
+        let max = Point::new(
+            self.vertices.iter().map(|p| p.x).max().unwrap(),
+            self.vertices.iter().map(|p| p.y).max().unwrap(),
+        );
+
+        (min, max)
+    }
+
+    /// A blank frame covering the bounding box, and the corner it starts at.
+    fn blank_frame(&self) -> (Frame, Point) {
+        let (min, max) = self.bounds();
+        let mut frame = Frame::new((max.x - min.x + 1) as usize, (max.y - min.y + 1) as usize);
+        frame.fill('.', Colour::BLACK);
+
+        (frame, min)
+    }
+
+    /// Draws the bounding box with `#` on the boundary, `I` inside and `.`
+    /// outside. Every point is tested separately, so this is only meant for
+    /// polygons of puzzle-map size.
+    pub fn frame(&self) -> Frame {
+        let (mut frame, min) = self.blank_frame();
+        for y in 0..frame.height() {
+            for x in 0..frame.width() {
+                let point = Point::new(min.x + x as i64, min.y + y as i64);
+                if self.on_boundary(point) {
+                    frame.set(x, y, '#', Colour::ORANGE);
+                } else if self.winding_number(point) != 0 {
+                    frame.set(x, y, 'I', Colour::RED);
+                }
+            }
+        }
+
+        frame
+    }
+
+    /// Records the boundary being walked one lattice point at a time from the
+    /// first vertex, in at most `max_frames` frames, followed by `frame`.
+    pub fn record_trace(&self, recorder: &mut Recorder, max_frames: usize) {
+        let (mut frame, min) = self.blank_frame();
+        let every = (self.perimeter() as usize)
+            .div_ceil(max_frames.max(1))
+            .max(1);
+
+        let mut walked = 0;
+        for (from, to) in self.edges() {
+            let (dx, dy) = ((to.x - from.x).signum(), (to.y - from.y).signum());
+            let mut point = from;
+            while point != to {
+                point = Point::new(point.x + dx, point.y + dy);
+                frame.set(
+                    (point.x - min.x) as usize,
+                    (point.y - min.y) as usize,
+                    '#',
+                    Colour::ORANGE,
+                );
+                walked += 1;
+                if walked % every == 0 {
+                    recorder.push(frame.clone());
+                }
+            }
+        }
+
+        recorder.push(self.frame());
+    }
+}
+
+/// The same drawing as `frame`, without the colours.
+impl Display for Polygon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.frame())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        polygon::{Direction, Point, Polygon, PolygonError, Winding},
+        visualize::Recorder,
+    };
+
+    fn example() -> Polygon {
+        Polygon::from_steps([
+            (Direction::Right, 6),
+            (Direction::Down, 5),
+            (Direction::Left, 2),
+            (Direction::Down, 2),
+            (Direction::Right, 2),
+            (Direction::Down, 2),
+            (Direction::Left, 5),
+            (Direction::Up, 2),
+            (Direction::Left, 1),
+            (Direction::Up, 2),
+            (Direction::Right, 2),
+            (Direction::Up, 3),
+            (Direction::Left, 2),
+            (Direction::Up, 2),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_measures_dig_plan() {
+        let polygon = example();
+
+        assert_eq!(polygon.perimeter(), 38);
+        assert_eq!(polygon.area(), 42);
+        assert_eq!(polygon.interior_points(), 24);
+        assert_eq!(polygon.lattice_points(), 62);
+        assert_eq!(polygon.winding(), Winding::Clockwise);
+    }
+
+    #[test]
+    fn test_winding_follows_walk_direction() {
+        let square = Polygon::from_vertices(vec![
+            Point::new(0, 0),
+            Point::new(0, 4),
+            Point::new(4, 4),
+            Point::new(4, 0),
+        ])
+        .unwrap();
+
+        assert_eq!(square.winding(), Winding::CounterClockwise);
+        assert_eq!(square.winding_number(Point::new(2, 2)), -1);
+        assert_eq!(square.winding_number(Point::new(5, 2)), 0);
+        assert_eq!(square.winding_number(Point::new(0, 2)), 0);
+        assert!(square.contains(Point::new(0, 2)));
+        assert!(!square.contains(Point::new(-1, 2)));
+        assert_eq!(square.interior_points(), 9);
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        assert_eq!(
+            Polygon::from_steps([(Direction::Right, 2), (Direction::Down, 2)]),
+            Err(PolygonError::NotClosed(Point::new(2, 2)))
+        );
+        assert_eq!(
+            Polygon::from_vertices(vec![
+                Point::new(0, 0),
+                Point::new(2, 0),
+                Point::new(2, 2),
+                Point::new(1, 3),
+            ]),
+            Err(PolygonError::DiagonalEdge(
+                Point::new(2, 2),
+                Point::new(1, 3)
+            ))
+        );
+        assert_eq!(
+            Polygon::from_vertices(vec![Point::new(0, 0), Point::new(2, 0)]),
+            Err(PolygonError::TooFewVertices)
+        );
+    }
+
+    #[test]
+    fn test_renders_ascii() {
+        assert_eq!(
+            example().to_string(),
+            "#######
+#IIIII#
+###III#
+..#III#
+..#III#
+###I###
+#III#..
+##II###
+.#IIII#
+.######
+"
+        );
+    }
+
+    #[test]
+    fn test_records_trace() {
+        let polygon = example();
+        let mut recorder = Recorder::new();
+        polygon.record_trace(&mut recorder, 10);
+
+        // A perimeter of 38 in steps of 4, then the filled drawing
+        assert_eq!(recorder.len(), 10);
+        let frames = recorder.frames();
+        assert_eq!(frames[0].to_string().matches('#').count(), 4);
+        assert_eq!(frames[8].to_string().matches('#').count(), 36);
+        assert_eq!(frames[9], polygon.frame());
+    }
+}
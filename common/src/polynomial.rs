@@ -0,0 +1,194 @@
+use crate::rational::Rational;
+
+/// The samples don't settle into a polynomial before they run out.
+#[derive(Debug, PartialEq, Eq)]
+pub struct NotPolynomial;
+
+/// The polynomial of lowest degree through samples taken at `x = 0, 1, 2, ...`,
+/// in Newton's forward-difference form: `p(x) = sum of Δ^k p(0) * C(x, k)`.
+/// Integer samples give integer differences, and `C(x, k)` is an integer for
+/// every integer `x`, so evaluating it never leaves the integers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial {
+    differences: Vec<i128>,
+    samples: usize,
+}
+
+impl Polynomial {
+    /// Builds the difference table down to the first row that is all zeros.
+    /// `n` samples always fit a polynomial of degree `n - 1`, so this never
+    /// fails; see `confirmations` for how much the samples back it up.
+    pub fn fit(samples: &[i128]) -> Self {
+        let mut differences = Vec::new();
+        let mut row = samples.to_vec();
+        while row.iter().any(|value| *value != 0) {
+            differences.push(row[0]);
+            row = row.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        }
+
+        Polynomial {
+            differences,
+            samples: samples.len(),
+        }
+    }
+
+    /// Fits the samples and insists that at least `confirmations` of them
+    /// were not needed to pin the polynomial down.
+    pub fn confirmed(samples: &[i128], confirmations: usize) -> Result<Self, NotPolynomial> {
+        let polynomial = Polynomial::fit(samples);
+        if polynomial.confirmations() < confirmations {
+            return Err(NotPolynomial);
+        }
+
+        Ok(polynomial)
+    }
+
+    /// The degree, counting the zero polynomial as degree 0.
+    pub fn degree(&self) -> usize {
+        self.differences.len().saturating_sub(1)
+    }
+
+    /// How many samples there were beyond the `degree + 1` any polynomial of
+    /// that degree needs. With none to spare, the samples could be anything.
+    pub fn confirmations(&self) -> usize {
+        self.samples.saturating_sub(self.differences.len())
+    }
+
+    pub fn evaluate(&self, x: i128) -> i128 {
+        let mut binomial = 1;
+        let mut value = 0;
+        for (k, difference) in self.differences.iter().enumerate() {
+            if k > 0 {
+                // C(x, k) = C(x, k - 1) * (x - k + 1) / k, always exact
+                binomial = binomial * (x - k as i128 + 1) / k as i128;
+            }
+            value += difference * binomial;
+        }
+
+        value
+    }
+
+    /// The value `k` steps after the last sample.
+    pub fn forward(&self, k: usize) -> i128 {
+        self.evaluate(self.samples as i128 - 1 + k as i128)
+    }
+
+    /// The value `k` steps before the first sample.
+    pub fn backward(&self, k: usize) -> i128 {
+        self.evaluate(-(k as i128))
+    }
+
+    /// The coefficients of `1, x, x^2, ...`, which is what Lagrange
+    /// interpolation through the samples would give. Each `C(x, k)` expands
+    /// into the falling factorial `x (x - 1) ... (x - k + 1)` over `k!`; the
+    /// numerators get large quickly, so this is only meant for the low
+    /// degrees puzzle growth curves have.
+    pub fn coefficients(&self) -> Vec<Rational> {
+        let degree = self.degree();
+        let denominator: i128 = (1..=degree as i128).product();
+        let mut numerators = vec![0; degree + 1];
+        let mut falling = vec![1];
+        let mut factorial = 1;
+
+        for (k, difference) in self.differences.iter().enumerate() {
+            if k > 0 {
+                factorial *= k as i128;
+                let mut next = vec![0; falling.len() + 1];
+                for (power, coefficient) in falling.iter().enumerate() {
+                    next[power + 1] += coefficient;
+                    next[power] -= coefficient * (k as i128 - 1);
+                }
+                falling = next;
+            }
+
+            for (power, coefficient) in falling.iter().enumerate() {
+                numerators[power] += difference * coefficient * (denominator / factorial);
+            }
+        }
+
+        numerators
+            .iter()
+            .map(|numerator| Rational::new(*numerator, denominator))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        polynomial::{NotPolynomial, Polynomial},
+        rational::Rational,
+    };
+
+    fn rationals(values: &[(i128, i128)]) -> Vec<Rational> {
+        values
+            .iter()
+            .map(|(numerator, denominator)| Rational::new(*numerator, *denominator))
+            .collect()
+    }
+
+    #[test]
+    fn test_fit() {
+        let polynomial = Polynomial::fit(&[1, 3, 6, 10, 15, 21]);
+
+        assert_eq!(polynomial.degree(), 2);
+        assert_eq!(polynomial.confirmations(), 3);
+        assert_eq!(polynomial.forward(1), 28);
+        assert_eq!(polynomial.backward(1), 0);
+        assert_eq!(polynomial.backward(3), 1);
+        assert_eq!(polynomial.evaluate(99), 5050);
+    }
+
+    #[test]
+    fn test_constant_and_zero() {
+        let constant = Polynomial::fit(&[4, 4, 4]);
+        assert_eq!(constant.degree(), 0);
+        assert_eq!(constant.forward(10), 4);
+        assert_eq!(constant.backward(10), 4);
+
+        let zero = Polynomial::fit(&[0, 0]);
+        assert_eq!(zero.degree(), 0);
+        assert_eq!(zero.evaluate(-7), 0);
+        assert_eq!(zero.coefficients(), rationals(&[(0, 1)]));
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        // 2x^3 - 5x^2 + x - 7, checked well outside the samples
+        let p = |x: i128| 2 * x * x * x - 5 * x * x + x - 7;
+        let samples: Vec<i128> = (0..8).map(p).collect();
+        let polynomial = Polynomial::fit(&samples);
+
+        assert_eq!(polynomial.degree(), 3);
+        for x in -50..50 {
+            assert_eq!(polynomial.evaluate(x), p(x), "{}", x);
+        }
+        assert_eq!(
+            polynomial.coefficients(),
+            rationals(&[(-7, 1), (1, 1), (-5, 1), (2, 1)])
+        );
+    }
+
+    #[test]
+    fn test_rational_coefficients() {
+        // The triangular numbers are x^2 / 2 + 3x / 2 + 1 from 1
+        let polynomial = Polynomial::fit(&[1, 3, 6, 10, 15, 21]);
+
+        assert_eq!(
+            polynomial.coefficients(),
+            rationals(&[(1, 1), (3, 2), (1, 2)])
+        );
+        assert_eq!(polynomial.coefficients()[1].to_string(), "3/2");
+    }
+
+    #[test]
+    fn test_not_polynomial() {
+        let powers = [1, 2, 4, 8, 16, 32];
+
+        assert_eq!(Polynomial::fit(&powers).degree(), 5);
+        assert_eq!(Polynomial::fit(&powers).confirmations(), 0);
+        assert_eq!(Polynomial::confirmed(&powers, 1), Err(NotPolynomial));
+        assert!(Polynomial::confirmed(&[0, 3, 6, 9], 2).is_ok());
+        assert_eq!(Polynomial::confirmed(&[0, 3, 6, 9], 3), Err(NotPolynomial));
+    }
+}
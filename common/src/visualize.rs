@@ -0,0 +1,635 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt::{self, Display},
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Colour {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Colour {
+    pub const BLACK: Colour = Colour::new(0, 0, 0);
+    pub const WHITE: Colour = Colour::new(255, 255, 255);
+    pub const GREY: Colour = Colour::new(110, 110, 110);
+    pub const RED: Colour = Colour::new(220, 50, 47);
+    pub const GREEN: Colour = Colour::new(80, 200, 80);
+    pub const BLUE: Colour = Colour::new(60, 120, 230);
+    pub const YELLOW: Colour = Colour::new(240, 200, 40);
+    pub const ORANGE: Colour = Colour::new(240, 130, 30);
+    pub const CYAN: Colour = Colour::new(40, 200, 200);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Colour { r, g, b }
+    }
+}
+
+/// What one cell of a frame shows: a character for the terminal and a colour
+/// for both the terminal and the images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyph {
+    pub symbol: char,
+    pub colour: Colour,
+}
+
+impl Glyph {
+    pub fn new(symbol: char, colour: Colour) -> Self {
+        Glyph { symbol, colour }
+    }
+}
+
+/// One picture of a grid, `x` going right and `y` going down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    cells: Vec<Glyph>,
+}
+
+impl Frame {
+    /// A frame of blank black cells.
+    pub fn new(width: usize, height: usize) -> Self {
+        Frame {
+            width,
+            height,
+            cells: vec![Glyph::new(' ', Colour::BLACK); width * height],
+        }
+    }
+
+    /// Colours every character of a grid with `palette`. Rows shorter than
+    /// the longest one are padded with blanks.
+    pub fn from_chars<F>(rows: &[Vec<char>], palette: F) -> Self
+    where
+        F: Fn(char) -> Colour,
+    {
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut frame = Frame::new(width, rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, symbol) in row.iter().enumerate() {
+                frame.set(x, y, *symbol, palette(*symbol));
+            }
+        }
+
+        frame
+    }
+
+    /// The same as `from_chars`, for a grid that is already drawn as text.
+    pub fn from_text<F>(text: &str, palette: F) -> Self
+    where
+        F: Fn(char) -> Colour,
+    {
+        let rows: Vec<Vec<char>> = text.lines().map(|line| line.chars().collect()).collect();
+
+        Frame::from_chars(&rows, palette)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Glyph {
+        self.cells[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, symbol: char, colour: Colour) {
+        self.cells[y * self.width + x] = Glyph::new(symbol, colour);
+    }
+
+    /// Sets every cell to the same glyph, to draw on top of.
+    pub fn fill(&mut self, symbol: char, colour: Colour) {
+        self.cells.fill(Glyph::new(symbol, colour));
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[Glyph]> {
+        self.cells.chunks(self.width.max(1)).take(self.height)
+    }
+
+    /// The frame with 24-bit ANSI colours, switching colour only where it
+    /// changes.
+    pub fn to_ansi(&self) -> String {
+        let mut result = String::new();
+        for row in self.rows() {
+            let mut current = None;
+            for glyph in row {
+                if current != Some(glyph.colour) {
+                    let Colour { r, g, b } = glyph.colour;
+                    result.push_str(&format!("\x1b[38;2;{};{};{}m", r, g, b));
+                    current = Some(glyph.colour);
+                }
+                result.push(glyph.symbol);
+            }
+            result.push_str("\x1b[0m\n");
+        }
+
+        result
+    }
+
+    /// The frame as a binary PPM image with every cell a `scale` by `scale`
+    /// square of its colour.
+    pub fn to_ppm(&self, scale: usize) -> Vec<u8> {
+        let mut result =
+            format!("P6\n{} {}\n255\n", self.width * scale, self.height * scale).into_bytes();
+        for row in self.rows() {
+            for _ in 0..scale {
+                for glyph in row {
+                    for _ in 0..scale {
+                        result.extend([glyph.colour.r, glyph.colour.g, glyph.colour.b]);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// The characters alone, one line per row.
+impl Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            let line: String = row.iter().map(|glyph| glyph.symbol).collect();
+            writeln!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Where `--visualize` sends the frames. Without a path they are replayed in
+/// the terminal; a path ending in `.gif` gets an animation and any other path
+/// is a directory for one PPM image per frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    Terminal,
+    Gif(PathBuf),
+    Ppm(PathBuf),
+}
+
+impl Output {
+    /// Reads `--visualize [path]` from the command line arguments, if it is
+    /// there.
+    pub fn from_args(args: &[String]) -> Option<Output> {
+        let position = args.iter().position(|arg| arg == "--visualize")?;
+        let output = match args.get(position + 1) {
+            Some(path) if !path.starts_with("--") => {
+                let path = PathBuf::from(path);
+                if path.extension().is_some_and(|extension| extension == "gif") {
+                    Output::Gif(path)
+                } else {
+                    Output::Ppm(path)
+                }
+            }
+            _ => Output::Terminal,
+        };
+
+        Some(output)
+    }
+
+    /// The same output for one of several recordings, told apart by `name`:
+    /// `out.gif` becomes `out_name.gif` and a directory gets a `name`
+    /// subdirectory.
+    pub fn named(&self, name: &str) -> Output {
+        match self {
+            Output::Terminal => Output::Terminal,
+            Output::Gif(path) => {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                Output::Gif(path.with_file_name(format!("{}_{}.gif", stem, name)))
+            }
+            Output::Ppm(directory) => Output::Ppm(directory.join(name)),
+        }
+    }
+}
+
+/// Collects the frames of a simulation to play them back afterwards.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    frames: Vec<Frame>,
+    delay: Duration,
+    scale: usize,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Recorder::new()
+    }
+}
+
+impl Recorder {
+    /// A recorder showing 10 frames a second, drawing each cell as a 4 by 4
+    /// square in images.
+    pub fn new() -> Self {
+        Recorder {
+            frames: Vec::new(),
+            delay: Duration::from_millis(100),
+            scale: 4,
+        }
+    }
+
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: usize) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn push(&mut self, frame: Frame) {
+        self.frames.push(frame);
+    }
+
+    /// Drops the frames, keeping the settings for the next recording.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Redraws every frame in place, waiting the delay in between.
+    pub fn replay(&self) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        for (i, frame) in self.frames.iter().enumerate() {
+            writeln!(
+                stdout,
+                "\x1b[H\x1b[2J{}frame {}/{}",
+                frame.to_ansi(),
+                i + 1,
+                self.frames.len()
+            )?;
+            stdout.flush()?;
+            thread::sleep(self.delay);
+        }
+
+        Ok(())
+    }
+
+    /// Writes `frame_00000.ppm`, `frame_00001.ppm`, ... into `directory`.
+    pub fn write_ppm(&self, directory: &Path) -> io::Result<()> {
+        fs::create_dir_all(directory)?;
+        for (i, frame) in self.frames.iter().enumerate() {
+            fs::write(
+                directory.join(format!("frame_{:05}.ppm", i)),
+                frame.to_ppm(self.scale),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the frames as a looping GIF. All the frames have to be the
+    /// size of the first one and use at most 256 colours between them.
+    pub fn write_gif(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_gif()?)
+    }
+
+    pub fn to_gif(&self) -> io::Result<Vec<u8>> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, message);
+        let first = self.frames.first().ok_or_else(|| invalid("No frames"))?;
+        if self
+            .frames
+            .iter()
+            .any(|frame| frame.width != first.width || frame.height != first.height)
+        {
+            return Err(invalid("Frames differ in size"));
+        }
+
+        let mut palette: Vec<Colour> = Vec::new();
+        let mut indexes: HashMap<Colour, u8> = HashMap::new();
+        for glyph in self.frames.iter().flat_map(|frame| &frame.cells) {
+            if let Entry::Vacant(entry) = indexes.entry(glyph.colour) {
+                if palette.len() == 256 {
+                    return Err(invalid("More than 256 colours"));
+                }
+                entry.insert(palette.len() as u8);
+                palette.push(glyph.colour);
+            }
+        }
+
+        // The colour table has to hold a power of two colours, at least 4 so
+        // the LZW codes start at 3 bits.
+        let bits = (usize::BITS - (palette.len().max(4) - 1).leading_zeros()) as u8;
+        palette.resize(1 << bits, Colour::BLACK);
+
+        let width = (first.width * self.scale) as u16;
+        let height = (first.height * self.scale) as u16;
+        let delay = (self.delay.as_millis() / 10) as u16;
+
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend(width.to_le_bytes());
+        gif.extend(height.to_le_bytes());
+        gif.extend([0xf0 | (bits - 1), 0, 0]);
+        for colour in &palette {
+            gif.extend([colour.r, colour.g, colour.b]);
+        }
+        // Loop forever
+        gif.extend(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+        for frame in &self.frames {
+            gif.extend([0x21, 0xf9, 0x04, 0x00]);
+            gif.extend(delay.to_le_bytes());
+            gif.extend([0x00, 0x00]);
+
+            gif.push(0x2c);
+            gif.extend(0u16.to_le_bytes());
+            gif.extend(0u16.to_le_bytes());
+            gif.extend(width.to_le_bytes());
+            gif.extend(height.to_le_bytes());
+            gif.push(0);
+
+            let mut pixels = Vec::with_capacity(width as usize * height as usize);
+            for row in frame.rows() {
+                for _ in 0..self.scale {
+                    for glyph in row {
+                        let index = indexes[&glyph.colour];
+                        pixels.extend(std::iter::repeat_n(index, self.scale));
+                    }
+                }
+            }
+
+            gif.push(bits);
+            for block in lzw_encode(&pixels, bits).chunks(255) {
+                gif.push(block.len() as u8);
+                gif.extend(block);
+            }
+            gif.push(0);
+        }
+        gif.push(0x3b);
+
+        Ok(gif)
+    }
+
+    /// Sends the frames wherever `output` says.
+    pub fn save(&self, output: &Output) -> io::Result<()> {
+        match output {
+            Output::Terminal => self.replay(),
+            Output::Gif(path) => self.write_gif(path),
+            Output::Ppm(directory) => self.write_ppm(directory),
+        }
+    }
+}
+
+/// GIF's variable-width LZW: codes start one bit wider than the pixel
+/// indexes, grow up to 12 bits and are packed least significant bit first.
+/// The table starts over with a clear code once it is full.
+fn lzw_encode(pixels: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut output = Vec::new();
+    let mut buffer = 0u32;
+    let mut buffered = 0;
+    let mut emit = |code: u16, width: u8, output: &mut Vec<u8>| {
+        buffer |= (code as u32) << buffered;
+        buffered += width;
+        while buffered >= 8 {
+            output.push(buffer as u8);
+            buffer >>= 8;
+            buffered -= 8;
+        }
+    };
+
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut width = min_code_size + 1;
+    emit(clear, width, &mut output);
+
+    let mut current: Option<u16> = None;
+    for pixel in pixels {
+        let Some(prefix) = current else {
+            current = Some(*pixel as u16);
+            continue;
+        };
+
+        if let Some(code) = table.get(&(prefix, *pixel)) {
+            current = Some(*code);
+            continue;
+        }
+
+        emit(prefix, width, &mut output);
+        if next == 4096 {
+            emit(clear, width, &mut output);
+            table.clear();
+            next = end + 1;
+            width = min_code_size + 1;
+        } else {
+            table.insert((prefix, *pixel), next);
+            if next == 1 << width && width < 12 {
+                width += 1;
+            }
+            next += 1;
+        }
+        current = Some(*pixel as u16);
+    }
+
+    if let Some(code) = current {
+        emit(code, width, &mut output);
+    }
+    emit(end, width, &mut output);
+    if buffered > 0 {
+        output.push(buffer as u8);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::visualize::{lzw_encode, Colour, Frame, Output, Recorder};
+
+    /// Reads the codes back the way a GIF decoder does.
+    fn lzw_decode(data: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let end = clear + 1;
+        let reset = || -> Vec<Vec<u8>> {
+            let mut table: Vec<Vec<u8>> = (0..clear).map(|i| vec![i as u8]).collect();
+            table.push(Vec::new());
+            table.push(Vec::new());
+            table
+        };
+
+        let mut table = reset();
+        let mut width = min_code_size + 1;
+        let mut output = Vec::new();
+        let mut previous: Option<Vec<u8>> = None;
+        let (mut buffer, mut buffered, mut position) = (0u32, 0u8, 0);
+
+        loop {
+            while buffered < width {
+                buffer |= (data[position] as u32) << buffered;
+                position += 1;
+                buffered += 8;
+            }
+            let code = (buffer & ((1 << width) - 1)) as usize;
+            buffer >>= width;
+            buffered -= width;
+
+            if code == clear {
+                table = reset();
+                width = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return output;
+            }
+
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => {
+                    let mut entry = previous.clone();
+                    entry.push(previous[0]);
+                    entry
+                }
+                (None, None) => panic!("Unknown first code {}", code),
+            };
+            output.extend(&entry);
+
+            if let Some(mut previous) = previous {
+                if table.len() < 4096 {
+                    previous.push(entry[0]);
+                    table.push(previous);
+                    if table.len() == 1 << width && width < 12 {
+                        width += 1;
+                    }
+                }
+            }
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn test_lzw_round_trip() {
+        let mut seed = 1u32;
+        let noise: Vec<u8> = (0..20000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                (seed >> 16) as u8 % 5
+            })
+            .collect();
+        let runs: Vec<u8> = (0..20000).map(|i| (i / 37 % 3) as u8).collect();
+
+        for pixels in [noise, runs, vec![0], vec![1, 1, 1, 1, 1, 1, 1]] {
+            assert_eq!(lzw_decode(&lzw_encode(&pixels, 3), 3), pixels);
+        }
+
+        let bytes: Vec<u8> = (0..50000u32).map(|i| (i * i / 7) as u8).collect();
+        assert_eq!(lzw_decode(&lzw_encode(&bytes, 8), 8), bytes);
+    }
+
+    fn example() -> Frame {
+        Frame::from_text("#.\n.@", |symbol| match symbol {
+            '#' => Colour::WHITE,
+            '@' => Colour::RED,
+            _ => Colour::BLACK,
+        })
+    }
+
+    #[test]
+    fn test_frame() {
+        let frame = example();
+
+        assert_eq!(frame.width(), 2);
+        assert_eq!(frame.height(), 2);
+        assert_eq!(frame.get(1, 1).colour, Colour::RED);
+        assert_eq!(frame.to_string(), "#.\n.@\n");
+        assert_eq!(
+            frame.to_ansi(),
+            "\x1b[38;2;255;255;255m#\x1b[38;2;0;0;0m.\x1b[0m\n\
+             \x1b[38;2;0;0;0m.\x1b[38;2;220;50;47m@\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_ppm() {
+        let ppm = example().to_ppm(2);
+        let header = b"P6\n4 4\n255\n";
+
+        assert_eq!(&ppm[..header.len()], header);
+        assert_eq!(ppm.len(), header.len() + 4 * 4 * 3);
+        // The second row of pixels still belongs to the first row of cells
+        let second_row = header.len() + 4 * 3;
+        assert_eq!(&ppm[second_row..second_row + 3], &[255, 255, 255]);
+        assert_eq!(&ppm[second_row + 6..second_row + 9], &[0, 0, 0]);
+    }
+
+    #[test]
+    fn test_gif() {
+        let mut recorder = Recorder::new().with_scale(3);
+        recorder.push(example());
+        let mut second = example();
+        second.set(0, 1, '@', Colour::RED);
+        recorder.push(second);
+
+        let gif = recorder.to_gif().unwrap();
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(&gif[6..10], &[6, 0, 6, 0]);
+        assert_eq!(*gif.last().unwrap(), 0x3b);
+
+        let mut too_big = Recorder::new();
+        too_big.push(example());
+        too_big.push(Frame::new(3, 3));
+        assert!(too_big.to_gif().is_err());
+        assert!(Recorder::new().to_gif().is_err());
+    }
+
+    #[test]
+    fn test_output_from_args() {
+        let args =
+            |args: &[&str]| -> Vec<String> { args.iter().map(|arg| arg.to_string()).collect() };
+
+        assert_eq!(Output::from_args(&args(&["day"])), None);
+        assert_eq!(
+            Output::from_args(&args(&["day", "--visualize"])),
+            Some(Output::Terminal)
+        );
+        assert_eq!(
+            Output::from_args(&args(&["day", "--visualize", "--fast"])),
+            Some(Output::Terminal)
+        );
+        assert_eq!(
+            Output::from_args(&args(&["day", "--visualize", "out.gif"])),
+            Some(Output::Gif(PathBuf::from("out.gif")))
+        );
+        assert_eq!(
+            Output::from_args(&args(&["day", "--visualize", "frames"])),
+            Some(Output::Ppm(PathBuf::from("frames")))
+        );
+    }
+
+    #[test]
+    fn test_output_named() {
+        assert_eq!(Output::Terminal.named("part1"), Output::Terminal);
+        assert_eq!(
+            Output::Gif(PathBuf::from("runs/out.gif")).named("part1"),
+            Output::Gif(PathBuf::from("runs/out_part1.gif"))
+        );
+        assert_eq!(
+            Output::Ppm(PathBuf::from("frames")).named("part1"),
+            Output::Ppm(PathBuf::from("frames/part1"))
+        );
+    }
+}